@@ -0,0 +1,18 @@
+-- Per-user unit preference
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS unit_system TEXT NOT NULL DEFAULT 'kg' CHECK (unit_system IN ('kg', 'lb')),
+ADD COLUMN IF NOT EXISTS current_weight_entered DOUBLE PRECISION,
+ADD COLUMN IF NOT EXISTS current_weight_unit TEXT CHECK (current_weight_unit IN ('kg', 'lb'));
+
+-- Keep the value as originally entered next to the canonical kg column
+ALTER TABLE sets
+ADD COLUMN IF NOT EXISTS entered_weight DOUBLE PRECISION,
+ADD COLUMN IF NOT EXISTS entered_unit TEXT CHECK (entered_unit IN ('kg', 'lb'));
+
+ALTER TABLE template_exercises
+ADD COLUMN IF NOT EXISTS target_weight_entered DOUBLE PRECISION,
+ADD COLUMN IF NOT EXISTS target_weight_unit TEXT CHECK (target_weight_unit IN ('kg', 'lb'));
+
+ALTER TABLE weight_logs
+ADD COLUMN IF NOT EXISTS entered_weight DOUBLE PRECISION,
+ADD COLUMN IF NOT EXISTS entered_unit TEXT CHECK (entered_unit IN ('kg', 'lb'));
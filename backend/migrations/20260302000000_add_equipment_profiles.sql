@@ -0,0 +1,9 @@
+-- Per-user equipment used for plate math. Weights are in `unit`.
+CREATE TABLE IF NOT EXISTS equipment_profiles (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    unit TEXT NOT NULL DEFAULT 'kg' CHECK (unit IN ('kg', 'lb')),
+    bar_weight DOUBLE PRECISION NOT NULL DEFAULT 20,
+    collar_weight DOUBLE PRECISION NOT NULL DEFAULT 0, -- Per collar
+    plates JSONB NOT NULL DEFAULT '[]', -- [{"weight": 20, "pairs": null}, ...]
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,3 @@
+-- Distinguish warm-up sets, which don't count towards PRs or volume
+ALTER TABLE sets
+ADD COLUMN IF NOT EXISTS set_type TEXT NOT NULL DEFAULT 'working' CHECK (set_type IN ('working', 'warmup'));
@@ -0,0 +1,15 @@
+-- Live state of an in-progress workout, shared across devices
+CREATE TABLE IF NOT EXISTS workout_sessions (
+    workout_id UUID PRIMARY KEY REFERENCES workouts(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    current_exercise_id UUID REFERENCES exercises(id),
+    next_set JSONB, -- Planned next set: {"exercise_id", "weight", "unit", "reps", "set_type"}
+    rest_started_at TIMESTAMPTZ,
+    rest_ends_at TIMESTAMPTZ,
+    paused_at TIMESTAMPTZ, -- Set while paused
+    paused_seconds BIGINT NOT NULL DEFAULT 0, -- Total of completed pauses
+    version INTEGER NOT NULL DEFAULT 0, -- Bumped on every write so devices can detect stale updates
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_workout_sessions_user_id ON workout_sessions(user_id);
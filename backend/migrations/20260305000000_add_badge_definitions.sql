@@ -0,0 +1,34 @@
+-- Data-driven badges, evaluated by the rules engine when a workout finishes.
+-- `params` depends on `rule_type`:
+--   volume            {"min_kg": 5000, "max_kg": 10000}
+--   duration          {"min_minutes": 90} / {"max_minutes": 30, "min_volume_kg": 2000}
+--   set_count         {"min_sets": 20}
+--   time_of_day       {"start_hour": 4, "end_hour": 7, "utc_offset_hours": 0}
+--   streak            {"min_days": 7}
+--   pr_count          {"min_prs": 3}
+--   bodyweight_ratio  {"exercises": ["Barbell Bench Press"], "ratio": 1.0}
+CREATE TABLE IF NOT EXISTS badge_definitions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT,
+    rule_type TEXT NOT NULL CHECK (rule_type IN ('volume', 'duration', 'set_count', 'time_of_day', 'streak', 'pr_count', 'bodyweight_ratio')),
+    params JSONB NOT NULL DEFAULT '{}',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Record which sets were PRs at the time they were logged
+ALTER TABLE sets
+ADD COLUMN IF NOT EXISTS pr_kind TEXT CHECK (pr_kind IN ('1rm', 'volume'));
+
+-- The badges that used to be hardcoded in finish_workout, plus the README's Early Bird
+INSERT INTO badge_definitions (name, description, rule_type, params) VALUES
+('Titan Volume', 'Move 10,000 kg in a single workout.', 'volume', '{"min_kg": 10000}'),
+('Heavy Lifter', 'Move 5,000 kg in a single workout.', 'volume', '{"min_kg": 5000, "max_kg": 10000}'),
+('Marathoner', 'Train for 90 minutes or more.', 'duration', '{"min_minutes": 90}'),
+('Speed Demon', 'Move over 2,000 kg in 30 minutes or less.', 'duration', '{"max_minutes": 30, "min_volume_kg": 2000}'),
+('Volume Warrior', 'Complete 20 working sets in one workout.', 'set_count', '{"min_sets": 20}'),
+('Early Bird', 'Start a workout before 7 AM.', 'time_of_day', '{"start_hour": 4, "end_hour": 7}'),
+('Iron Week', 'Train seven days in a row.', 'streak', '{"min_days": 7}'),
+('Record Breaker', 'Set three PRs in one workout.', 'pr_count', '{"min_prs": 3}')
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,11 @@
+-- Trophy tiers: earning a badge once is bronze, `silver_count` earns silver
+-- and `gold_count` earns gold.
+ALTER TABLE badge_definitions
+ADD COLUMN IF NOT EXISTS silver_count INT NOT NULL DEFAULT 5,
+ADD COLUMN IF NOT EXISTS gold_count INT NOT NULL DEFAULT 25;
+
+ALTER TABLE badge_definitions
+ADD CONSTRAINT badge_definitions_tiers_check CHECK (1 < silver_count AND silver_count < gold_count);
+
+-- Rarer badges get easier tiers
+UPDATE badge_definitions SET silver_count = 3, gold_count = 10 WHERE name IN ('Titan Volume', 'Iron Week', 'Record Breaker');
@@ -0,0 +1,26 @@
+-- Strength milestones are earned once per user, evaluated by the achievements
+-- module against the user's bodyweight at the time of the lift.
+--   lift_ratio  {"lift": "bench", "ratio": 1.5, "tested_only": false}
+--   lift_total  {"total_kg": 453.59}  (best bench + squat + deadlift)
+ALTER TABLE badge_definitions
+ADD COLUMN IF NOT EXISTS category TEXT NOT NULL DEFAULT 'workout' CHECK (category IN ('workout', 'milestone'));
+
+ALTER TABLE badge_definitions DROP CONSTRAINT IF EXISTS badge_definitions_rule_type_check;
+ALTER TABLE badge_definitions
+ADD CONSTRAINT badge_definitions_rule_type_check CHECK (rule_type IN (
+    'volume', 'duration', 'set_count', 'time_of_day', 'streak', 'pr_count', 'bodyweight_ratio',
+    'lift_ratio', 'lift_total'
+));
+
+INSERT INTO badge_definitions (name, description, rule_type, params, category) VALUES
+('Bodyweight Bench', 'Bench press your bodyweight.', 'lift_ratio', '{"lift": "bench", "ratio": 1.0}', 'milestone'),
+('1.5x Bench', 'Bench press 1.5 times your bodyweight.', 'lift_ratio', '{"lift": "bench", "ratio": 1.5}', 'milestone'),
+('Double Bodyweight Bench', 'Bench press twice your bodyweight.', 'lift_ratio', '{"lift": "bench", "ratio": 2.0}', 'milestone'),
+('Bodyweight Squat', 'Squat your bodyweight.', 'lift_ratio', '{"lift": "squat", "ratio": 1.0}', 'milestone'),
+('1.5x Squat', 'Squat 1.5 times your bodyweight.', 'lift_ratio', '{"lift": "squat", "ratio": 1.5}', 'milestone'),
+('Double Bodyweight Squat', 'Squat twice your bodyweight.', 'lift_ratio', '{"lift": "squat", "ratio": 2.0}', 'milestone'),
+('Bodyweight Deadlift', 'Deadlift your bodyweight.', 'lift_ratio', '{"lift": "deadlift", "ratio": 1.0}', 'milestone'),
+('1.5x Deadlift', 'Deadlift 1.5 times your bodyweight.', 'lift_ratio', '{"lift": "deadlift", "ratio": 1.5}', 'milestone'),
+('Double Bodyweight Deadlift', 'Deadlift twice your bodyweight.', 'lift_ratio', '{"lift": "deadlift", "ratio": 2.0}', 'milestone'),
+('1000 lb Club', 'Bench, squat and deadlift a combined 1000 lb.', 'lift_total', '{"total_kg": 453.59}', 'milestone')
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,7 @@
+-- Experience points. Each finished workout stores the XP it granted and the
+-- user's total is the sum, so recomputing history is a plain rebuild.
+ALTER TABLE workouts
+ADD COLUMN IF NOT EXISTS xp_earned INT NOT NULL DEFAULT 0;
+
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS xp BIGINT NOT NULL DEFAULT 0;
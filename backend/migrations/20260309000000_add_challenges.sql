@@ -0,0 +1,36 @@
+-- Time-boxed challenges between a creator and the followers they invite.
+-- With a `goal`, everyone who reaches it wins ("100 pull-ups a day for 30
+-- days" uses period 'daily': the goal must be hit every day). Without one,
+-- the highest total wins ("most deadlift volume in March").
+CREATE TABLE IF NOT EXISTS challenges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    creator_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    metric TEXT NOT NULL CHECK (metric IN ('reps', 'volume', 'sets', 'workouts')),
+    exercise_id UUID REFERENCES exercises(id), -- NULL counts every exercise
+    goal DOUBLE PRECISION CHECK (goal > 0),
+    period TEXT NOT NULL DEFAULT 'total' CHECK (period IN ('total', 'daily')),
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    badge_name VARCHAR(255) NOT NULL, -- Awarded to the winners
+    finalized_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (ends_at > starts_at),
+    CHECK (period = 'total' OR goal IS NOT NULL)
+);
+
+CREATE TABLE IF NOT EXISTS challenge_participants (
+    challenge_id UUID NOT NULL REFERENCES challenges(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'invited' CHECK (status IN ('invited', 'joined', 'declined')),
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    final_progress DOUBLE PRECISION, -- Frozen when the challenge is finalized
+    final_rank INT,
+    is_winner BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (challenge_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_challenge_participants_user_id ON challenge_participants(user_id);
+CREATE INDEX IF NOT EXISTS idx_challenges_due ON challenges(ends_at) WHERE finalized_at IS NULL;
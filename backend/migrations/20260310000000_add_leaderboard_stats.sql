@@ -0,0 +1,14 @@
+-- Materialized leaderboard values, one row per board and user. Refreshed for
+-- a single user when they log sets and for everyone on a schedule.
+CREATE TABLE IF NOT EXISTS leaderboard_stats (
+    period TEXT NOT NULL,    -- 'all', 'weekly', 'monthly'
+    metric TEXT NOT NULL,    -- 'volume', 'e1rm', 'wilks', 'dots', 'ipf_gl', 'workouts', 'streak'
+    dimension TEXT NOT NULL, -- Muscle group (volume), exercise id (e1rm), basis (scores) or ''
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    value DOUBLE PRECISION NOT NULL,
+    refreshed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (period, metric, dimension, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_leaderboard_stats_rank ON leaderboard_stats (period, metric, dimension, value DESC);
+CREATE INDEX IF NOT EXISTS idx_leaderboard_stats_user_id ON leaderboard_stats (user_id);
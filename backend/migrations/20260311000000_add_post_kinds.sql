@@ -0,0 +1,9 @@
+-- Posts are written by users or generated when a workout finishes or a set
+-- is a PR. A PR post goes away with its set.
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS kind TEXT NOT NULL DEFAULT 'manual' CHECK (kind IN ('manual', 'workout', 'pr'));
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS set_id UUID REFERENCES sets(id) ON DELETE CASCADE;
+
+-- One generated post per finished workout
+CREATE UNIQUE INDEX IF NOT EXISTS idx_posts_workout_finished ON posts (workout_id) WHERE kind = 'workout';
+
+CREATE INDEX IF NOT EXISTS idx_posts_user_id_created_at ON posts (user_id, created_at DESC);
@@ -0,0 +1,28 @@
+-- Kudos and threaded comments, on a feed post or directly on a workout.
+CREATE TABLE IF NOT EXISTS kudos (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_id UUID REFERENCES posts(id) ON DELETE CASCADE,
+    workout_id UUID REFERENCES workouts(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((post_id IS NULL) <> (workout_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_kudos_post_user ON kudos (post_id, user_id) WHERE post_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_kudos_workout_user ON kudos (workout_id, user_id) WHERE workout_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS comments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_id UUID REFERENCES posts(id) ON DELETE CASCADE,
+    workout_id UUID REFERENCES workouts(id) ON DELETE CASCADE,
+    parent_id UUID REFERENCES comments(id) ON DELETE CASCADE, -- Reply to another comment on the same target
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    edited_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ, -- Deleted comments keep their place in the thread
+    CHECK ((post_id IS NULL) <> (workout_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_comments_post_id ON comments (post_id, created_at) WHERE post_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_comments_workout_id ON comments (workout_id, created_at) WHERE workout_id IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Private accounts approve their followers. A follow of a private account
+-- starts out pending; rejecting a request deletes it.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_private BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE follows ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'accepted' CHECK (status IN ('pending', 'accepted'));
+ALTER TABLE follows ADD COLUMN IF NOT EXISTS accepted_at TIMESTAMPTZ;
+UPDATE follows SET accepted_at = created_at WHERE status = 'accepted' AND accepted_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_follows_following_id ON follows (following_id, status);
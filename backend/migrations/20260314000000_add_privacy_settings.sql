@@ -0,0 +1,14 @@
+-- Who can see each part of a profile: 'public', 'followers' (accepted) or
+-- 'only_me'. Lifts are shared by default, body metrics are not. Users
+-- without a row get the defaults.
+CREATE TABLE IF NOT EXISTS privacy_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    activity TEXT NOT NULL DEFAULT 'public' CHECK (activity IN ('public', 'followers', 'only_me')),               -- Totals, streaks, level, activity log
+    workout_history TEXT NOT NULL DEFAULT 'public' CHECK (workout_history IN ('public', 'followers', 'only_me')),
+    lifts TEXT NOT NULL DEFAULT 'public' CHECK (lifts IN ('public', 'followers', 'only_me')),                     -- Best lifts and strength scores
+    body_weight TEXT NOT NULL DEFAULT 'followers' CHECK (body_weight IN ('public', 'followers', 'only_me')),      -- Current weight and weight history
+    body_metrics TEXT NOT NULL DEFAULT 'followers' CHECK (body_metrics IN ('public', 'followers', 'only_me')),    -- Height, gender, activity level
+    date_of_birth TEXT NOT NULL DEFAULT 'only_me' CHECK (date_of_birth IN ('public', 'followers', 'only_me')),
+    nutrition TEXT NOT NULL DEFAULT 'only_me' CHECK (nutrition IN ('public', 'followers', 'only_me')),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
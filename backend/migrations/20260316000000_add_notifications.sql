@@ -0,0 +1,16 @@
+-- In-app inbox. `data` carries what the client needs to render and link the
+-- notification (post, workout, badge, boards passed ...), by kind.
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN (
+        'follow', 'follow_request', 'follow_accepted', 'badge', 'personal_record', 'kudos', 'comment', 'rank_beaten'
+    )),
+    actor_id UUID REFERENCES users(id) ON DELETE CASCADE, -- Who caused it, if anyone
+    data JSONB NOT NULL DEFAULT '{}',
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notifications_unread ON notifications (user_id) WHERE read_at IS NULL;
@@ -0,0 +1,7 @@
+-- Trigram indexes back both the substring (ILIKE) and the fuzzy (%) matches
+-- of user search.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_users_username_trgm ON users USING GIN (username gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_users_bio_trgm ON users USING GIN (bio gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_workouts_template_id ON workouts (template_id) WHERE template_id IS NOT NULL;
@@ -0,0 +1,36 @@
+-- Gyms and clubs. The owner runs the group, coaches help invite and manage
+-- members, members train and post together. Invitations are member rows
+-- with status 'invited'.
+CREATE TABLE IF NOT EXISTS groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS group_members (
+    group_id UUID NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL DEFAULT 'member' CHECK (role IN ('owner', 'coach', 'member')),
+    status TEXT NOT NULL DEFAULT 'invited' CHECK (status IN ('invited', 'joined')),
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    joined_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (group_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_group_members_user_id ON group_members (user_id);
+
+-- Posts shared to a group show in the group feed instead of the follower feed
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS group_id UUID REFERENCES groups(id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS idx_posts_group_id ON posts (group_id, created_at DESC) WHERE group_id IS NOT NULL;
+
+-- Group challenges are open to every member instead of the creator's followers
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS group_id UUID REFERENCES groups(id) ON DELETE CASCADE;
+
+ALTER TABLE notifications DROP CONSTRAINT IF EXISTS notifications_kind_check;
+ALTER TABLE notifications ADD CONSTRAINT notifications_kind_check CHECK (kind IN (
+    'follow', 'follow_request', 'follow_accepted', 'badge', 'personal_record', 'kudos', 'comment', 'rank_beaten',
+    'group_invite'
+));
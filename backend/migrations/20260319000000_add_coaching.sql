@@ -0,0 +1,29 @@
+-- An athlete grants a coach access to their training: reading workouts and
+-- sets, and editing and assigning templates. The athlete sets and revokes
+-- the grant; either side can end it.
+CREATE TABLE IF NOT EXISTS coaching (
+    coach_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    athlete_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    can_view_workouts BOOLEAN NOT NULL DEFAULT TRUE,
+    can_edit_templates BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (coach_id, athlete_id),
+    CHECK (coach_id <> athlete_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_coaching_athlete_id ON coaching (athlete_id);
+
+-- Templates a coach assigned keep track of who assigned them
+ALTER TABLE workout_templates ADD COLUMN IF NOT EXISTS assigned_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+-- Comments (and kudos) can also be left on a single set
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS set_id UUID REFERENCES sets(id) ON DELETE CASCADE;
+ALTER TABLE comments DROP CONSTRAINT IF EXISTS comments_check;
+ALTER TABLE comments ADD CONSTRAINT comments_target_check CHECK (num_nonnulls(post_id, workout_id, set_id) = 1);
+CREATE INDEX IF NOT EXISTS idx_comments_set_id ON comments (set_id, created_at) WHERE set_id IS NOT NULL;
+
+ALTER TABLE kudos ADD COLUMN IF NOT EXISTS set_id UUID REFERENCES sets(id) ON DELETE CASCADE;
+ALTER TABLE kudos DROP CONSTRAINT IF EXISTS kudos_check;
+ALTER TABLE kudos ADD CONSTRAINT kudos_target_check CHECK (num_nonnulls(post_id, workout_id, set_id) = 1);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_kudos_set_user ON kudos (set_id, user_id) WHERE set_id IS NOT NULL;
@@ -0,0 +1,13 @@
+-- Public share links for finished workouts. The link carries the share id
+-- and a signature over it; revoking a share kills its link for good, and a
+-- new share gets a new id.
+CREATE TABLE IF NOT EXISTS workout_shares (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    workout_id UUID NOT NULL REFERENCES workouts(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+-- At most one live link per workout
+CREATE UNIQUE INDEX IF NOT EXISTS idx_workout_shares_active ON workout_shares (workout_id) WHERE revoked_at IS NULL;
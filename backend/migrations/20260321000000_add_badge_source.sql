@@ -0,0 +1,10 @@
+-- Where a badge came from: the rules in badge_definitions, or winning a
+-- challenge. Challenge badge names are chosen by users, so only 'rule' badges
+-- are ever matched against badge_definitions.
+ALTER TABLE user_badges ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'rule'
+    CHECK (source IN ('rule', 'challenge'));
+
+-- Challenge wins so far: not tied to a workout, named after a challenge's badge
+UPDATE user_badges ub SET source = 'challenge'
+WHERE ub.workout_id IS NULL
+  AND EXISTS (SELECT 1 FROM challenges c WHERE c.badge_name = ub.badge_name);
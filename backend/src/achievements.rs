@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::models::BadgeDefinition;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lift {
+    Bench,
+    Squat,
+    Deadlift,
+}
+
+impl Lift {
+    pub const ALL: [Lift; 3] = [Lift::Bench, Lift::Squat, Lift::Deadlift];
+
+    /// Exercises that count as the competition lift. Variations (incline,
+    /// Romanian, machines...) don't.
+    pub fn exercise_names(self) -> &'static [&'static str] {
+        match self {
+            Lift::Bench => &["Bench Press", "Barbell Bench Press", "Flat Barbell Bench Press"],
+            Lift::Squat => &["Squat", "Barbell Squat"],
+            Lift::Deadlift => &["Deadlift", "Conventional Deadlift", "Sumo Deadlift"],
+        }
+    }
+
+    pub fn classify(exercise_name: &str) -> Option<Lift> {
+        Lift::ALL.into_iter()
+            .find(|lift| lift.exercise_names().iter().any(|n| n.eq_ignore_ascii_case(exercise_name)))
+    }
+}
+
+/// A milestone rule, parsed from a `badge_definitions` row with category "milestone".
+#[derive(Debug, Deserialize)]
+#[serde(tag = "rule_type", content = "params", rename_all = "snake_case")]
+pub enum MilestoneRule {
+    LiftRatio {
+        lift: Lift,
+        ratio: f64,
+        #[serde(default)]
+        tested_only: bool, // Only actual singles count, no e1RM
+    },
+    LiftTotal {
+        total_kg: f64,
+    },
+}
+
+impl MilestoneRule {
+    pub fn parse(definition: &BadgeDefinition) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(serde_json::json!({
+            "rule_type": definition.rule_type,
+            "params": definition.params.0,
+        }))
+    }
+
+    /// Where the user stands against this rule, in the rule's own measure
+    /// (bodyweight multiple or total kg), paired with the target.
+    pub fn progress(&self, strength: &StrengthFacts) -> (f64, f64) {
+        match self {
+            MilestoneRule::LiftRatio { lift, ratio, tested_only } => {
+                let best = strength.lifts.get(lift).map(|b| b.best_ratio(*tested_only)).unwrap_or(0.0);
+                (best, *ratio)
+            }
+            MilestoneRule::LiftTotal { total_kg } => (strength.total_kg(), *total_kg),
+        }
+    }
+
+    pub fn is_met(&self, strength: &StrengthFacts) -> bool {
+        let (current, target) = self.progress(strength);
+        current >= target
+    }
+}
+
+/// Epley estimate of the one-rep max. Sets above 10 reps are too far from a
+/// max to count.
+pub fn estimated_1rm(weight_kg: f64, reps: i32) -> Option<f64> {
+    match reps {
+        1 => Some(weight_kg),
+        2..=10 => Some(weight_kg * (1.0 + reps as f64 / 30.0)),
+        _ => None,
+    }
+}
+
+/// Best performances on one lift, absolute and relative to the bodyweight
+/// logged at the time of each set.
+#[derive(Debug, Default)]
+pub struct LiftBest {
+    pub single_kg: f64,
+    pub e1rm_kg: f64,
+    pub single_ratio: f64,
+    pub e1rm_ratio: f64,
+}
+
+impl LiftBest {
+    pub fn best_kg(&self) -> f64 {
+        self.single_kg.max(self.e1rm_kg)
+    }
+
+    pub fn best_ratio(&self, tested_only: bool) -> f64 {
+        if tested_only {
+            self.single_ratio
+        } else {
+            self.single_ratio.max(self.e1rm_ratio)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct StrengthFacts {
+    pub lifts: HashMap<Lift, LiftBest>,
+}
+
+impl StrengthFacts {
+    /// Powerlifting total from the best of each lift, 0 until all three are logged.
+    pub fn total_kg(&self) -> f64 {
+        if Lift::ALL.iter().all(|lift| self.lifts.contains_key(lift)) {
+            self.lifts.values().map(LiftBest::best_kg).sum()
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct LiftSet {
+    exercise_name: String,
+    weight_kg: f64,
+    reps: i32,
+    bodyweight_kg: Option<f64>,
+}
+
+/// Best bench, squat and deadlift of `user_id` from finished workouts, counting
+/// sets logged up to `as_of`.
+pub async fn gather_strength<'e, E>(db: E, user_id: Uuid, as_of: DateTime<Utc>) -> Result<StrengthFacts, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    let names: Vec<&str> = Lift::ALL.iter().flat_map(|lift| lift.exercise_names().iter().copied()).collect();
+
+    let sets = sqlx::query_as::<_, LiftSet>(
+        r#"
+        SELECT
+            e.name as exercise_name,
+            s.weight_kg::float8 as weight_kg,
+            s.reps,
+            COALESCE(
+                (SELECT wl.weight_kg FROM weight_logs wl WHERE wl.user_id = w.user_id AND wl.logged_at <= s.created_at ORDER BY wl.logged_at DESC LIMIT 1),
+                u.current_weight_kg
+            ) as bodyweight_kg
+        FROM sets s
+        JOIN workouts w ON s.workout_id = w.id
+        JOIN exercises e ON s.exercise_id = e.id
+        JOIN users u ON w.user_id = u.id
+        WHERE w.user_id = $1
+          AND w.end_time IS NOT NULL
+          AND s.set_type = 'working'
+          AND s.reps BETWEEN 1 AND 10
+          AND s.created_at <= $2
+          AND e.name = ANY($3)
+        "#
+    )
+    .bind(user_id)
+    .bind(as_of)
+    .bind(&names)
+    .fetch_all(db)
+    .await?;
+
+    let mut strength = StrengthFacts::default();
+    for set in sets {
+        let (Some(lift), Some(e1rm)) = (Lift::classify(&set.exercise_name), estimated_1rm(set.weight_kg, set.reps)) else {
+            continue;
+        };
+        let best = strength.lifts.entry(lift).or_default();
+        let bodyweight = set.bodyweight_kg.filter(|w| *w > 0.0);
+
+        if set.reps == 1 {
+            best.single_kg = best.single_kg.max(set.weight_kg);
+            if let Some(bw) = bodyweight {
+                best.single_ratio = best.single_ratio.max(set.weight_kg / bw);
+            }
+        } else {
+            best.e1rm_kg = best.e1rm_kg.max(e1rm);
+            if let Some(bw) = bodyweight {
+                best.e1rm_ratio = best.e1rm_ratio.max(e1rm / bw);
+            }
+        }
+    }
+
+    Ok(strength)
+}
+
+/// Active milestone definitions with their parsed rules; invalid ones are skipped.
+pub async fn milestone_rules<'e, E>(db: E) -> Result<Vec<(BadgeDefinition, MilestoneRule)>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    let definitions = sqlx::query_as::<_, BadgeDefinition>(
+        "SELECT * FROM badge_definitions WHERE is_active AND category = 'milestone' ORDER BY created_at, name"
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(definitions.into_iter()
+        .filter_map(|definition| match MilestoneRule::parse(&definition) {
+            Ok(rule) => Some((definition, rule)),
+            Err(e) => {
+                tracing::warn!("Skipping milestone '{}' with invalid rule: {}", definition.name, e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Award the milestones `user_id` reached by the end of `workout_id`. Each
+/// milestone is earned once; returns the names newly earned.
+pub async fn award_milestones(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    workout_id: Uuid,
+    as_of: DateTime<Utc>,
+) -> Result<Vec<String>, sqlx::Error> {
+    let strength = gather_strength(&mut *conn, user_id, as_of).await?;
+
+    let mut earned = Vec::new();
+    for (definition, rule) in milestone_rules(&mut *conn).await? {
+        if !rule.is_met(&strength) {
+            continue;
+        }
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO user_badges (user_id, workout_id, badge_name, earned_at)
+            SELECT $1, $2, $3, $4
+            WHERE NOT EXISTS (SELECT 1 FROM user_badges WHERE user_id = $1 AND badge_name = $3 AND source = 'rule')
+            "#
+        )
+        .bind(user_id)
+        .bind(workout_id)
+        .bind(&definition.name)
+        .bind(as_of)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        if inserted > 0 {
+            earned.push(definition.name);
+        }
+    }
+
+    Ok(earned)
+}
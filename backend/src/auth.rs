@@ -0,0 +1,149 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+/// The user making the request, taken from the `X-User-Id` header.
+///
+/// This is a development stub, not authentication: the header is trusted as
+/// sent, so the API must not be exposed anywhere a caller could pick their
+/// own id. Requests without it are rejected; the Vite dev server adds it for
+/// the seeded demo user. The live event stream doesn't use this; it takes a
+/// signed [`LiveTokens`] token instead.
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentUser(pub Uuid);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.headers
+            .get("x-user-id")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|v| Uuid::parse_str(v).ok())
+            .map(CurrentUser)
+            .ok_or((StatusCode::UNAUTHORIZED, "Missing or invalid X-User-Id".to_string()))
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Bytes of the HMAC kept in a token, as for share links
+const SIGNATURE_BYTES: usize = 16;
+
+// How long a live token can be used to open a stream; open streams stay open
+const LIVE_TOKEN_TTL_MINUTES: i64 = 60;
+
+/// Signed, expiring tokens for the live event stream. EventSource can't set
+/// headers, so clients fetch a token with their normal credentials and pass
+/// it as `?token=`. A token is `<user id>.<expiry>.<signature>`.
+#[derive(Clone)]
+pub struct LiveTokens {
+    key: Vec<u8>,
+}
+
+impl LiveTokens {
+    pub fn new(secret: &[u8]) -> Self {
+        LiveTokens { key: secret.to_vec() }
+    }
+
+    /// Use `LIVE_TOKEN_SECRET`. Without it a random key is used, which only
+    /// means clients fetch a new token after a restart.
+    pub fn from_env() -> Self {
+        match std::env::var("LIVE_TOKEN_SECRET") {
+            Ok(secret) if !secret.is_empty() => LiveTokens::new(secret.as_bytes()),
+            _ => {
+                let random = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+                LiveTokens::new(random.as_bytes())
+            }
+        }
+    }
+
+    fn mac(&self, user_id: Uuid, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC takes keys of any length");
+        mac.update(user_id.as_bytes());
+        mac.update(&expires.to_be_bytes());
+        mac
+    }
+
+    /// A token for `user_id`, with when it stops being accepted.
+    pub fn issue(&self, user_id: Uuid, now: DateTime<Utc>) -> (String, DateTime<Utc>) {
+        let expires_at = now + Duration::minutes(LIVE_TOKEN_TTL_MINUTES);
+        let expires = expires_at.timestamp();
+        let signature = self.mac(user_id, expires).finalize().into_bytes();
+        let hex: String = signature[..SIGNATURE_BYTES].iter().map(|b| format!("{:02x}", b)).collect();
+        (format!("{}.{}.{}", user_id.simple(), expires, hex), Utc.timestamp_opt(expires, 0).unwrap())
+    }
+
+    /// The user a token was issued to, if it is genuine and hasn't expired.
+    pub fn verify(&self, token: &str, now: DateTime<Utc>) -> Option<Uuid> {
+        let mut parts = token.split('.');
+        let (id, expires, hex) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() || hex.len() != SIGNATURE_BYTES * 2 || !hex.is_ascii() {
+            return None;
+        }
+        let user_id = Uuid::parse_str(id).ok()?;
+        let expires: i64 = expires.parse().ok()?;
+        if expires <= now.timestamp() {
+            return None;
+        }
+        let signature = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+
+        self.mac(user_id, expires).verify_truncated_left(&signature).ok()?;
+        Some(user_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    #[tokio::test]
+    async fn current_user_comes_from_the_header_only() {
+        let user = Uuid::new_v4();
+        let (mut parts, _) = Request::builder().header("x-user-id", user.to_string()).body(()).unwrap().into_parts();
+        assert_eq!(CurrentUser::from_request_parts(&mut parts, &()).await.unwrap().0, user);
+
+        let (mut parts, _) = Request::builder().body(()).unwrap().into_parts();
+        let rejection = CurrentUser::from_request_parts(&mut parts, &()).await.unwrap_err();
+        assert_eq!(rejection.0, StatusCode::UNAUTHORIZED);
+
+        let (mut parts, _) = Request::builder().header("x-user-id", "demo").body(()).unwrap().into_parts();
+        let rejection = CurrentUser::from_request_parts(&mut parts, &()).await.unwrap_err();
+        assert_eq!(rejection.0, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn live_token_round_trips() {
+        let tokens = LiveTokens::new(b"secret");
+        let user = Uuid::new_v4();
+        let now = Utc::now();
+        let (token, expires_at) = tokens.issue(user, now);
+        assert_eq!(tokens.verify(&token, now), Some(user));
+        assert!(expires_at > now);
+    }
+
+    #[test]
+    fn live_token_rejects_tampering_and_expiry() {
+        let tokens = LiveTokens::new(b"secret");
+        let user = Uuid::new_v4();
+        let now = Utc::now();
+        let (token, expires_at) = tokens.issue(user, now);
+
+        let other = token.replacen(&user.simple().to_string(), &Uuid::new_v4().simple().to_string(), 1);
+        assert_eq!(tokens.verify(&other, now), None);
+        assert_eq!(LiveTokens::new(b"other").verify(&token, now), None);
+        assert_eq!(tokens.verify(&token, expires_at), None);
+        assert_eq!(tokens.verify(user.simple().to_string().as_str(), now), None);
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::models::BadgeDefinition;
+
+/// A badge rule, parsed from a `badge_definitions` row (`rule_type` + `params`).
+#[derive(Debug, Deserialize)]
+#[serde(tag = "rule_type", content = "params", rename_all = "snake_case")]
+pub enum BadgeRule {
+    Volume {
+        min_kg: f64,
+        max_kg: Option<f64>, // Exclusive, lets tiers not overlap
+    },
+    Duration {
+        min_minutes: Option<f64>,
+        max_minutes: Option<f64>,
+        min_volume_kg: Option<f64>,
+    },
+    SetCount {
+        min_sets: i64,
+    },
+    TimeOfDay {
+        start_hour: u32, // Inclusive
+        end_hour: u32,   // Exclusive, may wrap past midnight
+        #[serde(default)]
+        utc_offset_hours: i64,
+    },
+    Streak {
+        min_days: i64,
+    },
+    PrCount {
+        min_prs: i64,
+    },
+    BodyweightRatio {
+        exercises: Vec<String>,
+        ratio: f64,
+    },
+}
+
+/// Everything a rule may look at, gathered once per finished workout.
+#[derive(Debug)]
+pub struct WorkoutFacts {
+    pub user_id: Uuid,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: DateTime<Utc>,
+    pub duration_minutes: f64,
+    pub volume_kg: f64, // Working sets only
+    pub set_count: i64, // Working sets only
+    pub pr_count: i64,
+    pub streak_days: i64, // Consecutive training days ending with this workout
+    pub bodyweight_kg: Option<f64>, // At the time of the workout
+    pub best_lifts: HashMap<String, f64>, // Lowercased exercise name -> heaviest working set (kg)
+}
+
+impl BadgeRule {
+    pub fn parse(definition: &BadgeDefinition) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(serde_json::json!({
+            "rule_type": definition.rule_type,
+            "params": definition.params.0,
+        }))
+    }
+
+    // `Option::is_none_or` would read better but needs Rust 1.82; the image builds with 1.80
+    #[allow(clippy::unnecessary_map_or)]
+    pub fn is_met(&self, facts: &WorkoutFacts) -> bool {
+        match self {
+            BadgeRule::Volume { min_kg, max_kg } => {
+                facts.volume_kg >= *min_kg && max_kg.map_or(true, |max| facts.volume_kg < max)
+            }
+            BadgeRule::Duration { min_minutes, max_minutes, min_volume_kg } => {
+                min_minutes.map_or(true, |min| facts.duration_minutes >= min)
+                    && max_minutes.map_or(true, |max| facts.duration_minutes <= max)
+                    && min_volume_kg.map_or(true, |min| facts.volume_kg > min)
+            }
+            BadgeRule::SetCount { min_sets } => facts.set_count >= *min_sets,
+            BadgeRule::TimeOfDay { start_hour, end_hour, utc_offset_hours } => {
+                let Some(started) = facts.started_at else {
+                    return false;
+                };
+                let hour = (started + Duration::hours(*utc_offset_hours)).hour();
+                if start_hour <= end_hour {
+                    hour >= *start_hour && hour < *end_hour
+                } else {
+                    hour >= *start_hour || hour < *end_hour
+                }
+            }
+            BadgeRule::Streak { min_days } => facts.streak_days >= *min_days,
+            BadgeRule::PrCount { min_prs } => facts.pr_count >= *min_prs,
+            BadgeRule::BodyweightRatio { exercises, ratio } => {
+                let Some(bodyweight) = facts.bodyweight_kg.filter(|w| *w > 0.0) else {
+                    return false;
+                };
+                exercises.iter()
+                    .filter_map(|name| facts.best_lifts.get(&name.to_lowercase()))
+                    .any(|best| best / bodyweight >= *ratio)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BadgeTier {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TierProgress {
+    pub tier: BadgeTier,
+    pub required: i64, // Total earns needed
+    pub remaining: i64,
+    pub percent: f64,  // 0-100, measured from the current tier
+}
+
+impl BadgeDefinition {
+    fn tiers(&self) -> [(BadgeTier, i64); 3] {
+        [
+            (BadgeTier::Bronze, 1),
+            (BadgeTier::Silver, self.silver_count as i64),
+            (BadgeTier::Gold, self.gold_count as i64),
+        ]
+    }
+
+    /// Milestones are earned once and have no tiers.
+    pub fn has_tiers(&self) -> bool {
+        self.category == "workout"
+    }
+
+    /// Highest tier reached after `count` earns.
+    pub fn tier_for(&self, count: i64) -> Option<BadgeTier> {
+        if !self.has_tiers() {
+            return None;
+        }
+        self.tiers().into_iter()
+            .rev()
+            .find(|(_, required)| count >= *required)
+            .map(|(tier, _)| tier)
+    }
+
+    /// Progress toward the first tier not yet reached, `None` once gold.
+    pub fn next_tier(&self, count: i64) -> Option<TierProgress> {
+        if !self.has_tiers() {
+            return None;
+        }
+        let tiers = self.tiers();
+        let next = tiers.iter().position(|(_, required)| count < *required)?;
+        let (tier, required) = tiers[next];
+        let floor = if next == 0 { 0 } else { tiers[next - 1].1 };
+
+        Some(TierProgress {
+            tier,
+            required,
+            remaining: required - count,
+            percent: ((count - floor) as f64 / (required - floor) as f64 * 100.0).clamp(0.0, 100.0),
+        })
+    }
+}
+
+/// Classify a working set against the lifter's history on the exercise: heavier
+/// than ever is a 1RM PR, otherwise more reps than ever at this weight or
+/// heavier is a volume PR.
+pub fn classify_pr(weight_kg: f32, reps: i32, prev_max_weight: f32, prev_max_reps: i32) -> Option<&'static str> {
+    if weight_kg > prev_max_weight {
+        Some("1rm")
+    } else if reps > prev_max_reps && reps > 5 { // Arbitrary min reps to avoid trivial PRs
+        Some("volume")
+    } else {
+        None
+    }
+}
+
+/// Length of the run of consecutive days at the start of `dates`
+/// (sorted newest first, no duplicates).
+pub fn consecutive_days(dates: &[NaiveDate]) -> i64 {
+    let mut streak = 0;
+    let mut expected = match dates.first() {
+        Some(d) => *d,
+        None => return 0,
+    };
+    for date in dates {
+        if *date != expected {
+            break;
+        }
+        streak += 1;
+        expected -= Duration::days(1);
+    }
+    streak
+}
+
+/// Collect the facts for `workout_id` as if it finished at `ended_at`.
+pub async fn gather_facts(conn: &mut PgConnection, workout_id: Uuid, ended_at: DateTime<Utc>) -> Result<WorkoutFacts, sqlx::Error> {
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            w.user_id,
+            w.start_time,
+            COALESCE(SUM(s.weight_kg * s.reps) FILTER (WHERE s.set_type = 'working'), 0)::float8 as volume,
+            COUNT(s.id) FILTER (WHERE s.set_type = 'working') as set_count,
+            COUNT(s.id) FILTER (WHERE s.pr_kind IS NOT NULL) as pr_count
+        FROM workouts w
+        LEFT JOIN sets s ON w.id = s.workout_id
+        WHERE w.id = $1
+        GROUP BY w.id, w.user_id, w.start_time
+        "#,
+        workout_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let reference_time = totals.start_time.unwrap_or(ended_at);
+
+    // Weight logged closest before the workout, else the profile weight
+    let bodyweight_kg = sqlx::query_scalar::<_, Option<f64>>(
+        r#"
+        SELECT COALESCE(
+            (SELECT weight_kg FROM weight_logs WHERE user_id = $1 AND logged_at <= $2 ORDER BY logged_at DESC LIMIT 1),
+            (SELECT current_weight_kg FROM users WHERE id = $1)
+        )
+        "#
+    )
+    .bind(totals.user_id)
+    .bind(reference_time)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let best_lifts = sqlx::query_as::<_, (String, f64)>(
+        r#"
+        SELECT LOWER(e.name), MAX(s.weight_kg)::float8
+        FROM sets s
+        JOIN exercises e ON s.exercise_id = e.id
+        WHERE s.workout_id = $1 AND s.set_type = 'working'
+        GROUP BY LOWER(e.name)
+        "#
+    )
+    .bind(workout_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .collect();
+
+    let training_days = sqlx::query_scalar::<_, NaiveDate>(
+        r#"
+        SELECT DISTINCT DATE(COALESCE(start_time, created_at)) as day
+        FROM workouts
+        WHERE user_id = $1
+          AND (end_time IS NOT NULL OR id = $2)
+          AND DATE(COALESCE(start_time, created_at)) <= DATE($3)
+        ORDER BY day DESC
+        "#
+    )
+    .bind(totals.user_id)
+    .bind(workout_id)
+    .bind(reference_time)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(WorkoutFacts {
+        user_id: totals.user_id,
+        started_at: totals.start_time,
+        ended_at,
+        duration_minutes: match totals.start_time {
+            Some(start) => (ended_at - start).num_minutes() as f64,
+            None => 60.0, // Default to 1 hour if start time missing
+        },
+        volume_kg: totals.volume.unwrap_or(0.0),
+        set_count: totals.set_count.unwrap_or(0),
+        pr_count: totals.pr_count.unwrap_or(0),
+        streak_days: consecutive_days(&training_days),
+        bodyweight_kg,
+        best_lifts,
+    })
+}
+
+/// Evaluate every active badge definition against `facts` and record the
+/// earned ones for `workout_id`. Returns the names of the badges newly earned.
+pub async fn award_for_workout(conn: &mut PgConnection, workout_id: Uuid, facts: &WorkoutFacts) -> Result<Vec<String>, sqlx::Error> {
+    let definitions = sqlx::query_as::<_, BadgeDefinition>(
+        "SELECT * FROM badge_definitions WHERE is_active AND category = 'workout' ORDER BY created_at, name"
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut earned = Vec::new();
+    for definition in definitions {
+        let rule = match BadgeRule::parse(&definition) {
+            Ok(rule) => rule,
+            Err(e) => {
+                tracing::warn!("Skipping badge '{}' with invalid rule: {}", definition.name, e);
+                continue;
+            }
+        };
+
+        if rule.is_met(facts) {
+            let inserted = sqlx::query!(
+                "INSERT INTO user_badges (user_id, workout_id, badge_name, earned_at) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+                facts.user_id,
+                workout_id,
+                definition.name,
+                facts.ended_at
+            )
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+            // Already held for this workout, e.g. on a retried request
+            if inserted > 0 {
+                earned.push(definition.name);
+            }
+        }
+    }
+
+    Ok(earned)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+    }
+
+    fn facts(volume_kg: f64, started_at: Option<DateTime<Utc>>) -> WorkoutFacts {
+        WorkoutFacts {
+            user_id: Uuid::nil(),
+            started_at,
+            ended_at: Utc::now(),
+            duration_minutes: 60.0,
+            volume_kg,
+            set_count: 0,
+            pr_count: 0,
+            streak_days: 1,
+            bodyweight_kg: None,
+            best_lifts: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn heavier_than_ever_is_a_1rm_pr() {
+        assert_eq!(classify_pr(102.5, 1, 100.0, 8), Some("1rm"));
+        assert_eq!(classify_pr(20.0, 5, 0.0, 0), Some("1rm"));
+    }
+
+    #[test]
+    fn more_reps_at_the_same_weight_is_a_volume_pr() {
+        assert_eq!(classify_pr(100.0, 9, 100.0, 8), Some("volume"));
+        // Too few reps to count, or only matching the best
+        assert_eq!(classify_pr(100.0, 5, 100.0, 4), None);
+        assert_eq!(classify_pr(100.0, 8, 100.0, 8), None);
+        assert_eq!(classify_pr(90.0, 6, 100.0, 8), None);
+    }
+
+    #[test]
+    fn bodyweight_sets_only_count_for_reps() {
+        assert_eq!(classify_pr(0.0, 12, 0.0, 10), Some("volume"));
+        assert_eq!(classify_pr(0.0, 10, 0.0, 10), None);
+    }
+
+    #[test]
+    fn streak_stops_at_the_first_gap() {
+        assert_eq!(consecutive_days(&[]), 0);
+        assert_eq!(consecutive_days(&[day(10)]), 1);
+        assert_eq!(consecutive_days(&[day(10), day(9), day(8), day(6), day(5)]), 3);
+        assert_eq!(consecutive_days(&[day(10), day(8)]), 1);
+    }
+
+    #[test]
+    fn volume_tiers_exclude_their_upper_bound() {
+        let rule = BadgeRule::Volume { min_kg: 5000.0, max_kg: Some(10000.0) };
+        assert!(rule.is_met(&facts(5000.0, None)));
+        assert!(!rule.is_met(&facts(10000.0, None)));
+        assert!(!rule.is_met(&facts(4999.9, None)));
+    }
+
+    #[test]
+    fn time_of_day_wraps_past_midnight() {
+        let rule = BadgeRule::TimeOfDay { start_hour: 22, end_hour: 4, utc_offset_hours: 0 };
+        let at = |hour| facts(0.0, Some(Utc.with_ymd_and_hms(2026, 3, 10, hour, 30, 0).unwrap()));
+        assert!(rule.is_met(&at(23)));
+        assert!(rule.is_met(&at(3)));
+        assert!(!rule.is_met(&at(4)));
+        assert!(!rule.is_met(&facts(0.0, None)));
+    }
+}
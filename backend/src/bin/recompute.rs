@@ -0,0 +1,34 @@
+//! Rebuild PR flags, badges, milestones and XP from workout history.
+//! Run after changing badge definitions or XP rules:
+//!
+//!     cargo run --bin recompute            # every user
+//!     cargo run --bin recompute -- <uuid>  # a single user
+use backend::recompute;
+use dotenvy::dotenv;
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use uuid::Uuid;
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPoolOptions::new().connect(&database_url).await.expect("Failed to connect");
+
+    let users: Vec<Uuid> = match env::args().nth(1) {
+        Some(id) => vec![Uuid::parse_str(&id).expect("Invalid user id")],
+        None => sqlx::query_scalar("SELECT id FROM users ORDER BY created_at")
+            .fetch_all(&pool)
+            .await
+            .expect("Failed to fetch users"),
+    };
+
+    for user_id in &users {
+        let summary = recompute::recompute_user(&pool, *user_id).await.expect("Recompute failed");
+        println!(
+            "{}: {} workouts, {} PRs, {} badges, {} XP",
+            user_id, summary.workouts, summary.prs, summary.badges, summary.xp
+        );
+    }
+    println!("Recomputed {} users.", users.len());
+}
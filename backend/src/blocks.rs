@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Block `blocked_id`, removing follows, follow requests and coaching in both
+/// directions.
+pub async fn block(db: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query("INSERT INTO user_blocks (blocker_id, blocked_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        "DELETE FROM follows WHERE (follower_id = $1 AND following_id = $2) OR (follower_id = $2 AND following_id = $1)"
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM coaching WHERE (coach_id = $1 AND athlete_id = $2) OR (coach_id = $2 AND athlete_id = $1)"
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+pub async fn unblock(db: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM user_blocks WHERE blocker_id = $1 AND blocked_id = $2")
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn mute(db: &PgPool, muter_id: Uuid, muted_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO user_mutes (muter_id, muted_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(muter_id)
+        .bind(muted_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn unmute(db: &PgPool, muter_id: Uuid, muted_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM user_mutes WHERE muter_id = $1 AND muted_id = $2")
+        .bind(muter_id)
+        .bind(muted_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Whether either user has blocked the other.
+pub async fn is_blocked(db: &PgPool, a: Uuid, b: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM user_blocks
+            WHERE (blocker_id = $1 AND blocked_id = $2) OR (blocker_id = $2 AND blocked_id = $1)
+        )
+        "#
+    )
+    .bind(a)
+    .bind(b)
+    .fetch_one(db)
+    .await
+}
+
+/// Everyone `user_id` has blocked or is blocked by; they never see each other.
+pub async fn hidden_users(db: &PgPool, user_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT blocked_id FROM user_blocks WHERE blocker_id = $1
+        UNION
+        SELECT blocker_id FROM user_blocks WHERE blocked_id = $1
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RestrictedUser {
+    pub id: Uuid,
+    pub username: String,
+    pub since: DateTime<Utc>,
+}
+
+/// Users `user_id` has blocked, newest first.
+pub async fn blocked_users(db: &PgPool, user_id: Uuid) -> Result<Vec<RestrictedUser>, sqlx::Error> {
+    sqlx::query_as::<_, RestrictedUser>(
+        r#"
+        SELECT u.id, u.username, b.created_at as since
+        FROM user_blocks b
+        JOIN users u ON b.blocked_id = u.id
+        WHERE b.blocker_id = $1
+        ORDER BY b.created_at DESC
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Users `user_id` has muted, newest first.
+pub async fn muted_users(db: &PgPool, user_id: Uuid) -> Result<Vec<RestrictedUser>, sqlx::Error> {
+    sqlx::query_as::<_, RestrictedUser>(
+        r#"
+        SELECT u.id, u.username, m.created_at as since
+        FROM user_mutes m
+        JOIN users u ON m.muted_id = u.id
+        WHERE m.muter_id = $1
+        ORDER BY m.created_at DESC
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
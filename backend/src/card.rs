@@ -0,0 +1,313 @@
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use tiny_skia::{
+    Color, FillRule, GradientStop, LinearGradient, Paint, PathBuilder, Pixmap, Point, PremultipliedColorU8, Rect,
+    SpreadMode, Transform,
+};
+
+use crate::summary::WorkoutCard;
+
+// Portrait 4:5, the tallest Instagram crops without cutting anything off
+pub const WIDTH: u32 = 1080;
+pub const HEIGHT: u32 = 1350;
+
+const PADDING: f32 = 80.0;
+const ROW_HEIGHT: f32 = 62.0;
+const PR_LINE_HEIGHT: f32 = 46.0;
+const MAX_PR_LINES: usize = 3;
+
+static REGULAR: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+static BOLD: &[u8] = include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf");
+
+#[derive(Clone, Copy)]
+struct Rgb(u8, u8, u8);
+
+const BACKGROUND_TOP: Rgb = Rgb(0x0f, 0x17, 0x2a);
+const BACKGROUND_BOTTOM: Rgb = Rgb(0x1e, 0x1b, 0x4b);
+const PANEL: Rgb = Rgb(0x1e, 0x29, 0x3b);
+const TEXT: Rgb = Rgb(0xf8, 0xfa, 0xfc);
+const MUTED: Rgb = Rgb(0x94, 0xa3, 0xb8);
+const ACCENT: Rgb = Rgb(0xf5, 0x9e, 0x0b);
+
+struct Canvas<'a> {
+    pixmap: Pixmap,
+    regular: FontRef<'a>,
+    bold: FontRef<'a>,
+}
+
+impl Canvas<'_> {
+    fn paint(color: Rgb) -> Paint<'static> {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(color.0, color.1, color.2, 255);
+        paint.anti_alias = true;
+        paint
+    }
+
+    fn rounded_rect(&mut self, x: f32, y: f32, w: f32, h: f32, r: f32, color: Rgb) {
+        let mut pb = PathBuilder::new();
+        pb.move_to(x + r, y);
+        pb.line_to(x + w - r, y);
+        pb.quad_to(x + w, y, x + w, y + r);
+        pb.line_to(x + w, y + h - r);
+        pb.quad_to(x + w, y + h, x + w - r, y + h);
+        pb.line_to(x + r, y + h);
+        pb.quad_to(x, y + h, x, y + h - r);
+        pb.line_to(x, y + r);
+        pb.quad_to(x, y, x + r, y);
+        pb.close();
+        if let Some(path) = pb.finish() {
+            self.pixmap.fill_path(&path, &Self::paint(color), FillRule::Winding, Transform::identity(), None);
+        }
+    }
+
+    fn font(&self, bold: bool) -> &FontRef<'_> {
+        if bold { &self.bold } else { &self.regular }
+    }
+
+    fn text_width(&self, text: &str, size: f32, bold: bool) -> f32 {
+        let font = self.font(bold).as_scaled(PxScale::from(size));
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                width += font.kern(previous, id);
+            }
+            width += font.h_advance(id);
+            previous = Some(id);
+        }
+        width
+    }
+
+    /// Shorten `text` with an ellipsis until it fits in `max_width`.
+    fn fit(&self, text: &str, size: f32, bold: bool, max_width: f32) -> String {
+        if self.text_width(text, size, bold) <= max_width {
+            return text.to_string();
+        }
+        let mut chars: Vec<char> = text.chars().collect();
+        while !chars.is_empty() {
+            chars.pop();
+            let candidate = format!("{}…", chars.iter().collect::<String>().trim_end());
+            if self.text_width(&candidate, size, bold) <= max_width {
+                return candidate;
+            }
+        }
+        "…".to_string()
+    }
+
+    /// Draw `text` with its baseline at `y`. Returns its width.
+    fn text(&mut self, text: &str, x: f32, y: f32, size: f32, bold: bool, color: Rgb) -> f32 {
+        let scale = PxScale::from(size);
+        let font = if bold { self.bold.clone() } else { self.regular.clone() };
+        let scaled = font.as_scaled(scale);
+        let (width, height) = (self.pixmap.width() as i32, self.pixmap.height() as i32);
+        let pixels = self.pixmap.pixels_mut();
+
+        let mut caret = x;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += scaled.kern(previous, id);
+            }
+            let glyph = id.with_scale_and_position(scale, point(caret, y));
+            caret += scaled.h_advance(id);
+            previous = Some(id);
+
+            let Some(outlined) = font.outline_glyph(glyph) else { continue };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px >= width || py >= height {
+                    return;
+                }
+                let pixel = &mut pixels[(py * width + px) as usize];
+                let c = coverage.clamp(0.0, 1.0);
+                let blend = |src: u8, dst: u8| (src as f32 * c + dst as f32 * (1.0 - c)).round() as u8;
+                if let Some(blended) = PremultipliedColorU8::from_rgba(
+                    blend(color.0, pixel.red()),
+                    blend(color.1, pixel.green()),
+                    blend(color.2, pixel.blue()),
+                    blend(255, pixel.alpha()),
+                ) {
+                    *pixel = blended;
+                }
+            });
+        }
+        caret - x
+    }
+
+    fn text_right(&mut self, text: &str, right: f32, y: f32, size: f32, bold: bool, color: Rgb) -> f32 {
+        let width = self.text_width(text, size, bold);
+        self.text(text, right - width, y, size, bold, color)
+    }
+
+    /// A rounded label, e.g. a badge. Returns its width.
+    fn pill(&mut self, text: &str, x: f32, y: f32, size: f32, fill: Rgb, color: Rgb) -> f32 {
+        let width = self.text_width(text, size, true) + size * 1.2;
+        let height = size * 1.7;
+        self.rounded_rect(x, y, width, height, height / 2.0, fill);
+        self.text(text, x + size * 0.6, y + height * 0.5 + size * 0.36, size, true, color);
+        width
+    }
+}
+
+/// Weights as lifters write them: 100, 102.5, 225.
+fn weight(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn thousands(value: f64) -> String {
+    let digits = (value.round() as i64).abs().to_string();
+    let groups: Vec<&str> = digits.as_bytes().rchunks(3).rev().map(|g| std::str::from_utf8(g).unwrap_or_default()).collect();
+    groups.join(",")
+}
+
+fn duration(minutes: f64) -> String {
+    let minutes = minutes.round() as i64;
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// Render a workout card as a PNG.
+pub fn render_png(card: &WorkoutCard) -> Result<Vec<u8>, String> {
+    let mut canvas = Canvas {
+        pixmap: Pixmap::new(WIDTH, HEIGHT).ok_or("Invalid image size")?,
+        regular: FontRef::try_from_slice(REGULAR).map_err(|e| e.to_string())?,
+        bold: FontRef::try_from_slice(BOLD).map_err(|e| e.to_string())?,
+    };
+    let (width, height) = (WIDTH as f32, HEIGHT as f32);
+    let right = width - PADDING;
+    let content_width = width - PADDING * 2.0;
+    let unit = card.unit.as_str();
+
+    // Background and brand stripe
+    let shader = LinearGradient::new(
+        Point::from_xy(0.0, 0.0),
+        Point::from_xy(0.0, height),
+        vec![
+            GradientStop::new(0.0, Color::from_rgba8(BACKGROUND_TOP.0, BACKGROUND_TOP.1, BACKGROUND_TOP.2, 255)),
+            GradientStop::new(1.0, Color::from_rgba8(BACKGROUND_BOTTOM.0, BACKGROUND_BOTTOM.1, BACKGROUND_BOTTOM.2, 255)),
+        ],
+        SpreadMode::Pad,
+        Transform::identity(),
+    )
+    .ok_or("Invalid gradient")?;
+    let background = Paint { shader, ..Paint::default() };
+    canvas.pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, width, height).ok_or("Invalid image size")?, &background, Transform::identity(), None);
+    canvas.pixmap.fill_rect(Rect::from_xywh(0.0, 0.0, width, 12.0).ok_or("Invalid image size")?, &Canvas::paint(ACCENT), Transform::identity(), None);
+
+    // Header
+    canvas.text("TITANLIFT", PADDING, 118.0, 34.0, true, ACCENT);
+    canvas.text_right(&card.start_time.format("%b %-d, %Y").to_string(), right, 118.0, 28.0, false, MUTED);
+    let title = canvas.fit(card.name.as_deref().unwrap_or("Workout"), 64.0, true, content_width);
+    canvas.text(&title, PADDING, 220.0, 64.0, true, TEXT);
+    canvas.text(&format!("@{}", card.username), PADDING, 272.0, 32.0, false, MUTED);
+
+    // Stats
+    let gap = 30.0;
+    let box_width = (content_width - gap * 2.0) / 3.0;
+    let stats = [
+        (duration(card.duration_minutes), "duration"),
+        (format!("{} {}", thousands(card.total_volume), unit), "volume"),
+        (card.pr_count.to_string(), if card.pr_count == 1 { "PR" } else { "PRs" }),
+    ];
+    for (i, (value, label)) in stats.iter().enumerate() {
+        let x = PADDING + i as f32 * (box_width + gap);
+        canvas.rounded_rect(x, 320.0, box_width, 150.0, 24.0, PANEL);
+        let value = canvas.fit(value, 46.0, true, box_width - 40.0);
+        canvas.text(&value, x + 24.0, 398.0, 46.0, true, TEXT);
+        canvas.text(label, x + 24.0, 442.0, 26.0, false, MUTED);
+    }
+
+    // What follows the exercise list, measured first so the list gets what's left
+    let pr_lines = card.prs.len().min(MAX_PR_LINES);
+    let footer_top = height - PADDING - 40.0;
+    let mut reserved = 0.0;
+    if pr_lines > 0 {
+        reserved += 70.0 + PR_LINE_HEIGHT * pr_lines as f32;
+    }
+    if !card.badges.is_empty() {
+        reserved += 90.0;
+    }
+
+    // Exercises with their top set
+    let mut y = 540.0;
+    canvas.text("EXERCISES", PADDING, y, 26.0, true, MUTED);
+    y += 20.0;
+    let rows = (((footer_top - reserved - y) / ROW_HEIGHT).floor().max(1.0)) as usize;
+    let shown = if card.exercises.len() > rows { rows - 1 } else { card.exercises.len() };
+    for exercise in &card.exercises[..shown] {
+        y += ROW_HEIGHT;
+        let top_set = exercise.top_set.as_ref().map(|top| format!("{} {} × {}", weight(top.weight), unit, top.reps));
+        let mut right_edge = right;
+        if let Some(top) = &exercise.top_set {
+            if top.pr_kind.is_some() {
+                let pr_width = canvas.text_width("PR", 22.0, true) + 22.0 * 1.2;
+                canvas.pill("PR", right - pr_width, y - 30.0, 22.0, ACCENT, BACKGROUND_TOP);
+                right_edge -= pr_width + 16.0;
+            }
+        }
+        let set_width = match &top_set {
+            Some(text) => canvas.text_right(text, right_edge, y, 32.0, true, TEXT),
+            None => 0.0,
+        };
+        let name = canvas.fit(&exercise.name, 32.0, false, right_edge - PADDING - set_width - 30.0);
+        canvas.text(&name, PADDING, y, 32.0, false, TEXT);
+    }
+    if shown < card.exercises.len() {
+        y += ROW_HEIGHT;
+        canvas.text(&format!("+{} more", card.exercises.len() - shown), PADDING, y, 30.0, false, MUTED);
+    }
+
+    // Personal records
+    if pr_lines > 0 {
+        y += 70.0;
+        canvas.text("PERSONAL RECORDS", PADDING, y, 26.0, true, ACCENT);
+        for pr in &card.prs[..pr_lines] {
+            y += PR_LINE_HEIGHT;
+            let label = if pr.kind == "1rm" { "heaviest" } else { "rep PR" };
+            let detail = format!("{} {} × {} · {}", weight(pr.weight), unit, pr.reps, label);
+            let detail_width = canvas.text_right(&detail, right, y, 30.0, false, MUTED);
+            let name = canvas.fit(&format!("★ {}", pr.exercise_name), 30.0, true, content_width - detail_width - 30.0);
+            canvas.text(&name, PADDING, y, 30.0, true, TEXT);
+        }
+    }
+
+    // Badges, as many as fit on one line
+    if !card.badges.is_empty() {
+        y += 40.0;
+        let mut x = PADDING;
+        for (i, badge) in card.badges.iter().enumerate() {
+            let remaining = card.badges.len() - i;
+            let badge_width = canvas.text_width(badge, 24.0, true) + 24.0 * 1.2;
+            let more_width = canvas.text_width("+00", 24.0, true) + 24.0 * 1.2 + 12.0;
+            let fits = x + badge_width <= right && (remaining == 1 || x + badge_width + more_width <= right);
+            if !fits {
+                canvas.pill(&format!("+{}", remaining), x, y, 24.0, PANEL, MUTED);
+                break;
+            }
+            x += canvas.pill(badge, x, y, 24.0, PANEL, TEXT) + 12.0;
+        }
+    }
+
+    // Footer: where to find the lifter
+    let handles: Vec<String> = [
+        card.instagram_handle.as_deref().map(|h| format!("IG @{}", h.trim_start_matches('@'))),
+        card.twitter_handle.as_deref().map(|h| format!("X @{}", h.trim_start_matches('@'))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let footer_y = height - PADDING + 10.0;
+    if !handles.is_empty() {
+        canvas.text(&handles.join("   "), PADDING, footer_y, 28.0, false, MUTED);
+    }
+    canvas.text_right("titanlift", right, footer_y, 28.0, true, ACCENT);
+
+    canvas.pixmap.encode_png().map_err(|e| e.to_string())
+}
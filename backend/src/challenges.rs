@@ -0,0 +1,340 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Challenge;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChallengeMetric {
+    Reps,
+    Volume, // kg
+    Sets,
+    Workouts,
+}
+
+impl ChallengeMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChallengeMetric::Reps => "reps",
+            ChallengeMetric::Volume => "volume",
+            ChallengeMetric::Sets => "sets",
+            ChallengeMetric::Workouts => "workouts",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "reps" => Some(ChallengeMetric::Reps),
+            "volume" => Some(ChallengeMetric::Volume),
+            "sets" => Some(ChallengeMetric::Sets),
+            "workouts" => Some(ChallengeMetric::Workouts),
+            _ => None,
+        }
+    }
+
+    /// Aggregate over the working sets of one participant and day.
+    fn sql_amount(self) -> &'static str {
+        match self {
+            ChallengeMetric::Reps => "SUM(s.reps)",
+            ChallengeMetric::Volume => "SUM(s.weight_kg * s.reps)",
+            ChallengeMetric::Sets => "COUNT(s.id)",
+            ChallengeMetric::Workouts => "COUNT(DISTINCT w.id)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChallengePeriod {
+    #[default]
+    Total, // Goal (if any) applies to the whole challenge
+    Daily, // Goal must be reached on every day of the challenge
+}
+
+impl ChallengePeriod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChallengePeriod::Total => "total",
+            ChallengePeriod::Daily => "daily",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "total" => Some(ChallengePeriod::Total),
+            "daily" => Some(ChallengePeriod::Daily),
+            _ => None,
+        }
+    }
+}
+
+/// Calendar days covered by the challenge (UTC), counting partial days.
+pub fn challenge_days(challenge: &Challenge) -> i64 {
+    let last = challenge.ends_at - chrono::Duration::nanoseconds(1);
+    (last.date_naive() - challenge.starts_at.date_naive()).num_days() + 1
+}
+
+#[derive(Debug, Serialize)]
+pub struct Standing {
+    pub user_id: Uuid,
+    pub username: String,
+    pub total: f64,         // Metric summed over the challenge
+    pub days_completed: i64, // Days the daily goal was reached
+    pub progress: f64,      // What the challenge is scored on: total, or days for daily goals
+    pub target: Option<f64>,
+    pub percent: Option<f64>,
+    pub rank: i64,
+    pub completed: bool,    // Reached the goal (goal challenges only)
+}
+
+#[derive(sqlx::FromRow)]
+struct StandingRow {
+    user_id: Uuid,
+    username: String,
+    total: f64,
+    days_completed: i64,
+}
+
+/// Current standings of the joined participants, computed from their logged
+/// working sets within the challenge window. Best first.
+pub async fn standings(db: &PgPool, challenge: &Challenge) -> Result<Vec<Standing>, sqlx::Error> {
+    let metric = ChallengeMetric::parse(&challenge.metric).unwrap_or(ChallengeMetric::Reps);
+    let period = ChallengePeriod::parse(&challenge.period).unwrap_or_default();
+
+    let sql = format!(
+        r#"
+        WITH daily AS (
+            SELECT w.user_id, DATE(s.created_at) as day, ({amount})::float8 as amount
+            FROM sets s
+            JOIN workouts w ON s.workout_id = w.id
+            JOIN challenge_participants p ON p.user_id = w.user_id AND p.challenge_id = $1 AND p.status = 'joined'
+            WHERE s.set_type = 'working'
+              AND s.created_at >= $2 AND s.created_at < $3
+              AND ($4::uuid IS NULL OR s.exercise_id = $4)
+            GROUP BY w.user_id, DATE(s.created_at)
+        )
+        SELECT
+            p.user_id,
+            u.username,
+            COALESCE(SUM(d.amount), 0)::float8 as total,
+            COUNT(d.day) FILTER (WHERE d.amount >= $5) as days_completed
+        FROM challenge_participants p
+        JOIN users u ON p.user_id = u.id
+        LEFT JOIN daily d ON d.user_id = p.user_id
+        WHERE p.challenge_id = $1 AND p.status = 'joined'
+        GROUP BY p.user_id, u.username
+        "#,
+        amount = metric.sql_amount()
+    );
+
+    let rows = sqlx::query_as::<_, StandingRow>(&sql)
+        .bind(challenge.id)
+        .bind(challenge.starts_at)
+        .bind(challenge.ends_at)
+        .bind(challenge.exercise_id)
+        .bind(challenge.goal.unwrap_or(f64::MAX))
+        .fetch_all(db)
+        .await?;
+
+    let target = match (period, challenge.goal) {
+        (ChallengePeriod::Daily, Some(_)) => Some(challenge_days(challenge) as f64),
+        (_, goal) => goal,
+    };
+
+    let mut standings: Vec<Standing> = rows.into_iter()
+        .map(|row| {
+            let progress = match period {
+                ChallengePeriod::Daily => row.days_completed as f64,
+                ChallengePeriod::Total => row.total,
+            };
+            Standing {
+                user_id: row.user_id,
+                username: row.username,
+                total: row.total,
+                days_completed: row.days_completed,
+                progress,
+                target,
+                percent: target.map(|t| (progress / t * 100.0).min(100.0)),
+                rank: 0,
+                completed: target.is_some_and(|t| progress >= t),
+            }
+        })
+        .collect();
+
+    standings.sort_by(|a, b| {
+        b.progress.total_cmp(&a.progress)
+            .then(b.total.total_cmp(&a.total))
+            .then(a.username.cmp(&b.username))
+    });
+
+    // Competition ranking: ties share a rank
+    for i in 0..standings.len() {
+        standings[i].rank = if i > 0 && standings[i].progress == standings[i - 1].progress {
+            standings[i - 1].rank
+        } else {
+            i as i64 + 1
+        };
+    }
+
+    Ok(standings)
+}
+
+/// Winners of a finished challenge: everyone who reached the goal, or the
+/// leaders of a goal-less challenge provided they logged anything.
+pub fn winners(challenge: &Challenge, standings: &[Standing]) -> Vec<Uuid> {
+    if challenge.goal.is_some() {
+        standings.iter().filter(|s| s.completed).map(|s| s.user_id).collect()
+    } else {
+        standings.iter().filter(|s| s.rank == 1 && s.progress > 0.0).map(|s| s.user_id).collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct FinalizedChallenge {
+    pub challenge: Challenge,
+    pub winners: Vec<Uuid>,
+}
+
+/// Freeze the standings of a challenge past its end date, mark the winners and
+/// award them the challenge badge. Returns `None` if the challenge is still
+/// running or was already finalized.
+pub async fn finalize(db: &PgPool, challenge_id: Uuid, now: DateTime<Utc>) -> Result<Option<FinalizedChallenge>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    // Claim it so concurrent finalizers don't award twice
+    let challenge = sqlx::query_as::<_, Challenge>(
+        "UPDATE challenges SET finalized_at = $2 WHERE id = $1 AND finalized_at IS NULL AND ends_at <= $2 RETURNING *"
+    )
+    .bind(challenge_id)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(challenge) = challenge else {
+        return Ok(None);
+    };
+
+    let standings = standings(db, &challenge).await?;
+    let winners = winners(&challenge, &standings);
+
+    for standing in &standings {
+        let is_winner = winners.contains(&standing.user_id);
+        sqlx::query(
+            "UPDATE challenge_participants SET final_progress = $3, final_rank = $4, is_winner = $5 WHERE challenge_id = $1 AND user_id = $2"
+        )
+        .bind(challenge.id)
+        .bind(standing.user_id)
+        .bind(standing.progress)
+        .bind(standing.rank as i32)
+        .bind(is_winner)
+        .execute(&mut *tx)
+        .await?;
+
+        if is_winner {
+            sqlx::query("INSERT INTO user_badges (user_id, badge_name, earned_at, source) VALUES ($1, $2, $3, 'challenge')")
+                .bind(standing.user_id)
+                .bind(&challenge.badge_name)
+                .bind(challenge.ends_at)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(Some(FinalizedChallenge { challenge, winners }))
+}
+
+/// Finalize every challenge whose end date has passed.
+pub async fn finalize_due(db: &PgPool, now: DateTime<Utc>) -> Result<Vec<FinalizedChallenge>, sqlx::Error> {
+    let due = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM challenges WHERE finalized_at IS NULL AND ends_at <= $1 ORDER BY ends_at"
+    )
+    .bind(now)
+    .fetch_all(db)
+    .await?;
+
+    let mut finalized = Vec::new();
+    for id in due {
+        if let Some(done) = finalize(db, id, now).await? {
+            finalized.push(done);
+        }
+    }
+    Ok(finalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn challenge(starts_at: DateTime<Utc>, ends_at: DateTime<Utc>, goal: Option<f64>) -> Challenge {
+        Challenge {
+            id: Uuid::nil(),
+            creator_id: Uuid::nil(),
+            name: "March reps".to_string(),
+            description: None,
+            metric: "reps".to_string(),
+            exercise_id: None,
+            goal,
+            period: "total".to_string(),
+            starts_at,
+            ends_at,
+            badge_name: "March reps".to_string(),
+            finalized_at: None,
+            created_at: starts_at,
+            group_id: None,
+        }
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, day, hour, 0, 0).unwrap()
+    }
+
+    fn standing(rank: i64, progress: f64, completed: bool) -> Standing {
+        Standing {
+            user_id: Uuid::new_v4(),
+            username: String::new(),
+            total: progress,
+            days_completed: 0,
+            progress,
+            target: None,
+            percent: None,
+            rank,
+            completed,
+        }
+    }
+
+    #[test]
+    fn days_end_exclusive_at_midnight() {
+        assert_eq!(challenge_days(&challenge(at(1, 0), at(8, 0), None)), 7);
+    }
+
+    #[test]
+    fn partial_days_count() {
+        assert_eq!(challenge_days(&challenge(at(1, 10), at(2, 9), None)), 2);
+        assert_eq!(challenge_days(&challenge(at(1, 10), at(1, 12), None)), 1);
+    }
+
+    #[test]
+    fn tied_leaders_all_win() {
+        let standings = [standing(1, 50.0, false), standing(1, 50.0, false), standing(3, 10.0, false)];
+        let winners = winners(&challenge(at(1, 0), at(8, 0), None), &standings);
+        assert_eq!(winners, vec![standings[0].user_id, standings[1].user_id]);
+    }
+
+    #[test]
+    fn nobody_wins_without_logging_anything() {
+        let standings = [standing(1, 0.0, false), standing(1, 0.0, false)];
+        assert!(winners(&challenge(at(1, 0), at(8, 0), None), &standings).is_empty());
+    }
+
+    #[test]
+    fn goal_challenges_reward_everyone_who_reached_it() {
+        let standings = [standing(1, 120.0, true), standing(2, 100.0, true), standing(3, 90.0, false)];
+        let winners = winners(&challenge(at(1, 0), at(8, 0), Some(100.0)), &standings);
+        assert_eq!(winners, vec![standings[0].user_id, standings[1].user_id]);
+    }
+}
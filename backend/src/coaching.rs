@@ -0,0 +1,187 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{Workout, WorkoutTemplate};
+
+/// What an athlete lets one coach do.
+#[derive(Debug, Clone, Copy, Serialize, sqlx::FromRow)]
+pub struct Grant {
+    pub can_view_workouts: bool,
+    pub can_edit_templates: bool,
+}
+
+/// Grant `coach_id` access to `athlete_id`'s training, or change what they
+/// may do. Returns `false` if the coach doesn't exist.
+pub async fn grant(db: &PgPool, athlete_id: Uuid, coach_id: Uuid, grant: Grant) -> Result<bool, sqlx::Error> {
+    let granted = sqlx::query(
+        r#"
+        INSERT INTO coaching (coach_id, athlete_id, can_view_workouts, can_edit_templates)
+        SELECT u.id, $2, $3, $4 FROM users u WHERE u.id = $1
+        ON CONFLICT (coach_id, athlete_id) DO UPDATE SET
+            can_view_workouts = EXCLUDED.can_view_workouts,
+            can_edit_templates = EXCLUDED.can_edit_templates,
+            updated_at = NOW()
+        "#
+    )
+    .bind(coach_id)
+    .bind(athlete_id)
+    .bind(grant.can_view_workouts)
+    .bind(grant.can_edit_templates)
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    Ok(granted > 0)
+}
+
+/// End a coaching relationship, from either side.
+pub async fn end(db: &PgPool, coach_id: Uuid, athlete_id: Uuid) -> Result<bool, sqlx::Error> {
+    let ended = sqlx::query("DELETE FROM coaching WHERE coach_id = $1 AND athlete_id = $2")
+        .bind(coach_id)
+        .bind(athlete_id)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+    Ok(ended > 0)
+}
+
+pub async fn get_grant(db: &PgPool, coach_id: Uuid, athlete_id: Uuid) -> Result<Option<Grant>, sqlx::Error> {
+    sqlx::query_as::<_, Grant>(
+        "SELECT can_view_workouts, can_edit_templates FROM coaching WHERE coach_id = $1 AND athlete_id = $2"
+    )
+    .bind(coach_id)
+    .bind(athlete_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Whether `user_id` may read `athlete_id`'s workouts and sets: their own, or
+/// as a coach with read access.
+pub async fn can_view_workouts(db: &PgPool, user_id: Uuid, athlete_id: Uuid) -> Result<bool, sqlx::Error> {
+    if user_id == athlete_id {
+        return Ok(true);
+    }
+    Ok(get_grant(db, user_id, athlete_id).await?.is_some_and(|g| g.can_view_workouts))
+}
+
+/// Whether `user_id` may change a template: its owner, or a coach of the
+/// owner with template access. `None` if the template doesn't exist.
+pub async fn can_edit_template(db: &PgPool, user_id: Uuid, template_id: Uuid) -> Result<Option<bool>, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT t.user_id = $2 OR EXISTS (
+            SELECT 1 FROM coaching c
+            WHERE c.coach_id = $2 AND c.athlete_id = t.user_id AND c.can_edit_templates
+        )
+        FROM workout_templates t
+        WHERE t.id = $1
+        "#
+    )
+    .bind(template_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CoachingLink {
+    pub user_id: Uuid, // The coach or the athlete, depending on the list
+    pub username: String,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub grant: Grant,
+    pub since: DateTime<Utc>,
+}
+
+/// Coaches of `athlete_id`.
+pub async fn coaches(db: &PgPool, athlete_id: Uuid) -> Result<Vec<CoachingLink>, sqlx::Error> {
+    sqlx::query_as::<_, CoachingLink>(
+        r#"
+        SELECT u.id as user_id, u.username, c.can_view_workouts, c.can_edit_templates, c.created_at as since
+        FROM coaching c
+        JOIN users u ON c.coach_id = u.id
+        WHERE c.athlete_id = $1
+        ORDER BY u.username
+        "#
+    )
+    .bind(athlete_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Athletes coached by `coach_id`.
+pub async fn athletes(db: &PgPool, coach_id: Uuid) -> Result<Vec<CoachingLink>, sqlx::Error> {
+    sqlx::query_as::<_, CoachingLink>(
+        r#"
+        SELECT u.id as user_id, u.username, c.can_view_workouts, c.can_edit_templates, c.created_at as since
+        FROM coaching c
+        JOIN users u ON c.athlete_id = u.id
+        WHERE c.coach_id = $1
+        ORDER BY u.username
+        "#
+    )
+    .bind(coach_id)
+    .fetch_all(db)
+    .await
+}
+
+/// An athlete's workouts, newest first.
+pub async fn workouts(db: &PgPool, athlete_id: Uuid, limit: i64, offset: i64) -> Result<Vec<Workout>, sqlx::Error> {
+    sqlx::query_as::<_, Workout>(
+        r#"
+        SELECT * FROM workouts
+        WHERE user_id = $1
+        ORDER BY COALESCE(start_time, created_at) DESC, id
+        LIMIT $2 OFFSET $3
+        "#
+    )
+    .bind(athlete_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await
+}
+
+/// Copy a template with its exercises to `athlete_id`, marked as assigned by
+/// `coach_id`.
+pub async fn assign_template(
+    db: &PgPool,
+    template_id: Uuid,
+    coach_id: Uuid,
+    athlete_id: Uuid,
+) -> Result<WorkoutTemplate, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let template = sqlx::query_as::<_, WorkoutTemplate>(
+        r#"
+        INSERT INTO workout_templates (user_id, name, description, assigned_by)
+        SELECT $2, name, description, $3 FROM workout_templates WHERE id = $1
+        RETURNING *
+        "#
+    )
+    .bind(template_id)
+    .bind(athlete_id)
+    .bind(coach_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO template_exercises
+            (template_id, exercise_id, order_index, target_sets, target_reps, target_weight_kg, target_weight_entered, target_weight_unit)
+        SELECT $2, exercise_id, order_index, target_sets, target_reps, target_weight_kg, target_weight_entered, target_weight_unit
+        FROM template_exercises
+        WHERE template_id = $1
+        "#
+    )
+    .bind(template_id)
+    .bind(template.id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(template)
+}
@@ -0,0 +1,273 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    achievements,
+    units::{self, WeightUnit},
+};
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CompareSet {
+    pub exercise_id: Uuid,
+    pub exercise_name: String,
+    pub weight_kg: f32,
+    pub reps: i32,
+    pub rpe: Option<f32>,
+    pub entered_weight: Option<f64>,
+    pub entered_unit: Option<String>,
+}
+
+/// Working sets of a workout, exercises in the order they were first done.
+pub async fn working_sets(db: &PgPool, workout_id: Uuid) -> Result<Vec<CompareSet>, sqlx::Error> {
+    sqlx::query_as::<_, CompareSet>(
+        r#"
+        SELECT s.exercise_id, e.name as exercise_name, s.weight_kg, s.reps, s.rpe, s.entered_weight, s.entered_unit
+        FROM sets s
+        JOIN exercises e ON s.exercise_id = e.id
+        WHERE s.workout_id = $1 AND s.set_type = 'working'
+        ORDER BY MIN(s.created_at) OVER (PARTITION BY s.exercise_id), s.created_at
+        "#
+    )
+    .bind(workout_id)
+    .fetch_all(db)
+    .await
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ComparedWorkout {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: Option<String>,
+    pub template_id: Option<Uuid>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+pub async fn get_workout(db: &PgPool, workout_id: Uuid) -> Result<Option<ComparedWorkout>, sqlx::Error> {
+    sqlx::query_as::<_, ComparedWorkout>(
+        "SELECT id, user_id, name, template_id, COALESCE(start_time, created_at) as start_time, end_time FROM workouts WHERE id = $1"
+    )
+    .bind(workout_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// The last finished workout from the same template before this one.
+pub async fn previous_instance(db: &PgPool, workout: &ComparedWorkout) -> Result<Option<ComparedWorkout>, sqlx::Error> {
+    let Some(template_id) = workout.template_id else { return Ok(None) };
+
+    sqlx::query_as::<_, ComparedWorkout>(
+        r#"
+        SELECT id, user_id, name, template_id, COALESCE(start_time, created_at) as start_time, end_time
+        FROM workouts
+        WHERE user_id = $1 AND template_id = $2 AND id <> $3
+          AND end_time IS NOT NULL
+          AND COALESCE(start_time, created_at) < $4
+        ORDER BY COALESCE(start_time, created_at) DESC
+        LIMIT 1
+        "#
+    )
+    .bind(workout.user_id)
+    .bind(template_id)
+    .bind(workout.id)
+    .bind(workout.start_time)
+    .fetch_optional(db)
+    .await
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SetLine {
+    pub weight: f64,
+    pub reps: i32,
+    pub rpe: Option<f32>,
+}
+
+/// One exercise in one of the two workouts.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExerciseStats {
+    pub sets: Vec<SetLine>,
+    pub set_count: usize,
+    pub total_reps: i64,
+    pub best_set: Option<SetLine>, // Heaviest, then most reps
+    pub volume: f64,
+    pub e1rm: Option<f64>, // Best Epley estimate over sets of up to 10 reps
+}
+
+/// `a` minus `b`; absent when the exercise is missing from either workout.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExerciseDelta {
+    pub set_count: i64,
+    pub total_reps: i64,
+    pub best_weight: Option<f64>,
+    pub best_reps: Option<i64>,
+    pub volume: f64,
+    pub e1rm: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExerciseComparison {
+    pub exercise_id: Uuid,
+    pub name: String,
+    pub a: Option<ExerciseStats>,
+    pub b: Option<ExerciseStats>,
+    pub delta: Option<ExerciseDelta>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TotalsDelta {
+    pub volume_a: f64,
+    pub volume_b: f64,
+    pub volume: f64,
+    pub set_count: i64,
+    pub total_reps: i64,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn stats(sets: &[&CompareSet], unit: WeightUnit) -> ExerciseStats {
+    let line = |s: &CompareSet| SetLine {
+        weight: units::display(s.weight_kg as f64, s.entered_weight, s.entered_unit.as_deref(), unit),
+        reps: s.reps,
+        rpe: s.rpe,
+    };
+
+    let volume_kg: f64 = sets.iter().map(|s| s.weight_kg as f64 * s.reps as f64).sum();
+    let best = sets.iter().copied().max_by(|x, y| {
+        (x.weight_kg, x.reps).partial_cmp(&(y.weight_kg, y.reps)).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let e1rm_kg = sets
+        .iter()
+        .filter_map(|s| achievements::estimated_1rm(s.weight_kg as f64, s.reps))
+        .fold(None, |best: Option<f64>, e| Some(best.map_or(e, |b| b.max(e))));
+
+    ExerciseStats {
+        sets: sets.iter().map(|s| line(s)).collect(),
+        set_count: sets.len(),
+        total_reps: sets.iter().map(|s| s.reps as i64).sum(),
+        best_set: best.map(line),
+        volume: round2(unit.convert_kg(volume_kg)),
+        e1rm: e1rm_kg.map(|kg| round2(unit.convert_kg(kg))),
+    }
+}
+
+fn delta(a: &ExerciseStats, b: &ExerciseStats) -> ExerciseDelta {
+    let both = |x: Option<f64>, y: Option<f64>| Some(round2(x? - y?));
+    ExerciseDelta {
+        set_count: a.set_count as i64 - b.set_count as i64,
+        total_reps: a.total_reps - b.total_reps,
+        best_weight: both(a.best_set.as_ref().map(|s| s.weight), b.best_set.as_ref().map(|s| s.weight)),
+        best_reps: a.best_set.as_ref().zip(b.best_set.as_ref()).map(|(x, y)| x.reps as i64 - y.reps as i64),
+        volume: round2(a.volume - b.volume),
+        e1rm: both(a.e1rm, b.e1rm),
+    }
+}
+
+/// Per-exercise comparison of two workouts' working sets, `a` against `b`.
+/// Exercises follow `a`'s order, then those only in `b`.
+pub fn compare(a: &[CompareSet], b: &[CompareSet], unit: WeightUnit) -> (Vec<ExerciseComparison>, TotalsDelta) {
+    let mut order: Vec<(Uuid, &str)> = Vec::new();
+    for set in a.iter().chain(b) {
+        if !order.iter().any(|(id, _)| *id == set.exercise_id) {
+            order.push((set.exercise_id, &set.exercise_name));
+        }
+    }
+
+    let of = |sets: &'_ [CompareSet], exercise_id: Uuid| -> Option<ExerciseStats> {
+        let sets: Vec<&CompareSet> = sets.iter().filter(|s| s.exercise_id == exercise_id).collect();
+        (!sets.is_empty()).then(|| stats(&sets, unit))
+    };
+
+    let exercises: Vec<ExerciseComparison> = order
+        .into_iter()
+        .map(|(exercise_id, name)| {
+            let (stats_a, stats_b) = (of(a, exercise_id), of(b, exercise_id));
+            let delta = match (&stats_a, &stats_b) {
+                (Some(x), Some(y)) => Some(delta(x, y)),
+                _ => None,
+            };
+            ExerciseComparison { exercise_id, name: name.to_string(), a: stats_a, b: stats_b, delta }
+        })
+        .collect();
+
+    let volume = |sets: &[CompareSet]| round2(unit.convert_kg(sets.iter().map(|s| s.weight_kg as f64 * s.reps as f64).sum()));
+    let reps = |sets: &[CompareSet]| sets.iter().map(|s| s.reps as i64).sum::<i64>();
+    let totals = TotalsDelta {
+        volume_a: volume(a),
+        volume_b: volume(b),
+        volume: round2(volume(a) - volume(b)),
+        set_count: a.len() as i64 - b.len() as i64,
+        total_reps: reps(a) - reps(b),
+    };
+
+    (exercises, totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(exercise_id: Uuid, name: &str, weight_kg: f32, reps: i32) -> CompareSet {
+        CompareSet {
+            exercise_id,
+            exercise_name: name.to_string(),
+            weight_kg,
+            reps,
+            rpe: None,
+            entered_weight: None,
+            entered_unit: None,
+        }
+    }
+
+    #[test]
+    fn compares_exercise_by_exercise() {
+        let (squat, bench, deadlift) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let a = [set(squat, "Squat", 100.0, 5), set(bench, "Bench", 60.0, 8), set(squat, "Squat", 100.0, 5)];
+        let b = [
+            set(squat, "Squat", 95.0, 5),
+            set(squat, "Squat", 95.0, 5),
+            set(squat, "Squat", 95.0, 5),
+            set(deadlift, "Deadlift", 140.0, 5),
+        ];
+
+        let (exercises, totals) = compare(&a, &b, WeightUnit::Kg);
+
+        let names: Vec<&str> = exercises.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Squat", "Bench", "Deadlift"]);
+
+        let delta = exercises[0].delta.as_ref().unwrap();
+        assert_eq!((delta.set_count, delta.total_reps, delta.best_reps), (-1, -5, Some(0)));
+        assert_eq!(delta.best_weight, Some(5.0));
+        assert_eq!(delta.volume, -425.0);
+        assert_eq!(delta.e1rm, Some(5.84));
+
+        // Only in one of the two
+        assert!(exercises[1].b.is_none() && exercises[1].delta.is_none());
+        assert!(exercises[2].a.is_none() && exercises[2].delta.is_none());
+
+        assert_eq!((totals.volume_a, totals.volume_b, totals.volume), (1480.0, 2125.0, -645.0));
+        assert_eq!((totals.set_count, totals.total_reps), (-1, -2));
+    }
+
+    #[test]
+    fn weights_are_shown_as_entered() {
+        let squat = Uuid::new_v4();
+        let mut entered = set(squat, "Squat", WeightUnit::Lb.to_kg(225.0) as f32, 5);
+        entered.entered_weight = Some(225.0);
+        entered.entered_unit = Some("lb".to_string());
+
+        let (exercises, _) = compare(&[entered], &[set(squat, "Squat", 100.0, 5)], WeightUnit::Lb);
+        assert_eq!(exercises[0].a.as_ref().unwrap().best_set.as_ref().unwrap().weight, 225.0);
+        assert_eq!(exercises[0].b.as_ref().unwrap().best_set.as_ref().unwrap().weight, 220.46);
+    }
+
+    #[test]
+    fn empty_workouts_compare_to_nothing() {
+        let (exercises, totals) = compare(&[], &[], WeightUnit::Kg);
+        assert!(exercises.is_empty());
+        assert_eq!((totals.volume, totals.set_count, totals.total_reps), (0.0, 0, 0));
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream,
+};
+use uuid::Uuid;
+
+use crate::{
+    notifications::{Delivery, Notification},
+    reactions::Target,
+};
+
+// Events buffered per user before their slowest connection starts missing them
+const HUB_CAPACITY: usize = 256;
+
+/// Something the user's other devices should know about right away.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    SetLogged {
+        workout_id: Uuid,
+        set_id: Uuid,
+        exercise_id: Uuid,
+        weight_kg: f32,
+        reps: i32,
+        set_type: String,
+    },
+    SetDeleted {
+        workout_id: Uuid,
+        set_id: Uuid,
+    },
+    WorkoutFinished {
+        workout_id: Uuid,
+        badges: Vec<String>,
+    },
+    BadgeEarned {
+        workout_id: Option<Uuid>,
+        badge_name: String,
+    },
+    PersonalRecord {
+        workout_id: Uuid,
+        set_id: Uuid,
+        exercise_id: Uuid,
+        kind: String, // "1rm" or "volume"
+        weight_kg: f32,
+        reps: i32,
+    },
+    KudosReceived {
+        target: Target,
+        from_user_id: Uuid,
+    },
+    CommentAdded {
+        target: Target,
+        comment_id: Uuid,
+        parent_id: Option<Uuid>,
+        from_user_id: Uuid,
+    },
+    Notification {
+        notification: Notification,
+    },
+    /// The connection fell behind and `missed` events were dropped; refetch.
+    Resync {
+        missed: u64,
+    },
+}
+
+type Channels = Arc<Mutex<HashMap<Uuid, broadcast::Sender<LiveEvent>>>>;
+
+/// Fan-out of live events to every open connection of the same user. Each user
+/// gets their own channel, opened on first subscribe and dropped with their
+/// last connection, so one busy user can't make others miss events.
+#[derive(Clone)]
+pub struct EventHub {
+    channels: Channels,
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        EventHub { channels: Arc::default() }
+    }
+
+    pub fn publish(&self, user_id: Uuid, event: LiveEvent) {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(sender) = channels.get(&user_id) {
+            // An error means the last connection just went away
+            if sender.send(event).is_err() {
+                channels.remove(&user_id);
+            }
+        }
+    }
+
+    /// Events for `user_id`, from now on. If the connection lags behind, the
+    /// dropped events are replaced by a single [`LiveEvent::Resync`].
+    pub fn subscribe(&self, user_id: Uuid) -> Subscription {
+        let receiver = self.channels
+            .lock()
+            .unwrap()
+            .entry(user_id)
+            .or_insert_with(|| broadcast::channel(HUB_CAPACITY).0)
+            .subscribe();
+
+        Subscription {
+            inner: BroadcastStream::new(receiver),
+            channels: self.channels.clone(),
+            user_id,
+        }
+    }
+
+    /// Users with at least one open connection.
+    pub fn connected_users(&self) -> usize {
+        self.channels.lock().unwrap().len()
+    }
+}
+
+/// One connection's events. Dropping the last one for a user closes their channel.
+pub struct Subscription {
+    inner: BroadcastStream<LiveEvent>,
+    channels: Channels,
+    user_id: Uuid,
+}
+
+impl Stream for Subscription {
+    type Item = LiveEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<LiveEvent>> {
+        Pin::new(&mut self.inner).poll_next(cx).map(|msg| {
+            msg.map(|msg| match msg {
+                Ok(event) => event,
+                Err(BroadcastStreamRecvError::Lagged(missed)) => LiveEvent::Resync { missed },
+            })
+        })
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut channels = self.channels.lock().unwrap();
+        // Our own receiver is still alive here, hence 1
+        if channels.get(&self.user_id).is_some_and(|sender| sender.receiver_count() <= 1) {
+            channels.remove(&self.user_id);
+        }
+    }
+}
+
+/// In-app delivery: a stored notification shows up live on every open device.
+impl Delivery for EventHub {
+    fn deliver(&self, notification: &Notification) {
+        self.publish(notification.user_id, LiveEvent::Notification { notification: notification.clone() });
+    }
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    fn deleted(n: u128) -> LiveEvent {
+        LiveEvent::SetDeleted { workout_id: Uuid::nil(), set_id: Uuid::from_u128(n) }
+    }
+
+    #[tokio::test]
+    async fn busy_user_does_not_lag_others() {
+        let hub = EventHub::new();
+        let (busy, quiet) = (Uuid::new_v4(), Uuid::new_v4());
+        let _busy_stream = hub.subscribe(busy);
+        let mut quiet_stream = hub.subscribe(quiet);
+
+        for n in 0..(HUB_CAPACITY as u128 * 2) {
+            hub.publish(busy, deleted(n));
+        }
+        hub.publish(quiet, deleted(7));
+
+        match quiet_stream.next().await {
+            Some(LiveEvent::SetDeleted { set_id, .. }) => assert_eq!(set_id, Uuid::from_u128(7)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn lagging_connection_gets_resync() {
+        let hub = EventHub::new();
+        let user = Uuid::new_v4();
+        let mut stream = hub.subscribe(user);
+
+        for n in 0..(HUB_CAPACITY as u128 + 10) {
+            hub.publish(user, deleted(n));
+        }
+
+        match stream.next().await {
+            Some(LiveEvent::Resync { missed }) => assert_eq!(missed, 10),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(stream.next().await, Some(LiveEvent::SetDeleted { .. })));
+    }
+
+    #[tokio::test]
+    async fn channel_closes_with_last_connection() {
+        let hub = EventHub::new();
+        let user = Uuid::new_v4();
+        let first = hub.subscribe(user);
+        let second = hub.subscribe(user);
+        assert_eq!(hub.connected_users(), 1);
+
+        drop(first);
+        assert_eq!(hub.connected_users(), 1);
+        drop(second);
+        assert_eq!(hub.connected_users(), 0);
+
+        // Nobody listening: publishing doesn't open a channel
+        hub.publish(user, deleted(1));
+        assert_eq!(hub.connected_users(), 0);
+    }
+}
@@ -0,0 +1,287 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Post, Set},
+    privacy::{self, Field},
+    reactions::ReactionCounts,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostKind {
+    Manual,
+    Workout, // Generated when a workout finishes
+    Pr,      // Generated when a set is a PR
+}
+
+impl PostKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PostKind::Manual => "manual",
+            PostKind::Workout => "workout",
+            PostKind::Pr => "pr",
+        }
+    }
+}
+
+/// The linked workout as shown on a post.
+#[derive(Debug, Serialize)]
+pub struct WorkoutSummary {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration_minutes: Option<f64>, // Once finished
+    pub total_volume_kg: f64, // Working sets only
+    pub set_count: i64,
+    pub exercise_count: i64,
+    pub pr_count: i64,
+    pub calories_burned: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedItem {
+    #[serde(flatten)]
+    pub post: Post,
+    pub username: String,
+    #[serde(flatten)]
+    pub reactions: ReactionCounts,
+    pub workout: Option<WorkoutSummary>,
+}
+
+#[derive(sqlx::FromRow)]
+struct FeedRow {
+    #[sqlx(flatten)]
+    post: Post,
+    username: String,
+    #[sqlx(flatten)]
+    reactions: ReactionCounts,
+    workout_name: Option<String>,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    calories_burned: Option<i32>,
+    total_volume_kg: f64,
+    set_count: i64,
+    exercise_count: i64,
+    pr_count: i64,
+}
+
+impl From<FeedRow> for FeedItem {
+    fn from(row: FeedRow) -> Self {
+        let workout = row.post.workout_id.map(|id| {
+            let duration_minutes = match (row.start_time, row.end_time) {
+                (Some(start), Some(end)) => Some((end - start).num_seconds() as f64 / 60.0),
+                _ => None,
+            };
+            WorkoutSummary {
+                id,
+                name: row.workout_name,
+                start_time: row.start_time,
+                end_time: row.end_time,
+                duration_minutes,
+                total_volume_kg: row.total_volume_kg,
+                set_count: row.set_count,
+                exercise_count: row.exercise_count,
+                pr_count: row.pr_count,
+                calories_burned: row.calories_burned,
+            }
+        });
+        FeedItem { post: row.post, username: row.username, reactions: row.reactions, workout }
+    }
+}
+
+/// Posts joined with their author, reactions as seen by the viewer (`$1`) and
+/// workout summary; `{filter}` is the WHERE clause over `p`. Posts showing what
+/// the author keeps from the viewer are left out, see [`shown_to_viewer`].
+fn feed_sql(filter: &str) -> String {
+    let shown = shown_to_viewer();
+    format!(
+        r#"
+        SELECT
+            p.*,
+            u.username,
+            (SELECT COUNT(*) FROM kudos k WHERE k.post_id = p.id) as kudos_count,
+            (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id AND c.deleted_at IS NULL) as comment_count,
+            EXISTS (SELECT 1 FROM kudos k WHERE k.post_id = p.id AND k.user_id = $1) as has_kudos,
+            w.name as workout_name,
+            COALESCE(w.start_time, w.created_at) as start_time,
+            w.end_time,
+            w.calories_burned,
+            COALESCE(ws.total_volume_kg, 0)::float8 as total_volume_kg,
+            COALESCE(ws.set_count, 0) as set_count,
+            COALESCE(ws.exercise_count, 0) as exercise_count,
+            COALESCE(ws.pr_count, 0) as pr_count
+        FROM posts p
+        JOIN users u ON p.user_id = u.id
+        LEFT JOIN workouts w ON p.workout_id = w.id
+        LEFT JOIN LATERAL (
+            SELECT
+                SUM(s.weight_kg * s.reps) as total_volume_kg,
+                COUNT(s.id) as set_count,
+                COUNT(DISTINCT s.exercise_id) as exercise_count,
+                COUNT(s.id) FILTER (WHERE s.pr_kind IS NOT NULL) as pr_count
+            FROM sets s
+            WHERE s.workout_id = w.id AND s.set_type = 'working'
+        ) ws ON TRUE
+        WHERE ({filter}) AND {shown}
+        ORDER BY p.created_at DESC, p.id DESC
+        "#
+    )
+}
+
+/// Posts outside groups that the viewer (`$1`) gets in their feed: their own
+/// and those of the users they follow.
+const FOLLOWED_POST: &str = r#"
+    p.group_id IS NULL
+    AND (p.user_id = $1 OR p.user_id IN (SELECT following_id FROM follows WHERE follower_id = $1 AND status = 'accepted'))
+"#;
+
+/// Group posts the viewer (`$1`) may see, as a member of the group.
+const MEMBER_POST: &str = r#"
+    EXISTS (
+        SELECT 1 FROM group_members gm
+        WHERE gm.group_id = p.group_id AND gm.user_id = $1 AND gm.status = 'joined'
+    )
+"#;
+
+/// Posts with a workout need the author's workout history visible to the
+/// viewer (`$1`), PR posts their lifts.
+fn shown_to_viewer() -> String {
+    format!(
+        "(p.workout_id IS NULL OR {}) AND (p.kind <> 'pr' OR {})",
+        privacy::can_see_sql(Field::WorkoutHistory, "$1", "p.user_id"),
+        privacy::can_see_sql(Field::Lifts, "$1", "p.user_id"),
+    )
+}
+
+/// Newest posts by `viewer` and the users they follow, leaving out muted users.
+pub async fn feed(db: &PgPool, viewer: Uuid, limit: i64, offset: i64) -> Result<Vec<FeedItem>, sqlx::Error> {
+    let sql = feed_sql(&format!(
+        r#"
+        {FOLLOWED_POST}
+        AND p.user_id NOT IN (SELECT muted_id FROM user_mutes WHERE muter_id = $1)
+        "#
+    ));
+    let rows = sqlx::query_as::<_, FeedRow>(&format!("{sql} LIMIT $2 OFFSET $3"))
+        .bind(viewer)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(db)
+        .await?;
+
+    Ok(rows.into_iter().map(FeedItem::from).collect())
+}
+
+/// Newest posts shared to a group, leaving out users `viewer` muted or is
+/// blocked from either way.
+pub async fn group_feed(
+    db: &PgPool,
+    viewer: Uuid,
+    group_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<FeedItem>, sqlx::Error> {
+    let sql = feed_sql(
+        r#"
+        p.group_id = $4
+        AND p.user_id NOT IN (SELECT muted_id FROM user_mutes WHERE muter_id = $1)
+        AND p.user_id NOT IN (
+            SELECT blocked_id FROM user_blocks WHERE blocker_id = $1
+            UNION
+            SELECT blocker_id FROM user_blocks WHERE blocked_id = $1
+        )
+        "#
+    );
+    let rows = sqlx::query_as::<_, FeedRow>(&format!("{sql} LIMIT $2 OFFSET $3"))
+        .bind(viewer)
+        .bind(limit)
+        .bind(offset)
+        .bind(group_id)
+        .fetch_all(db)
+        .await?;
+
+    Ok(rows.into_iter().map(FeedItem::from).collect())
+}
+
+/// Whether `viewer` may see a post, by the same rules as the feeds: posts
+/// outside groups are for the author and their followers, group posts are for
+/// members only, and neither may show what the author keeps from the viewer.
+pub async fn can_see_post(db: &PgPool, post_id: Uuid, viewer: Uuid) -> Result<bool, sqlx::Error> {
+    let shown = shown_to_viewer();
+    sqlx::query_scalar::<_, bool>(&format!(
+        "SELECT EXISTS (SELECT 1 FROM posts p WHERE p.id = $2 AND (({FOLLOWED_POST}) OR ({MEMBER_POST})) AND {shown})"
+    ))
+    .bind(viewer)
+    .bind(post_id)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn get_item(db: &PgPool, post_id: Uuid, viewer: Uuid) -> Result<Option<FeedItem>, sqlx::Error> {
+    let row = sqlx::query_as::<_, FeedRow>(&feed_sql("p.id = $2"))
+        .bind(viewer)
+        .bind(post_id)
+        .fetch_optional(db)
+        .await?;
+
+    Ok(row.map(FeedItem::from))
+}
+
+/// Post a finished workout, once. Returns the post if one was created.
+pub async fn post_workout_finished(
+    db: &PgPool,
+    user_id: Uuid,
+    workout_id: Uuid,
+    badges: &[String],
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let content = match badges {
+        [] => None,
+        badges => Some(format!("Earned {}", badges.join(", "))),
+    };
+
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO posts (user_id, workout_id, content, kind)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (workout_id) WHERE kind = 'workout' DO NOTHING
+        RETURNING id
+        "#
+    )
+    .bind(user_id)
+    .bind(workout_id)
+    .bind(content)
+    .bind(PostKind::Workout.as_str())
+    .fetch_optional(db)
+    .await
+}
+
+/// Post a PR set, in the unit it was entered in.
+pub async fn post_personal_record(db: &PgPool, user_id: Uuid, set: &Set) -> Result<Uuid, sqlx::Error> {
+    let exercise = sqlx::query_scalar::<_, String>("SELECT name FROM exercises WHERE id = $1")
+        .bind(set.exercise_id)
+        .fetch_one(db)
+        .await?;
+
+    let weight = match (set.entered_weight, set.entered_unit.as_deref()) {
+        (Some(value), Some(unit)) => format!("{} {}", value, unit),
+        _ => format!("{} kg", set.weight_kg),
+    };
+    let content = match set.pr_kind.as_deref() {
+        Some("1rm") => format!("New heaviest {}: {} x {}", exercise, weight, set.reps),
+        _ => format!("Rep PR on {}: {} x {}", exercise, weight, set.reps),
+    };
+
+    sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO posts (user_id, workout_id, set_id, content, kind) VALUES ($1, $2, $3, $4, $5) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(set.workout_id)
+    .bind(set.id)
+    .bind(content)
+    .bind(PostKind::Pr.as_str())
+    .fetch_one(db)
+    .await
+}
@@ -0,0 +1,208 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FollowStatus {
+    Pending,  // Waiting for a private account to accept
+    Accepted,
+}
+
+impl FollowStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FollowStatus::Pending => "pending",
+            FollowStatus::Accepted => "accepted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(FollowStatus::Pending),
+            "accepted" => Some(FollowStatus::Accepted),
+            _ => None,
+        }
+    }
+}
+
+/// Follow `target_id`, or ask to if the account is private, with whether the
+/// follow is new. Following again keeps the existing state. `None` if the
+/// target doesn't exist.
+pub async fn follow(db: &PgPool, follower_id: Uuid, target_id: Uuid) -> Result<Option<(FollowStatus, bool)>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        WITH target AS (SELECT id, is_private FROM users WHERE id = $2)
+        INSERT INTO follows (follower_id, following_id, status, accepted_at)
+        SELECT $1, t.id,
+            CASE WHEN t.is_private THEN 'pending' ELSE 'accepted' END,
+            CASE WHEN t.is_private THEN NULL ELSE NOW() END
+        FROM target t
+        ON CONFLICT (follower_id, following_id) DO UPDATE SET status = follows.status
+        RETURNING status, (xmax = 0) as "inserted!"
+        "#,
+        follower_id,
+        target_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.and_then(|row| Some((FollowStatus::parse(&row.status)?, row.inserted))))
+}
+
+/// Unfollow, or withdraw a pending request.
+pub async fn unfollow(db: &PgPool, follower_id: Uuid, target_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM follows WHERE follower_id = $1 AND following_id = $2", follower_id, target_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Accept a pending request to follow `user_id`. Returns whether there was one.
+pub async fn accept_request(db: &PgPool, user_id: Uuid, follower_id: Uuid) -> Result<bool, sqlx::Error> {
+    let accepted = sqlx::query!(
+        "UPDATE follows SET status = 'accepted', accepted_at = NOW() WHERE follower_id = $1 AND following_id = $2 AND status = 'pending'",
+        follower_id,
+        user_id
+    )
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    Ok(accepted > 0)
+}
+
+/// Reject a pending request to follow `user_id`. Returns whether there was one.
+pub async fn reject_request(db: &PgPool, user_id: Uuid, follower_id: Uuid) -> Result<bool, sqlx::Error> {
+    let rejected = sqlx::query!(
+        "DELETE FROM follows WHERE follower_id = $1 AND following_id = $2 AND status = 'pending'",
+        follower_id,
+        user_id
+    )
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    Ok(rejected > 0)
+}
+
+/// Make an account private or public. Going public accepts every pending request.
+pub async fn set_private(db: &PgPool, user_id: Uuid, is_private: bool) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query!("UPDATE users SET is_private = $2 WHERE id = $1", user_id, is_private)
+        .execute(&mut *tx)
+        .await?;
+
+    if !is_private {
+        sqlx::query!("UPDATE follows SET status = 'accepted', accepted_at = NOW() WHERE following_id = $1 AND status = 'pending'", user_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await
+}
+
+/// Whether `follower_id` is an accepted follower of `user_id`.
+pub async fn is_follower(db: &PgPool, follower_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM follows WHERE follower_id = $1 AND following_id = $2 AND status = 'accepted') as "exists!""#,
+        follower_id,
+        user_id
+    )
+    .fetch_one(db)
+    .await
+}
+
+/// Whether `viewer` may see who `user_id` follows and is followed by: anyone
+/// for public accounts, only the owner and accepted followers for private ones.
+pub async fn can_see_connections(db: &PgPool, viewer: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    if viewer == user_id {
+        return Ok(true);
+    }
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT NOT u.is_private
+            OR EXISTS (SELECT 1 FROM follows f WHERE f.follower_id = $1 AND f.following_id = u.id AND f.status = 'accepted')
+        FROM users u
+        WHERE u.id = $2
+        "#
+    )
+    .bind(viewer)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+    .map(|visible| visible.unwrap_or(false))
+}
+
+/// Users `user_id` follows (accepted only).
+pub async fn following_ids(db: &PgPool, user_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!("SELECT following_id FROM follows WHERE follower_id = $1 AND status = 'accepted'", user_id)
+        .fetch_all(db)
+        .await
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Connection {
+    pub id: Uuid,
+    pub username: String,
+    pub bio: Option<String>,
+    pub since: DateTime<Utc>, // When the follow (or request) was made
+    pub is_following: bool,   // Does the viewer follow this user?
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Direction {
+    Followers,
+    Following,
+    Requests, // Pending requests to follow the user
+}
+
+/// A page of `user_id`'s followers, followings or incoming requests, newest first.
+pub async fn connections(
+    db: &PgPool,
+    user_id: Uuid,
+    direction: Direction,
+    viewer: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<Connection>, i64), sqlx::Error> {
+    // (column matching the user, column of the listed users, status)
+    let (own, other, status) = match direction {
+        Direction::Followers => ("following_id", "follower_id", FollowStatus::Accepted),
+        Direction::Following => ("follower_id", "following_id", FollowStatus::Accepted),
+        Direction::Requests => ("following_id", "follower_id", FollowStatus::Pending),
+    };
+
+    let connections = sqlx::query_as::<_, Connection>(&format!(
+        r#"
+        SELECT
+            u.id,
+            u.username,
+            u.bio,
+            f.created_at as since,
+            EXISTS (SELECT 1 FROM follows v WHERE v.follower_id = $3 AND v.following_id = u.id AND v.status = 'accepted') as is_following
+        FROM follows f
+        JOIN users u ON f.{other} = u.id
+        WHERE f.{own} = $1 AND f.status = $2
+        ORDER BY f.created_at DESC, u.id
+        LIMIT $4 OFFSET $5
+        "#
+    ))
+    .bind(user_id)
+    .bind(status.as_str())
+    .bind(viewer)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM follows WHERE {own} = $1 AND status = $2"))
+        .bind(user_id)
+        .bind(status.as_str())
+        .fetch_one(db)
+        .await?;
+
+    Ok((connections, total))
+}
@@ -0,0 +1,217 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Group;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupRole {
+    Owner,  // Created the group; manages roles
+    Coach,  // Invites and removes members, runs group challenges
+    Member,
+}
+
+impl GroupRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GroupRole::Owner => "owner",
+            GroupRole::Coach => "coach",
+            GroupRole::Member => "member",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "owner" => Some(GroupRole::Owner),
+            "coach" => Some(GroupRole::Coach),
+            "member" => Some(GroupRole::Member),
+            _ => None,
+        }
+    }
+
+    /// Owners and coaches run the group.
+    pub fn is_staff(self) -> bool {
+        matches!(self, GroupRole::Owner | GroupRole::Coach)
+    }
+}
+
+/// Someone's place in a group.
+#[derive(Debug, Clone, Copy)]
+pub struct Membership {
+    pub role: GroupRole,
+    pub joined: bool, // false while only invited
+}
+
+pub async fn create(db: &PgPool, owner_id: Uuid, name: &str, description: Option<&str>) -> Result<Group, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let group = sqlx::query_as::<_, Group>(
+        "INSERT INTO groups (owner_id, name, description) VALUES ($1, $2, $3) RETURNING *"
+    )
+    .bind(owner_id)
+    .bind(name)
+    .bind(description)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO group_members (group_id, user_id, role, status, joined_at) VALUES ($1, $2, 'owner', 'joined', NOW())"
+    )
+    .bind(group.id)
+    .bind(owner_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(group)
+}
+
+pub async fn get(db: &PgPool, group_id: Uuid) -> Result<Option<Group>, sqlx::Error> {
+    sqlx::query_as::<_, Group>("SELECT * FROM groups WHERE id = $1")
+        .bind(group_id)
+        .fetch_optional(db)
+        .await
+}
+
+pub async fn membership(db: &PgPool, group_id: Uuid, user_id: Uuid) -> Result<Option<Membership>, sqlx::Error> {
+    let row = sqlx::query_as::<_, (String, String)>(
+        "SELECT role, status FROM group_members WHERE group_id = $1 AND user_id = $2"
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.and_then(|(role, status)| {
+        Some(Membership { role: GroupRole::parse(&role)?, joined: status == "joined" })
+    }))
+}
+
+/// Users who have joined the group.
+pub async fn member_ids(db: &PgPool, group_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM group_members WHERE group_id = $1 AND status = 'joined'")
+        .bind(group_id)
+        .fetch_all(db)
+        .await
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct GroupMember {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: String,
+    pub status: String, // "invited" or "joined"
+    pub joined_at: Option<DateTime<Utc>>,
+}
+
+/// Everyone in the group, staff first, then invitations last.
+pub async fn members(db: &PgPool, group_id: Uuid) -> Result<Vec<GroupMember>, sqlx::Error> {
+    sqlx::query_as::<_, GroupMember>(
+        r#"
+        SELECT gm.user_id, u.username, gm.role, gm.status, gm.joined_at
+        FROM group_members gm
+        JOIN users u ON gm.user_id = u.id
+        WHERE gm.group_id = $1
+        ORDER BY gm.status = 'invited', CASE gm.role WHEN 'owner' THEN 0 WHEN 'coach' THEN 1 ELSE 2 END, u.username
+        "#
+    )
+    .bind(group_id)
+    .fetch_all(db)
+    .await
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct GroupSummary {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub group: Group,
+    pub my_role: String,
+    pub my_status: String,
+    pub member_count: i64,
+}
+
+/// Groups `user_id` has joined or been invited to.
+pub async fn for_user(db: &PgPool, user_id: Uuid) -> Result<Vec<GroupSummary>, sqlx::Error> {
+    sqlx::query_as::<_, GroupSummary>(
+        r#"
+        SELECT
+            g.*,
+            gm.role as my_role,
+            gm.status as my_status,
+            (SELECT COUNT(*) FROM group_members m WHERE m.group_id = g.id AND m.status = 'joined') as member_count
+        FROM groups g
+        JOIN group_members gm ON gm.group_id = g.id AND gm.user_id = $1
+        ORDER BY gm.status = 'invited' DESC, g.name
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Invite users with `role`. Users already in the group are left as they are.
+/// Returns the users newly invited.
+pub async fn invite(
+    db: &PgPool,
+    group_id: Uuid,
+    invited_by: Uuid,
+    user_ids: &[Uuid],
+    role: GroupRole,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO group_members (group_id, user_id, role, status, invited_by)
+        SELECT $1, invitee, $4, 'invited', $2
+        FROM UNNEST($3::uuid[]) AS invitee
+        JOIN users u ON u.id = invitee
+        ON CONFLICT DO NOTHING
+        RETURNING user_id
+        "#
+    )
+    .bind(group_id)
+    .bind(invited_by)
+    .bind(user_ids)
+    .bind(role.as_str())
+    .fetch_all(db)
+    .await
+}
+
+/// Accept an invitation. Returns whether there was one.
+pub async fn join(db: &PgPool, group_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let joined = sqlx::query(
+        "UPDATE group_members SET status = 'joined', joined_at = NOW() WHERE group_id = $1 AND user_id = $2 AND status = 'invited'"
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    Ok(joined > 0)
+}
+
+/// Leave a group, decline an invitation or remove a member.
+pub async fn remove(db: &PgPool, group_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let removed = sqlx::query("DELETE FROM group_members WHERE group_id = $1 AND user_id = $2")
+        .bind(group_id)
+        .bind(user_id)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+    Ok(removed > 0)
+}
+
+pub async fn set_role(db: &PgPool, group_id: Uuid, user_id: Uuid, role: GroupRole) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query("UPDATE group_members SET role = $3 WHERE group_id = $1 AND user_id = $2")
+        .bind(group_id)
+        .bind(user_id)
+        .bind(role.as_str())
+        .execute(db)
+        .await?
+        .rows_affected();
+
+    Ok(updated > 0)
+}
@@ -0,0 +1,376 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    challenges::{self, ChallengeMetric, ChallengePeriod, Standing},
+    events::LiveEvent,
+    groups,
+    handlers::db_error,
+    models::Challenge,
+    notifications::NotificationKind,
+    AppState,
+};
+
+#[derive(Deserialize)]
+pub struct CreateChallengeRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub metric: ChallengeMetric,
+    pub exercise_id: Option<Uuid>,
+    pub goal: Option<f64>,
+    #[serde(default)]
+    pub period: ChallengePeriod,
+    pub starts_at: Option<DateTime<Utc>>, // Defaults to now
+    pub ends_at: DateTime<Utc>,
+    pub badge_name: Option<String>, // Defaults to the challenge name
+    #[serde(default)]
+    pub invite: Vec<Uuid>,
+    pub group_id: Option<Uuid>, // Invites every member; owners and coaches only
+}
+
+#[derive(Serialize)]
+pub struct ChallengeDetails {
+    #[serde(flatten)]
+    pub challenge: Challenge,
+    pub my_status: Option<String>,
+    pub standings: Vec<Standing>,
+    pub invited: Vec<Uuid>, // Pending invitations
+}
+
+async fn load_challenge(state: &AppState, id: Uuid) -> Result<Challenge, (StatusCode, String)> {
+    sqlx::query_as::<_, Challenge>("SELECT * FROM challenges WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Challenge not found".to_string()))
+}
+
+async fn challenge_details(state: &AppState, id: Uuid, user_id: Uuid) -> Result<ChallengeDetails, (StatusCode, String)> {
+    // Late viewers settle a challenge the background finalizer hasn't reached yet
+    if let Some(done) = challenges::finalize(&state.db, id, Utc::now()).await.map_err(db_error)? {
+        publish_winners(state, &done);
+    }
+
+    let challenge = load_challenge(state, id).await?;
+    let participants = sqlx::query_as::<_, (Uuid, String)>(
+        "SELECT user_id, status FROM challenge_participants WHERE challenge_id = $1"
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    let my_status = participants.iter()
+        .find(|(participant, _)| *participant == user_id)
+        .map(|(_, status)| status.clone());
+    if my_status.is_none() && challenge.creator_id != user_id && !is_group_member(state, &challenge, user_id).await? {
+        return Err((StatusCode::NOT_FOUND, "Challenge not found".to_string()));
+    }
+
+    let standings = challenges::standings(&state.db, &challenge).await.map_err(db_error)?;
+    let invited = participants.into_iter()
+        .filter(|(_, status)| status == "invited")
+        .map(|(participant, _)| participant)
+        .collect();
+
+    Ok(ChallengeDetails { challenge, my_status, standings, invited })
+}
+
+pub fn publish_winners(state: &AppState, done: &challenges::FinalizedChallenge) {
+    for winner in &done.winners {
+        state.events.publish(*winner, LiveEvent::BadgeEarned {
+            workout_id: None,
+            badge_name: done.challenge.badge_name.clone(),
+        });
+        state.notifier.send(*winner, NotificationKind::Badge, None, serde_json::json!({
+            "challenge_id": done.challenge.id,
+            "badge_name": done.challenge.badge_name,
+        }));
+    }
+}
+
+async fn is_group_member(state: &AppState, challenge: &Challenge, user_id: Uuid) -> Result<bool, (StatusCode, String)> {
+    let Some(group_id) = challenge.group_id else {
+        return Ok(false);
+    };
+    let membership = groups::membership(&state.db, group_id, user_id).await.map_err(db_error)?;
+    Ok(membership.is_some_and(|m| m.joined))
+}
+
+/// Group challenges are open to the group's members only.
+fn check_members(members: &[Uuid], user_ids: &[Uuid]) -> Result<(), (StatusCode, String)> {
+    match user_ids.iter().find(|id| !members.contains(id)) {
+        Some(id) => Err((StatusCode::BAD_REQUEST, format!("User {} is not a member of this group", id))),
+        None => Ok(()),
+    }
+}
+
+/// Only followers of the creator can be invited.
+async fn check_followers(state: &AppState, creator_id: Uuid, user_ids: &[Uuid]) -> Result<(), (StatusCode, String)> {
+    let followers = sqlx::query_scalar::<_, Uuid>(
+        "SELECT follower_id FROM follows WHERE following_id = $1 AND follower_id = ANY($2) AND status = 'accepted'"
+    )
+    .bind(creator_id)
+    .bind(user_ids)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    match user_ids.iter().find(|id| !followers.contains(id)) {
+        Some(id) => Err((StatusCode::BAD_REQUEST, format!("User {} does not follow you", id))),
+        None => Ok(()),
+    }
+}
+
+async fn insert_invites<'e, E>(executor: E, challenge_id: Uuid, invited_by: Uuid, user_ids: &[Uuid]) -> Result<(), (StatusCode, String)>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO challenge_participants (challenge_id, user_id, status, invited_by)
+        SELECT $1, invitee, 'invited', $2 FROM UNNEST($3::uuid[]) AS invitee
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .bind(challenge_id)
+    .bind(invited_by)
+    .bind(user_ids)
+    .execute(executor)
+    .await
+    .map_err(db_error)?;
+    Ok(())
+}
+
+pub async fn create_challenge(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<CreateChallengeRequest>,
+) -> Result<Json<ChallengeDetails>, (StatusCode, String)> {
+    let name = payload.name.trim();
+    let starts_at = payload.starts_at.unwrap_or_else(Utc::now);
+
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name is required".to_string()));
+    }
+    if payload.ends_at <= starts_at || payload.ends_at <= Utc::now() {
+        return Err((StatusCode::BAD_REQUEST, "ends_at must be in the future and after starts_at".to_string()));
+    }
+    if payload.goal.is_some_and(|g| g <= 0.0) {
+        return Err((StatusCode::BAD_REQUEST, "goal must be positive".to_string()));
+    }
+    if payload.period == ChallengePeriod::Daily && payload.goal.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Daily challenges need a goal".to_string()));
+    }
+
+    // The reward can't pass for one of the built-in badges
+    let badge_name = payload.badge_name.as_deref().map(str::trim).filter(|b| !b.is_empty()).unwrap_or(name);
+    let taken = sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM badge_definitions WHERE LOWER(name) = LOWER($1))")
+        .bind(badge_name)
+        .fetch_one(&state.db)
+        .await
+        .map_err(db_error)?;
+    if taken {
+        return Err((StatusCode::CONFLICT, format!("'{}' is already a badge; pick another badge_name", badge_name)));
+    }
+
+    let invite: Vec<Uuid> = match payload.group_id {
+        Some(group_id) => {
+            let membership = groups::membership(&state.db, group_id, user_id).await.map_err(db_error)?;
+            if !membership.is_some_and(|m| m.joined && m.role.is_staff()) {
+                return Err((StatusCode::FORBIDDEN, "Only the group's owner and coaches can create group challenges".to_string()));
+            }
+            let members = groups::member_ids(&state.db, group_id).await.map_err(db_error)?;
+            check_members(&members, &payload.invite)?;
+            members.into_iter().filter(|id| *id != user_id).collect()
+        }
+        None => {
+            let invite: Vec<Uuid> = payload.invite.into_iter().filter(|id| *id != user_id).collect();
+            check_followers(&state, user_id, &invite).await?;
+            invite
+        }
+    };
+
+    // The challenge, the creator's place in it and the invites land together
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+
+    let challenge = sqlx::query_as::<_, Challenge>(
+        r#"
+        INSERT INTO challenges (creator_id, name, description, metric, exercise_id, goal, period, starts_at, ends_at, badge_name, group_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING *
+        "#
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(&payload.description)
+    .bind(payload.metric.as_str())
+    .bind(payload.exercise_id)
+    .bind(payload.goal)
+    .bind(payload.period.as_str())
+    .bind(starts_at)
+    .bind(payload.ends_at)
+    .bind(badge_name)
+    .bind(payload.group_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    // The creator takes part
+    sqlx::query("INSERT INTO challenge_participants (challenge_id, user_id, status) VALUES ($1, $2, 'joined')")
+        .bind(challenge.id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    insert_invites(&mut *tx, challenge.id, user_id, &invite).await?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(Json(challenge_details(&state, challenge.id, user_id).await?))
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ChallengeSummary {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub challenge: Challenge,
+    pub my_status: String,
+    pub participants: i64,
+}
+
+/// Challenges the caller takes part in or is invited to, running ones first.
+pub async fn list_challenges(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<ChallengeSummary>>, (StatusCode, String)> {
+    let challenges = sqlx::query_as::<_, ChallengeSummary>(
+        r#"
+        SELECT
+            c.*,
+            p.status as my_status,
+            (SELECT COUNT(*) FROM challenge_participants cp WHERE cp.challenge_id = c.id AND cp.status = 'joined') as participants
+        FROM challenges c
+        JOIN challenge_participants p ON p.challenge_id = c.id AND p.user_id = $1
+        WHERE p.status <> 'declined'
+        ORDER BY c.finalized_at IS NOT NULL, c.ends_at DESC
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(challenges))
+}
+
+pub async fn get_challenge(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ChallengeDetails>, (StatusCode, String)> {
+    Ok(Json(challenge_details(&state, id, user_id).await?))
+}
+
+#[derive(Deserialize)]
+pub struct InviteRequest {
+    pub user_ids: Vec<Uuid>,
+}
+
+pub async fn invite_to_challenge(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<InviteRequest>,
+) -> Result<Json<ChallengeDetails>, (StatusCode, String)> {
+    let challenge = load_challenge(&state, id).await?;
+    if challenge.creator_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "Only the creator can invite".to_string()));
+    }
+    if challenge.finalized_at.is_some() || challenge.ends_at <= Utc::now() {
+        return Err((StatusCode::CONFLICT, "Challenge is over".to_string()));
+    }
+
+    let invite: Vec<Uuid> = payload.user_ids.into_iter().filter(|id| *id != user_id).collect();
+    match challenge.group_id {
+        Some(group_id) => check_members(&groups::member_ids(&state.db, group_id).await.map_err(db_error)?, &invite)?,
+        None => check_followers(&state, user_id, &invite).await?,
+    }
+    insert_invites(&state.db, id, user_id, &invite).await?;
+
+    Ok(Json(challenge_details(&state, id, user_id).await?))
+}
+
+async fn respond_to_invite(state: &AppState, id: Uuid, user_id: Uuid, status: &str) -> Result<ChallengeDetails, (StatusCode, String)> {
+    let challenge = load_challenge(state, id).await?;
+    if challenge.finalized_at.is_some() || challenge.ends_at <= Utc::now() {
+        return Err((StatusCode::CONFLICT, "Challenge is over".to_string()));
+    }
+
+    // Members who joined the group after the challenge started have no invitation yet
+    let sql = if is_group_member(state, &challenge, user_id).await? {
+        r#"
+        INSERT INTO challenge_participants (challenge_id, user_id, status) VALUES ($1, $2, $3)
+        ON CONFLICT (challenge_id, user_id) DO UPDATE SET status = EXCLUDED.status
+        "#
+    } else {
+        "UPDATE challenge_participants SET status = $3 WHERE challenge_id = $1 AND user_id = $2"
+    };
+    let updated = sqlx::query(sql)
+        .bind(id)
+        .bind(user_id)
+        .bind(status)
+        .execute(&state.db)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+    if updated == 0 {
+        return Err((StatusCode::FORBIDDEN, "You were not invited to this challenge".to_string()));
+    }
+
+    challenge_details(state, id, user_id).await
+}
+
+pub async fn join_challenge(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ChallengeDetails>, (StatusCode, String)> {
+    Ok(Json(respond_to_invite(&state, id, user_id, "joined").await?))
+}
+
+/// Decline an invitation, or leave a challenge already joined.
+pub async fn leave_challenge(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ChallengeDetails>, (StatusCode, String)> {
+    Ok(Json(respond_to_invite(&state, id, user_id, "declined").await?))
+}
+
+/// Background task settling challenges shortly after they end.
+pub async fn run_finalizer(state: AppState) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        match challenges::finalize_due(&state.db, Utc::now()).await {
+            Ok(finalized) => {
+                for done in &finalized {
+                    tracing::info!("Finalized challenge {} with {} winner(s)", done.challenge.id, done.winners.len());
+                    publish_winners(&state, done);
+                }
+            }
+            Err(e) => tracing::error!("Failed to finalize challenges: {}", e),
+        }
+    }
+}
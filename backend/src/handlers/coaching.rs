@@ -0,0 +1,188 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    blocks,
+    coaching::{self, CoachingLink, Grant},
+    handlers::db_error,
+    handlers::workouts::SetView,
+    models::{Set, Workout, WorkoutTemplate},
+    units::{self, UnitQuery},
+    AppState,
+};
+
+async fn require_grant(
+    state: &AppState,
+    coach_id: Uuid,
+    athlete_id: Uuid,
+    allowed: impl Fn(&Grant) -> bool,
+) -> Result<(), (StatusCode, String)> {
+    let grant = coaching::get_grant(&state.db, coach_id, athlete_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "You don't coach this athlete".to_string()))?;
+
+    if allowed(&grant) {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "The athlete hasn't given you access to this".to_string()))
+    }
+}
+
+/// Both default to allowed.
+#[derive(Deserialize)]
+pub struct GrantRequest {
+    pub can_view_workouts: Option<bool>,
+    pub can_edit_templates: Option<bool>,
+}
+
+/// Make a user your coach, or change what they may do.
+pub async fn grant_coach(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(coach_id): Path<Uuid>,
+    Json(payload): Json<GrantRequest>,
+) -> Result<Json<Vec<CoachingLink>>, (StatusCode, String)> {
+    if coach_id == user_id {
+        return Err((StatusCode::BAD_REQUEST, "You cannot coach yourself".to_string()));
+    }
+    if blocks::is_blocked(&state.db, user_id, coach_id).await.map_err(db_error)? {
+        return Err((StatusCode::FORBIDDEN, "You cannot add this coach".to_string()));
+    }
+
+    let grant = Grant {
+        can_view_workouts: payload.can_view_workouts.unwrap_or(true),
+        can_edit_templates: payload.can_edit_templates.unwrap_or(true),
+    };
+    if !coaching::grant(&state.db, user_id, coach_id, grant).await.map_err(db_error)? {
+        return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
+    }
+
+    coaching::coaches(&state.db, user_id).await.map(Json).map_err(db_error)
+}
+
+/// Revoke a coach's access.
+pub async fn remove_coach(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(coach_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if coaching::end(&state.db, coach_id, user_id).await.map_err(db_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Not your coach".to_string()))
+    }
+}
+
+/// Stop coaching an athlete.
+pub async fn remove_athlete(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(athlete_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if coaching::end(&state.db, user_id, athlete_id).await.map_err(db_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "You don't coach this athlete".to_string()))
+    }
+}
+
+pub async fn list_coaches(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<CoachingLink>>, (StatusCode, String)> {
+    coaching::coaches(&state.db, user_id).await.map(Json).map_err(db_error)
+}
+
+pub async fn list_athletes(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<CoachingLink>>, (StatusCode, String)> {
+    coaching::athletes(&state.db, user_id).await.map(Json).map_err(db_error)
+}
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// An athlete's workouts, for their coach.
+pub async fn athlete_workouts(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(athlete_id): Path<Uuid>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<Vec<Workout>>, (StatusCode, String)> {
+    require_grant(&state, user_id, athlete_id, |g| g.can_view_workouts).await?;
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+    coaching::workouts(&state.db, athlete_id, limit, offset).await.map(Json).map_err(db_error)
+}
+
+/// The sets of one of an athlete's workouts, in the coach's unit unless `?unit=` says otherwise.
+pub async fn athlete_workout_sets(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path((athlete_id, workout_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<UnitQuery>,
+) -> Result<Json<Vec<SetView>>, (StatusCode, String)> {
+    require_grant(&state, user_id, athlete_id, |g| g.can_view_workouts).await?;
+
+    let sets = sqlx::query_as::<_, Set>(
+        r#"
+        SELECT s.*
+        FROM sets s
+        JOIN workouts w ON s.workout_id = w.id
+        WHERE w.id = $1 AND w.user_id = $2
+        ORDER BY s.created_at
+        "#
+    )
+    .bind(workout_id)
+    .bind(athlete_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    let unit = match query.unit {
+        Some(unit) => unit,
+        None => units::preferred_unit(&state.db, user_id).await,
+    };
+    Ok(Json(sets.into_iter().map(|set| SetView::new(set, unit)).collect()))
+}
+
+#[derive(Deserialize)]
+pub struct AssignTemplateRequest {
+    pub template_id: Uuid, // One of the coach's own templates
+}
+
+/// Give an athlete a copy of one of the coach's templates.
+pub async fn assign_template(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(athlete_id): Path<Uuid>,
+    Json(payload): Json<AssignTemplateRequest>,
+) -> Result<(StatusCode, Json<WorkoutTemplate>), (StatusCode, String)> {
+    require_grant(&state, user_id, athlete_id, |g| g.can_edit_templates).await?;
+
+    let owner = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM workout_templates WHERE id = $1")
+        .bind(payload.template_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(db_error)?;
+    if owner != Some(user_id) {
+        return Err((StatusCode::NOT_FOUND, "Template not found".to_string()));
+    }
+
+    let template = coaching::assign_template(&state.db, payload.template_id, user_id, athlete_id)
+        .await
+        .map_err(db_error)?;
+    Ok((StatusCode::CREATED, Json(template)))
+}
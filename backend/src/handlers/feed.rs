@@ -0,0 +1,129 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    feed::{self, FeedItem, PostKind},
+    groups,
+    handlers::db_error,
+    AppState,
+};
+
+#[derive(Deserialize)]
+pub struct CreatePostRequest {
+    pub content: Option<String>,
+    pub workout_id: Option<Uuid>, // Must be one of the caller's workouts
+    pub media_url: Option<String>,
+    pub group_id: Option<Uuid>, // Share to this group's feed only
+}
+
+pub async fn create_post(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<CreatePostRequest>,
+) -> Result<Json<FeedItem>, (StatusCode, String)> {
+    let content = payload.content.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    let media_url = payload.media_url.as_deref().map(str::trim).filter(|m| !m.is_empty());
+
+    if content.is_none() && media_url.is_none() && payload.workout_id.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "A post needs content, media or a workout".to_string()));
+    }
+
+    if let Some(workout_id) = payload.workout_id {
+        let owned = sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM workouts WHERE id = $1 AND user_id = $2)")
+            .bind(workout_id)
+            .bind(user_id)
+            .fetch_one(&state.db)
+            .await
+            .map_err(db_error)?;
+        if !owned {
+            return Err((StatusCode::NOT_FOUND, "Workout not found".to_string()));
+        }
+    }
+
+    if let Some(group_id) = payload.group_id {
+        let membership = groups::membership(&state.db, group_id, user_id).await.map_err(db_error)?;
+        if !membership.is_some_and(|m| m.joined) {
+            return Err((StatusCode::NOT_FOUND, "Group not found".to_string()));
+        }
+    }
+
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO posts (user_id, workout_id, content, media_url, kind, group_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(payload.workout_id)
+    .bind(content)
+    .bind(media_url)
+    .bind(PostKind::Manual.as_str())
+    .bind(payload.group_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    feed::get_item(&state.db, id, user_id)
+        .await
+        .map_err(db_error)?
+        .map(Json)
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Post vanished after insert".to_string()))
+}
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct FeedPage {
+    pub items: Vec<FeedItem>,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+}
+
+impl FeedPage {
+    // Pages are fetched with one extra row, which tells whether there is another page
+    fn new(mut items: Vec<FeedItem>, limit: i64, offset: i64) -> Self {
+        let has_more = items.len() as i64 > limit;
+        items.truncate(limit as usize);
+        FeedPage { items, limit, offset, has_more }
+    }
+}
+
+/// Newest posts from the caller and the users they follow.
+pub async fn get_feed(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Query(query): Query<FeedQuery>,
+) -> Result<Json<FeedPage>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let items = feed::feed(&state.db, user_id, limit + 1, offset).await.map_err(db_error)?;
+    Ok(Json(FeedPage::new(items, limit, offset)))
+}
+
+/// Newest posts shared to a group the caller is a member of.
+pub async fn get_group_feed(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<FeedQuery>,
+) -> Result<Json<FeedPage>, (StatusCode, String)> {
+    let membership = groups::membership(&state.db, group_id, user_id).await.map_err(db_error)?;
+    if !membership.is_some_and(|m| m.joined) {
+        return Err((StatusCode::NOT_FOUND, "Group not found".to_string()));
+    }
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let items = feed::group_feed(&state.db, user_id, group_id, limit + 1, offset).await.map_err(db_error)?;
+    Ok(Json(FeedPage::new(items, limit, offset)))
+}
@@ -1,22 +1,50 @@
 use axum::{
     extract::{State, Path},
+    http::StatusCode,
     Json,
 };
-use crate::{AppState, models::UserBadge};
+use crate::{
+    AppState,
+    achievements::{self, Lift, MilestoneRule},
+    auth::CurrentUser,
+    badges::{BadgeTier, TierProgress},
+    handlers::privacy::{access_to, require},
+    models::{BadgeDefinition, UserBadge},
+    privacy::Field,
+};
 use uuid::Uuid;
 use serde::Serialize;
 
+/// One trophy in the trophy case: a badge definition and how often the user earned it.
 #[derive(Serialize)]
 pub struct BadgeGroup {
     pub name: String,
+    pub description: Option<String>,
+    pub category: String,
     pub count: i64,
-    pub last_earned_at: chrono::DateTime<chrono::Utc>,
+    pub first_earned_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_earned_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub tier: Option<BadgeTier>, // None until earned once
+    pub next_tier: Option<TierProgress>,
+}
+
+#[derive(sqlx::FromRow)]
+struct BadgeGroupRow {
+    #[sqlx(flatten)]
+    definition: BadgeDefinition,
+    count: i64,
+    first_earned_at: Option<chrono::DateTime<chrono::Utc>>,
+    last_earned_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Badges tell how and how much the user trains, so they go with their activity.
 pub async fn get_user_badges(
     State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
     Path(user_id): Path<Uuid>,
-) -> Json<Vec<UserBadge>> {
+) -> Result<Json<Vec<UserBadge>>, (StatusCode, String)> {
+    require(&access_to(&state, viewer, user_id).await?, Field::Activity)?;
+
     let badges: Vec<UserBadge> = sqlx::query_as!(
         UserBadge,
         "SELECT * FROM user_badges WHERE user_id = $1 ORDER BY earned_at DESC",
@@ -24,7 +52,129 @@ pub async fn get_user_badges(
     )
     .fetch_all(&state.db)
     .await
-    .unwrap_or(vec![]);
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch badges: {}", e)))?;
+
+    Ok(Json(badges))
+}
+
+pub async fn get_trophies(
+    State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Vec<BadgeGroup>>, (StatusCode, String)> {
+    require(&access_to(&state, viewer, user_id).await?, Field::Activity)?;
+
+    // Every active badge, plus retired ones the user already holds
+    let rows = sqlx::query_as::<_, BadgeGroupRow>(
+        r#"
+        SELECT
+            d.*,
+            COUNT(ub.id) as count,
+            MIN(ub.earned_at) as first_earned_at,
+            MAX(ub.earned_at) as last_earned_at
+        FROM badge_definitions d
+        LEFT JOIN user_badges ub ON ub.badge_name = d.name AND ub.user_id = $1 AND ub.source = 'rule'
+        GROUP BY d.id
+        HAVING d.is_active OR COUNT(ub.id) > 0
+        ORDER BY COUNT(ub.id) DESC, MAX(ub.earned_at) DESC NULLS LAST, d.name
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch trophies: {}", e)))?;
+
+    let trophies = rows.into_iter()
+        .map(|row| BadgeGroup {
+            tier: row.definition.tier_for(row.count),
+            next_tier: row.definition.next_tier(row.count),
+            name: row.definition.name,
+            description: row.definition.description,
+            category: row.definition.category,
+            count: row.count,
+            first_earned_at: row.first_earned_at,
+            last_earned_at: row.last_earned_at,
+        })
+        .collect();
+
+    Ok(Json(trophies))
+}
+
+#[derive(Serialize)]
+pub struct LiftSummary {
+    pub lift: Lift,
+    pub best_kg: f64, // Heaviest single or e1RM
+    pub best_ratio: Option<f64>, // Against bodyweight at the time of the lift; hidden with bodyweight
+}
+
+#[derive(Serialize)]
+pub struct MilestoneProgress {
+    pub name: String,
+    pub description: Option<String>,
+    pub earned_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub current: Option<f64>, // Bodyweight multiple, or kg for totals; multiples are hidden with bodyweight
+    pub target: f64,
+    pub percent: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct MilestonesResponse {
+    pub lifts: Vec<LiftSummary>,
+    pub total_kg: f64,
+    pub milestones: Vec<MilestoneProgress>,
+}
+
+/// Lifts and milestone progress, for viewers who may see the user's lifts.
+/// Bodyweight multiples give away bodyweight, so they also need that visible.
+pub async fn get_strength_milestones(
+    State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<MilestonesResponse>, (StatusCode, String)> {
+    let access = access_to(&state, viewer, user_id).await?;
+    require(&access, Field::Lifts)?;
+    let ratios_visible = access.can_see(Field::BodyWeight);
+
+    let db_error = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch milestones: {}", e));
+
+    let strength = achievements::gather_strength(&state.db, user_id, chrono::Utc::now()).await.map_err(db_error)?;
+    let rules = achievements::milestone_rules(&state.db).await.map_err(db_error)?;
+
+    let earned: std::collections::HashMap<String, chrono::DateTime<chrono::Utc>> = sqlx::query_as(
+        "SELECT badge_name, MIN(earned_at) FROM user_badges WHERE user_id = $1 AND source = 'rule' GROUP BY badge_name"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error)?
+    .into_iter()
+    .collect();
+
+    let milestones = rules.into_iter()
+        .map(|(definition, rule)| {
+            let (current, target) = rule.progress(&strength);
+            let visible = ratios_visible || !matches!(rule, MilestoneRule::LiftRatio { .. });
+            MilestoneProgress {
+                earned_at: earned.get(&definition.name).copied(),
+                name: definition.name,
+                description: definition.description,
+                current: Some(current).filter(|_| visible),
+                target,
+                percent: Some(if target > 0.0 { (current / target * 100.0).min(100.0) } else { 100.0 }).filter(|_| visible),
+            }
+        })
+        .collect();
+
+    let lifts = Lift::ALL.into_iter()
+        .filter_map(|lift| {
+            let best = strength.lifts.get(&lift)?;
+            Some(LiftSummary {
+                lift,
+                best_kg: best.best_kg(),
+                best_ratio: Some(best.best_ratio(false)).filter(|_| ratios_visible),
+            })
+        })
+        .collect();
 
-    Json(badges)
+    Ok(Json(MilestonesResponse { lifts, total_kg: strength.total_kg(), milestones }))
 }
@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    blocks,
+    groups::{self, GroupMember, GroupRole, GroupSummary, Membership},
+    handlers::db_error,
+    models::Group,
+    notifications::NotificationKind,
+    AppState,
+};
+
+/// The caller's membership. Groups they aren't in or invited to don't exist for them.
+async fn my_membership(state: &AppState, group_id: Uuid, user_id: Uuid) -> Result<Membership, (StatusCode, String)> {
+    groups::membership(&state.db, group_id, user_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Group not found".to_string()))
+}
+
+async fn require_staff(state: &AppState, group_id: Uuid, user_id: Uuid) -> Result<Membership, (StatusCode, String)> {
+    let membership = my_membership(state, group_id, user_id).await?;
+    if membership.joined && membership.role.is_staff() {
+        Ok(membership)
+    } else {
+        Err((StatusCode::FORBIDDEN, "Only the group's owner and coaches can do this".to_string()))
+    }
+}
+
+#[derive(Serialize)]
+pub struct GroupDetails {
+    #[serde(flatten)]
+    pub group: Group,
+    pub my_role: GroupRole,
+    pub my_status: String,
+    pub members: Vec<GroupMember>,
+}
+
+async fn group_details(state: &AppState, group_id: Uuid, user_id: Uuid) -> Result<GroupDetails, (StatusCode, String)> {
+    let membership = my_membership(state, group_id, user_id).await?;
+    let group = groups::get(&state.db, group_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Group not found".to_string()))?;
+    let members = groups::members(&state.db, group_id).await.map_err(db_error)?;
+
+    Ok(GroupDetails {
+        group,
+        my_role: membership.role,
+        my_status: if membership.joined { "joined" } else { "invited" }.to_string(),
+        members,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct CreateGroupRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+pub async fn create_group(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<CreateGroupRequest>,
+) -> Result<(StatusCode, Json<GroupDetails>), (StatusCode, String)> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name is required".to_string()));
+    }
+    let description = payload.description.as_deref().map(str::trim).filter(|d| !d.is_empty());
+
+    let group = groups::create(&state.db, user_id, name, description).await.map_err(db_error)?;
+    Ok((StatusCode::CREATED, Json(group_details(&state, group.id, user_id).await?)))
+}
+
+/// Groups the caller is in, pending invitations first.
+pub async fn list_groups(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<GroupSummary>>, (StatusCode, String)> {
+    groups::for_user(&state.db, user_id).await.map(Json).map_err(db_error)
+}
+
+pub async fn get_group(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GroupDetails>, (StatusCode, String)> {
+    Ok(Json(group_details(&state, id, user_id).await?))
+}
+
+#[derive(Deserialize)]
+pub struct GroupInviteRequest {
+    pub user_ids: Vec<Uuid>,
+    pub role: Option<GroupRole>, // Defaults to member; only the owner can invite coaches
+}
+
+pub async fn invite_to_group(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<GroupInviteRequest>,
+) -> Result<Json<GroupDetails>, (StatusCode, String)> {
+    let membership = require_staff(&state, id, user_id).await?;
+    let role = payload.role.unwrap_or(GroupRole::Member);
+    match role {
+        GroupRole::Owner => return Err((StatusCode::BAD_REQUEST, "A group has one owner".to_string())),
+        GroupRole::Coach if membership.role != GroupRole::Owner => {
+            return Err((StatusCode::FORBIDDEN, "Only the owner can invite coaches".to_string()));
+        }
+        _ => {}
+    }
+
+    let hidden = blocks::hidden_users(&state.db, user_id).await.map_err(db_error)?;
+    if let Some(blocked) = payload.user_ids.iter().find(|id| hidden.contains(id)) {
+        return Err((StatusCode::BAD_REQUEST, format!("User {} cannot be invited", blocked)));
+    }
+
+    let invited = groups::invite(&state.db, id, user_id, &payload.user_ids, role).await.map_err(db_error)?;
+    for invitee in invited {
+        state.notifier.send(invitee, NotificationKind::GroupInvite, Some(user_id), serde_json::json!({
+            "group_id": id,
+            "role": role,
+        }));
+    }
+
+    Ok(Json(group_details(&state, id, user_id).await?))
+}
+
+/// Accept an invitation.
+pub async fn join_group(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GroupDetails>, (StatusCode, String)> {
+    if !groups::join(&state.db, id, user_id).await.map_err(db_error)? {
+        return Err((StatusCode::NOT_FOUND, "No pending invitation to this group".to_string()));
+    }
+    Ok(Json(group_details(&state, id, user_id).await?))
+}
+
+/// Leave a group or decline an invitation. The owner can't leave their group.
+pub async fn leave_group(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let membership = my_membership(&state, id, user_id).await?;
+    if membership.role == GroupRole::Owner {
+        return Err((StatusCode::CONFLICT, "The owner cannot leave the group".to_string()));
+    }
+    groups::remove(&state.db, id, user_id).await.map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct UpdateRoleRequest {
+    pub role: GroupRole,
+}
+
+/// Make a member a coach or back. Owner only.
+pub async fn update_member_role(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path((id, member_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<UpdateRoleRequest>,
+) -> Result<Json<GroupDetails>, (StatusCode, String)> {
+    let membership = my_membership(&state, id, user_id).await?;
+    if membership.role != GroupRole::Owner {
+        return Err((StatusCode::FORBIDDEN, "Only the owner can change roles".to_string()));
+    }
+    if payload.role == GroupRole::Owner || member_id == user_id {
+        return Err((StatusCode::BAD_REQUEST, "The owner's role cannot be changed".to_string()));
+    }
+
+    if !groups::set_role(&state.db, id, member_id, payload.role).await.map_err(db_error)? {
+        return Err((StatusCode::NOT_FOUND, "Member not found".to_string()));
+    }
+    Ok(Json(group_details(&state, id, user_id).await?))
+}
+
+/// Remove a member or withdraw an invitation. Coaches can only remove members.
+pub async fn remove_member(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path((id, member_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<GroupDetails>, (StatusCode, String)> {
+    let membership = require_staff(&state, id, user_id).await?;
+    let target = groups::membership(&state.db, id, member_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Member not found".to_string()))?;
+
+    let allowed = match target.role {
+        GroupRole::Owner => false,
+        GroupRole::Coach => membership.role == GroupRole::Owner,
+        GroupRole::Member => true,
+    };
+    if !allowed {
+        return Err((StatusCode::FORBIDDEN, "You cannot remove this member".to_string()));
+    }
+
+    groups::remove(&state.db, id, member_id).await.map_err(db_error)?;
+    Ok(Json(group_details(&state, id, user_id).await?))
+}
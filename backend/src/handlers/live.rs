@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{auth::CurrentUser, AppState};
+
+#[derive(Serialize)]
+pub struct LiveToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>, // Connect before then; fetch a new one to reconnect after
+}
+
+/// A token for opening the event stream as the current user.
+pub async fn issue_live_token(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+) -> Json<LiveToken> {
+    let (token, expires_at) = state.live_tokens.issue(user_id, Utc::now());
+    Json(LiveToken { token, expires_at })
+}
+
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    pub token: Option<String>,
+}
+
+/// Server-sent events for the token's user: sets logged/deleted, finished
+/// workouts, badges and PRs from any of their devices. A `resync` event means
+/// the connection fell behind and missed some; the client should refetch.
+pub async fn stream_events(
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, (StatusCode, String)> {
+    let user_id = query.token
+        .and_then(|token| state.live_tokens.verify(&token, Utc::now()))
+        .ok_or((StatusCode::UNAUTHORIZED, "A valid live token is required".to_string()))?;
+
+    let stream = state.events
+        .subscribe(user_id)
+        .map(|event| Event::default().json_data(event));
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
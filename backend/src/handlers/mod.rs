@@ -5,3 +5,20 @@ pub mod templates;
 pub mod profile;
 pub mod gamification;
 pub mod social;
+pub mod tools;
+pub mod sessions;
+pub mod live;
+pub mod challenges;
+pub mod feed;
+pub mod reactions;
+pub mod privacy;
+pub mod notifications;
+pub mod groups;
+pub mod coaching;
+pub mod shares;
+
+use axum::http::StatusCode;
+
+pub fn db_error(e: sqlx::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e))
+}
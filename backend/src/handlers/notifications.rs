@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    handlers::db_error,
+    notifications::{self, Notification},
+    AppState,
+};
+
+#[derive(Deserialize)]
+pub struct NotificationsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    #[serde(default)]
+    pub unread_only: bool,
+}
+
+#[derive(Serialize)]
+pub struct NotificationsPage {
+    pub items: Vec<Notification>,
+    pub unread_count: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+}
+
+/// The caller's inbox, newest first.
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Query(query): Query<NotificationsQuery>,
+) -> Result<Json<NotificationsPage>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    // One extra row tells whether there is another page
+    let mut items = notifications::list(&state.db, user_id, query.unread_only, limit + 1, offset)
+        .await
+        .map_err(db_error)?;
+    let has_more = items.len() as i64 > limit;
+    items.truncate(limit as usize);
+
+    let unread_count = notifications::unread_count(&state.db, user_id).await.map_err(db_error)?;
+
+    Ok(Json(NotificationsPage { items, unread_count, limit, offset, has_more }))
+}
+
+#[derive(Deserialize)]
+pub struct MarkReadRequest {
+    pub ids: Option<Vec<Uuid>>, // Everything when left out
+}
+
+#[derive(Serialize)]
+pub struct UnreadCount {
+    pub unread_count: i64,
+}
+
+pub async fn mark_read(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<MarkReadRequest>,
+) -> Result<Json<UnreadCount>, (StatusCode, String)> {
+    notifications::mark_read(&state.db, user_id, payload.ids.as_deref()).await.map_err(db_error)?;
+    let unread_count = notifications::unread_count(&state.db, user_id).await.map_err(db_error)?;
+    Ok(Json(UnreadCount { unread_count }))
+}
@@ -0,0 +1,98 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    coaching,
+    handlers::db_error,
+    privacy::{self, Access, Audience, Field, PrivacySettings, Visibility},
+    scoring::StrengthProfile,
+    AppState,
+};
+
+/// What `viewer` may see of `owner`'s data. Every handler serving profile data
+/// goes through this. Blocked users don't exist for each other.
+pub async fn access_to(state: &AppState, viewer: Uuid, owner: Uuid) -> Result<Access, (StatusCode, String)> {
+    let access = privacy::access(&state.db, viewer, owner).await.map_err(db_error)?;
+    if access.audience == Audience::Blocked {
+        return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
+    }
+    Ok(access)
+}
+
+pub fn require(access: &Access, field: Field) -> Result<(), (StatusCode, String)> {
+    if access.can_see(field) {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "This information is private".to_string()))
+    }
+}
+
+/// Reading `owner`'s workouts and sets: their own, as a coach with read access,
+/// or through a workout history visible to the viewer.
+pub async fn require_workouts(state: &AppState, viewer: Uuid, owner: Uuid) -> Result<(), (StatusCode, String)> {
+    if coaching::can_view_workouts(&state.db, viewer, owner).await.map_err(db_error)? {
+        return Ok(());
+    }
+    require(&access_to(state, viewer, owner).await?, Field::WorkoutHistory)
+}
+
+/// Only the owner may change their own data.
+pub fn require_owner(viewer: Uuid, owner: Uuid) -> Result<(), (StatusCode, String)> {
+    if viewer == owner {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "You can only change your own profile".to_string()))
+    }
+}
+
+/// Hide the body metrics a strength profile carries along with the lifts.
+pub fn mask_strength(access: &Access, mut strength: StrengthProfile) -> StrengthProfile {
+    strength.bodyweight_kg = access.filter(Field::BodyWeight, strength.bodyweight_kg);
+    strength.sex = access.filter(Field::BodyMetrics, strength.sex);
+    strength
+}
+
+pub async fn get_privacy_settings(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<PrivacySettings>, (StatusCode, String)> {
+    privacy::settings(&state.db, user_id).await.map(Json).map_err(db_error)
+}
+
+/// Fields left out keep their current visibility.
+#[derive(Deserialize)]
+pub struct UpdatePrivacyRequest {
+    pub activity: Option<Visibility>,
+    pub workout_history: Option<Visibility>,
+    pub lifts: Option<Visibility>,
+    pub body_weight: Option<Visibility>,
+    pub body_metrics: Option<Visibility>,
+    pub date_of_birth: Option<Visibility>,
+    pub nutrition: Option<Visibility>,
+}
+
+pub async fn update_privacy_settings(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<UpdatePrivacyRequest>,
+) -> Result<Json<PrivacySettings>, (StatusCode, String)> {
+    let current = privacy::settings(&state.db, user_id).await.map_err(db_error)?;
+    let settings = PrivacySettings {
+        activity: payload.activity.unwrap_or(current.activity),
+        workout_history: payload.workout_history.unwrap_or(current.workout_history),
+        lifts: payload.lifts.unwrap_or(current.lifts),
+        body_weight: payload.body_weight.unwrap_or(current.body_weight),
+        body_metrics: payload.body_metrics.unwrap_or(current.body_metrics),
+        date_of_birth: payload.date_of_birth.unwrap_or(current.date_of_birth),
+        nutrition: payload.nutrition.unwrap_or(current.nutrition),
+    };
+
+    privacy::save_settings(&state.db, user_id, &settings).await.map_err(db_error)?;
+    Ok(Json(settings))
+}
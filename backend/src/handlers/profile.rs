@@ -1,9 +1,19 @@
 use axum::{
     extract::{State, Path},
+    http::StatusCode,
     response::IntoResponse,
     Json,
 };
-use crate::{AppState, models::{User, WeightLog, NutritionLog}};
+use crate::{
+    AppState,
+    auth::CurrentUser,
+    handlers::privacy::{access_to, mask_strength, require, require_owner},
+    privacy::Field,
+    models::{User, WeightLog, NutritionLog},
+    units::{self, EnteredWeight, UnitQuery, WeightUnit},
+    scoring::{self, StrengthBasis, StrengthProfile},
+    xp::{self, LevelProgress},
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, NaiveDate, Utc};
@@ -12,6 +22,8 @@ use chrono::{DateTime, NaiveDate, Utc};
 pub struct PhysicalStatsResponse {
     pub height_cm: Option<f64>,
     pub current_weight_kg: Option<f64>,
+    pub current_weight: Option<f64>, // In unit_system
+    pub unit_system: WeightUnit,
     pub gender: Option<String>,
     pub date_of_birth: Option<NaiveDate>,
     pub activity_level: Option<String>,
@@ -28,18 +40,24 @@ pub struct ActivityLogEntry {
 #[derive(Serialize)]
 pub struct FullUserProfile {
     pub username: String,
-    pub total_workouts: i64,
-    pub total_volume_kg: f64,
     pub join_date: DateTime<Utc>,
-    pub activity_log: Vec<ActivityLogEntry>,
-    pub current_streak: i64,
-    pub max_streak: i64,
+    // Activity, null when private
+    pub total_workouts: Option<i64>,
+    pub total_volume_kg: Option<f64>,
+    pub activity_log: Option<Vec<ActivityLogEntry>>,
+    pub current_streak: Option<i64>,
+    pub max_streak: Option<i64>,
+    pub level: Option<LevelProgress>,
+    pub strength: Option<StrengthProfile>, // Null when lifts are private
 }
 
 #[derive(Deserialize)]
 pub struct UpdateStatsRequest {
     pub height_cm: Option<f64>,
-    pub weight_kg: Option<f64>,
+    pub weight_kg: Option<f64>, // Legacy metric field, used when `weight` is absent
+    pub weight: Option<f64>,
+    pub unit: Option<WeightUnit>, // Unit of `weight`, defaults to unit_system
+    pub unit_system: Option<WeightUnit>, // Preferred display/input unit
     pub gender: Option<String>, // 'male' or 'female'
     pub date_of_birth: Option<NaiveDate>,
     pub activity_level: Option<String>, // 'sedentary', 'light', 'moderate', 'active', 'athlete'
@@ -47,9 +65,18 @@ pub struct UpdateStatsRequest {
 
 pub async fn update_physical_stats(
     State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
     Path(user_id): Path<Uuid>,
     Json(payload): Json<UpdateStatsRequest>,
-) -> Json<PhysicalStatsResponse> {
+) -> Result<Json<PhysicalStatsResponse>, (StatusCode, String)> {
+    require_owner(viewer, user_id)?;
+
+    let preferred = match payload.unit_system {
+        Some(unit) => unit,
+        None => units::preferred_unit(&state.db, user_id).await,
+    };
+    let entered = EnteredWeight::resolve(payload.weight, payload.unit, payload.weight_kg, preferred);
+
     let mut tx = state.db.begin().await.unwrap();
 
     // 1. Update User Table
@@ -58,16 +85,22 @@ pub async fn update_physical_stats(
         UPDATE users 
         SET height_cm = COALESCE($1, height_cm),
             current_weight_kg = COALESCE($2, current_weight_kg),
-            gender = COALESCE($3, gender),
-            date_of_birth = COALESCE($4, date_of_birth),
-            activity_level = COALESCE($5, activity_level)
-        WHERE id = $6
+            current_weight_entered = COALESCE($3, current_weight_entered),
+            current_weight_unit = COALESCE($4, current_weight_unit),
+            gender = COALESCE($5, gender),
+            date_of_birth = COALESCE($6, date_of_birth),
+            activity_level = COALESCE($7, activity_level),
+            unit_system = COALESCE($8, unit_system)
+        WHERE id = $9
         "#,
         payload.height_cm,
-        payload.weight_kg,
+        entered.map(|e| e.kg()),
+        entered.map(|e| e.value),
+        entered.map(|e| e.unit.as_str()),
         payload.gender,
         payload.date_of_birth,
         payload.activity_level,
+        payload.unit_system.map(WeightUnit::as_str),
         user_id
     )
     .execute(&mut *tx)
@@ -75,11 +108,13 @@ pub async fn update_physical_stats(
     .unwrap();
 
     // 2. If weight changed, log in weight_logs
-    if let Some(weight) = payload.weight_kg {
+    if let Some(weight) = entered {
         let _ = sqlx::query!(
-            "INSERT INTO weight_logs (user_id, weight_kg) VALUES ($1, $2)",
+            "INSERT INTO weight_logs (user_id, weight_kg, entered_weight, entered_unit) VALUES ($1, $2, $3, $4)",
             user_id,
-            weight
+            weight.kg(),
+            weight.value,
+            weight.unit.as_str()
         )
         .execute(&mut *tx)
         .await
@@ -88,14 +123,21 @@ pub async fn update_physical_stats(
 
     tx.commit().await.unwrap();
 
+    // Bodyweight and sex feed the strength scores
+    state.leaderboards.schedule(user_id);
+
     // 3. Return updated stats with BMR/TDEE
-    get_physical_stats(State(state), Path(user_id)).await
+    get_physical_stats(State(state), CurrentUser(viewer), Path(user_id)).await
 }
 
+/// Body stats, each shown as far as the owner's privacy settings allow. BMR
+/// and TDEE give away weight and age, so they need all of them visible.
 pub async fn get_physical_stats(
     State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
     Path(user_id): Path<Uuid>,
-) -> Json<PhysicalStatsResponse> {
+) -> Result<Json<PhysicalStatsResponse>, (StatusCode, String)> {
+    let access = access_to(&state, viewer, user_id).await?;
     let user = sqlx::query_as!(
         User,
         "SELECT * FROM users WHERE id = $1",
@@ -105,6 +147,11 @@ pub async fn get_physical_stats(
     .await
     .unwrap();
 
+    let unit_system = WeightUnit::parse(&user.unit_system).unwrap_or_default();
+    let current_weight = user.current_weight_kg.map(|kg| {
+        units::display(kg, user.current_weight_entered, user.current_weight_unit.as_deref(), unit_system)
+    });
+
     // Calculate BMR (Mifflin-St Jeor)
     let bmr = if let (Some(w), Some(h), Some(dob), Some(gender)) = (
         user.current_weight_kg,
@@ -137,27 +184,44 @@ pub async fn get_physical_stats(
         None
     };
 
-    Json(PhysicalStatsResponse {
-        height_cm: user.height_cm,
-        current_weight_kg: user.current_weight_kg,
-        gender: user.gender,
-        date_of_birth: user.date_of_birth,
-        activity_level: user.activity_level,
-        bmr,
-        tdee,
-    })
+    let derived_visible = [Field::BodyWeight, Field::BodyMetrics, Field::DateOfBirth]
+        .into_iter()
+        .all(|field| access.can_see(field));
+
+    Ok(Json(PhysicalStatsResponse {
+        height_cm: access.filter(Field::BodyMetrics, user.height_cm),
+        current_weight_kg: access.filter(Field::BodyWeight, user.current_weight_kg),
+        current_weight: access.filter(Field::BodyWeight, current_weight),
+        unit_system,
+        gender: access.filter(Field::BodyMetrics, user.gender),
+        date_of_birth: access.filter(Field::DateOfBirth, user.date_of_birth),
+        activity_level: access.filter(Field::BodyMetrics, user.activity_level),
+        bmr: bmr.filter(|_| derived_visible),
+        tdee: tdee.filter(|_| derived_visible),
+    }))
 }
 
 #[derive(Serialize)]
 pub struct WeightHistoryEntry {
     pub date: DateTime<Utc>,
     pub weight_kg: f64,
+    pub weight: f64,
+    pub unit: WeightUnit,
 }
 
 pub async fn get_weight_history(
     State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
     Path(user_id): Path<Uuid>,
-) -> Json<Vec<WeightHistoryEntry>> {
+    axum::extract::Query(query): axum::extract::Query<UnitQuery>,
+) -> Result<Json<Vec<WeightHistoryEntry>>, (StatusCode, String)> {
+    require(&access_to(&state, viewer, user_id).await?, Field::BodyWeight)?;
+
+    let unit = match query.unit {
+        Some(unit) => unit,
+        None => units::preferred_unit(&state.db, user_id).await,
+    };
+
     let history = sqlx::query_as!(
         WeightLog,
         "SELECT * FROM weight_logs WHERE user_id = $1 ORDER BY logged_at ASC",
@@ -170,9 +234,11 @@ pub async fn get_weight_history(
     let res = history.iter().map(|h| WeightHistoryEntry {
         date: h.logged_at,
         weight_kg: h.weight_kg,
+        weight: units::display(h.weight_kg, h.entered_weight, h.entered_unit.as_deref(), unit),
+        unit,
     }).collect();
 
-    Json(res)
+    Ok(Json(res))
 }
 
 #[derive(Deserialize)]
@@ -185,9 +251,11 @@ pub struct LogNutritionRequest {
 
 pub async fn log_nutrition(
     State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
     Path(user_id): Path<Uuid>,
     Json(payload): Json<LogNutritionRequest>,
-) -> Json<NutritionLog> {
+) -> Result<Json<NutritionLog>, (StatusCode, String)> {
+    require_owner(viewer, user_id)?;
     let today = Utc::now().date_naive();
     
     // Log entry (Upsert logic could be better, but insert helps for now)
@@ -218,13 +286,15 @@ pub async fn log_nutrition(
     .await
     .unwrap();
 
-    Json(log)
+    Ok(Json(log))
 }
 
 pub async fn get_nutrition_log(
     State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
     Path(user_id): Path<Uuid>,
-) -> Json<Option<NutritionLog>> {
+) -> Result<Json<Option<NutritionLog>>, (StatusCode, String)> {
+    require(&access_to(&state, viewer, user_id).await?, Field::Nutrition)?;
     let today = Utc::now().date_naive();
     let log = sqlx::query_as!(
         NutritionLog,
@@ -236,13 +306,19 @@ pub async fn get_nutrition_log(
     .await
     .unwrap();
 
-    Json(log)
+    Ok(Json(log))
 }
 
 pub async fn get_full_profile(
     State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
     Path(target_id): Path<Uuid>,
 ) -> impl axum::response::IntoResponse {
+    let access = match access_to(&state, viewer, target_id).await {
+        Ok(access) => access,
+        Err(e) => return e.into_response(),
+    };
+
     // 1. Get basic user info
     let user = sqlx::query!(
         "SELECT username, created_at FROM users WHERE id = $1",
@@ -257,14 +333,14 @@ pub async fn get_full_profile(
         None => return axum::http::StatusCode::NOT_FOUND.into_response(),
     };
 
-    // 2. Get total workouts and volume
+    // 2. Get total workouts and volume (warm-ups excluded)
     let stats = sqlx::query!(
         r#"
         SELECT 
-            COUNT(*) as count,
-            COALESCE(SUM(s.weight_kg * s.reps), 0.0) as volume
+            COUNT(DISTINCT w.id) as count,
+            COALESCE(SUM(s.weight_kg * s.reps), 0)::float8 as volume
         FROM workouts w
-        LEFT JOIN sets s ON w.id = s.workout_id
+        LEFT JOIN sets s ON w.id = s.workout_id AND s.set_type = 'working'
         WHERE w.user_id = $1 AND w.end_time IS NOT NULL
         "#,
         target_id
@@ -279,10 +355,10 @@ pub async fn get_full_profile(
         r#"
         SELECT 
             DATE(w.start_time) as work_date,
-            COALESCE(SUM(s.weight_kg * s.reps), 0.0) as daily_volume
+            COALESCE(SUM(s.weight_kg * s.reps), 0)::float8 as daily_volume
         FROM workouts w
-        LEFT JOIN sets s ON w.id = s.workout_id
-        WHERE w.user_id = $1 AND w.end_time IS NOT NULL
+        LEFT JOIN sets s ON w.id = s.workout_id AND s.set_type = 'working'
+        WHERE w.user_id = $1 AND w.end_time IS NOT NULL AND w.start_time IS NOT NULL
         GROUP BY DATE(w.start_time)
         ORDER BY work_date ASC
         "#,
@@ -293,7 +369,7 @@ pub async fn get_full_profile(
     .unwrap();
 
     let activity_log: Vec<ActivityLogEntry> = activity.into_iter().map(|r| ActivityLogEntry {
-        date: r.work_date.unwrap(), // Date from generated series or truncation should be valid
+        date: r.work_date.unwrap(), // start_time is filtered non-null above
         volume_kg: r.daily_volume.unwrap_or(0.0),
     }).collect();
 
@@ -308,13 +384,43 @@ pub async fn get_full_profile(
         max_streak = 1;
     }
 
+    // 5. Experience level and relative strength
+    let xp = xp::user_xp(&state.db, target_id).await.unwrap_or(0);
+    let strength = scoring::strength_profile(&state.db, target_id, StrengthBasis::E1rm).await
+        .map_err(|e| tracing::error!("Failed to score strength for {}: {}", target_id, e))
+        .ok();
+
+    let activity = access.can_see(Field::Activity);
     Json(FullUserProfile {
         username: user.username,
-        total_workouts: stats.count.unwrap_or(0),
-        total_volume_kg: stats.volume.unwrap_or(0.0),
         join_date: user.created_at,
-        activity_log,
-        current_streak,
-        max_streak,
+        total_workouts: Some(stats.count.unwrap_or(0)).filter(|_| activity),
+        total_volume_kg: Some(stats.volume.unwrap_or(0.0)).filter(|_| activity),
+        activity_log: Some(activity_log).filter(|_| activity),
+        current_streak: Some(current_streak).filter(|_| activity),
+        max_streak: Some(max_streak).filter(|_| activity),
+        level: Some(LevelProgress::new(xp)).filter(|_| activity),
+        strength: access.filter(Field::Lifts, strength).map(|strength| mask_strength(&access, strength)),
     }).into_response()
 }
+
+#[derive(Deserialize)]
+pub struct StrengthQuery {
+    #[serde(default)]
+    pub basis: StrengthBasis,
+}
+
+pub async fn get_strength_scores(
+    State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
+    Path(user_id): Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<StrengthQuery>,
+) -> Result<Json<StrengthProfile>, (StatusCode, String)> {
+    let access = access_to(&state, viewer, user_id).await?;
+    require(&access, Field::Lifts)?;
+
+    scoring::strength_profile(&state.db, user_id, query.basis)
+        .await
+        .map(|strength| Json(mask_strength(&access, strength)))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to score strength: {}", e)))
+}
@@ -0,0 +1,264 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    blocks,
+    coaching,
+    events::LiveEvent,
+    feed,
+    handlers::db_error,
+    models::Comment,
+    notifications::NotificationKind,
+    privacy::{self, Field},
+    reactions::{self, CommentNode, ReactionCounts, Target, MAX_COMMENT_LENGTH},
+    AppState,
+};
+
+/// Owner of the target. Targets of users blocked either way, posts that
+/// wouldn't reach `user_id`'s feeds, workouts in a history hidden from them and
+/// sets of athletes they don't coach don't exist for them.
+async fn target_owner(state: &AppState, target: Target, user_id: Uuid) -> Result<Uuid, (StatusCode, String)> {
+    let not_found = || match target {
+        Target::Post(_) => (StatusCode::NOT_FOUND, "Post not found".to_string()),
+        Target::Workout(_) => (StatusCode::NOT_FOUND, "Workout not found".to_string()),
+        Target::Set(_) => (StatusCode::NOT_FOUND, "Set not found".to_string()),
+    };
+
+    let owner = target.owner(&state.db).await.map_err(db_error)?.ok_or_else(not_found)?;
+    if blocks::is_blocked(&state.db, user_id, owner).await.map_err(db_error)? {
+        return Err(not_found());
+    }
+    let visible = match target {
+        Target::Post(id) => feed::can_see_post(&state.db, id, user_id).await.map_err(db_error)?,
+        Target::Workout(_) => {
+            coaching::can_view_workouts(&state.db, user_id, owner).await.map_err(db_error)?
+                || privacy::access(&state.db, user_id, owner).await.map_err(db_error)?.can_see(Field::WorkoutHistory)
+        }
+        Target::Set(_) => coaching::can_view_workouts(&state.db, user_id, owner).await.map_err(db_error)?,
+    };
+    if !visible {
+        return Err(not_found());
+    }
+    Ok(owner)
+}
+
+fn comment_body(body: &str) -> Result<&str, (StatusCode, String)> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Comment cannot be empty".to_string()));
+    }
+    if body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err((StatusCode::BAD_REQUEST, format!("Comments are limited to {} characters", MAX_COMMENT_LENGTH)));
+    }
+    Ok(body)
+}
+
+async fn give_kudos(state: &AppState, target: Target, user_id: Uuid) -> Result<ReactionCounts, (StatusCode, String)> {
+    let owner = target_owner(state, target, user_id).await?;
+    let new = reactions::give_kudos(&state.db, target, user_id).await.map_err(db_error)?;
+
+    if new && owner != user_id {
+        state.events.publish(owner, LiveEvent::KudosReceived { target, from_user_id: user_id });
+        state.notifier.send(owner, NotificationKind::Kudos, Some(user_id), serde_json::json!({ "target": target }));
+    }
+
+    reactions::counts(&state.db, target, user_id).await.map_err(db_error)
+}
+
+async fn remove_kudos(state: &AppState, target: Target, user_id: Uuid) -> Result<ReactionCounts, (StatusCode, String)> {
+    target_owner(state, target, user_id).await?;
+    reactions::remove_kudos(&state.db, target, user_id).await.map_err(db_error)?;
+    reactions::counts(&state.db, target, user_id).await.map_err(db_error)
+}
+
+pub async fn give_post_kudos(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReactionCounts>, (StatusCode, String)> {
+    Ok(Json(give_kudos(&state, Target::Post(id), user_id).await?))
+}
+
+pub async fn remove_post_kudos(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReactionCounts>, (StatusCode, String)> {
+    Ok(Json(remove_kudos(&state, Target::Post(id), user_id).await?))
+}
+
+pub async fn give_workout_kudos(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReactionCounts>, (StatusCode, String)> {
+    Ok(Json(give_kudos(&state, Target::Workout(id), user_id).await?))
+}
+
+pub async fn remove_workout_kudos(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReactionCounts>, (StatusCode, String)> {
+    Ok(Json(remove_kudos(&state, Target::Workout(id), user_id).await?))
+}
+
+#[derive(Serialize)]
+pub struct CommentsResponse {
+    #[serde(flatten)]
+    pub counts: ReactionCounts,
+    pub comments: Vec<CommentNode>,
+}
+
+async fn list_comments(state: &AppState, target: Target, user_id: Uuid) -> Result<CommentsResponse, (StatusCode, String)> {
+    target_owner(state, target, user_id).await?;
+    let counts = reactions::counts(&state.db, target, user_id).await.map_err(db_error)?;
+    let comments = reactions::threads(&state.db, target).await.map_err(db_error)?;
+    Ok(CommentsResponse { counts, comments })
+}
+
+pub async fn list_post_comments(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CommentsResponse>, (StatusCode, String)> {
+    Ok(Json(list_comments(&state, Target::Post(id), user_id).await?))
+}
+
+pub async fn list_workout_comments(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CommentsResponse>, (StatusCode, String)> {
+    Ok(Json(list_comments(&state, Target::Workout(id), user_id).await?))
+}
+
+/// Comments on one set, between the athlete and their coaches.
+pub async fn list_set_comments(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CommentsResponse>, (StatusCode, String)> {
+    Ok(Json(list_comments(&state, Target::Set(id), user_id).await?))
+}
+
+#[derive(Deserialize)]
+pub struct CommentRequest {
+    pub body: String,
+    pub parent_id: Option<Uuid>, // Reply to this comment
+}
+
+async fn add_comment(
+    state: &AppState,
+    target: Target,
+    user_id: Uuid,
+    payload: CommentRequest,
+) -> Result<Comment, (StatusCode, String)> {
+    let owner = target_owner(state, target, user_id).await?;
+    let body = comment_body(&payload.body)?;
+
+    // Replies stay on the thread of their parent
+    let parent_author = match payload.parent_id {
+        Some(parent_id) => {
+            let parent = reactions::get_comment(&state.db, parent_id)
+                .await
+                .map_err(db_error)?
+                .filter(|parent| Target::of(parent) == Some(target))
+                .ok_or((StatusCode::BAD_REQUEST, "parent_id is not a comment on this thread".to_string()))?;
+            Some(parent.user_id)
+        }
+        None => None,
+    };
+
+    let comment = reactions::add_comment(&state.db, target, user_id, payload.parent_id, body)
+        .await
+        .map_err(db_error)?;
+
+    let mut notify = vec![owner];
+    notify.extend(parent_author);
+    notify.dedup();
+    for recipient in notify.into_iter().filter(|recipient| *recipient != user_id) {
+        state.events.publish(recipient, LiveEvent::CommentAdded {
+            target,
+            comment_id: comment.id,
+            parent_id: comment.parent_id,
+            from_user_id: user_id,
+        });
+        state.notifier.send(recipient, NotificationKind::Comment, Some(user_id), serde_json::json!({
+            "target": target,
+            "comment_id": comment.id,
+            "parent_id": comment.parent_id,
+        }));
+    }
+
+    Ok(comment)
+}
+
+pub async fn add_post_comment(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CommentRequest>,
+) -> Result<(StatusCode, Json<Comment>), (StatusCode, String)> {
+    let comment = add_comment(&state, Target::Post(id), user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+pub async fn add_workout_comment(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CommentRequest>,
+) -> Result<(StatusCode, Json<Comment>), (StatusCode, String)> {
+    let comment = add_comment(&state, Target::Workout(id), user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+pub async fn add_set_comment(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CommentRequest>,
+) -> Result<(StatusCode, Json<Comment>), (StatusCode, String)> {
+    let comment = add_comment(&state, Target::Set(id), user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+#[derive(Deserialize)]
+pub struct EditCommentRequest {
+    pub body: String,
+}
+
+/// Edit one of the caller's own comments.
+pub async fn edit_comment(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<EditCommentRequest>,
+) -> Result<Json<Comment>, (StatusCode, String)> {
+    let body = comment_body(&payload.body)?;
+    reactions::edit_comment(&state.db, id, user_id, body)
+        .await
+        .map_err(db_error)?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Comment not found".to_string()))
+}
+
+/// Delete one of the caller's own comments. Replies to it are kept.
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if reactions::delete_comment(&state.db, id, user_id).await.map_err(db_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Comment not found".to_string()))
+    }
+}
@@ -0,0 +1,262 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    handlers::db_error,
+    models::{PlannedSet, WorkoutSession},
+    AppState,
+};
+
+/// Session state plus the timers resolved against the server clock, so every
+/// device shows the same numbers regardless of its own clock.
+#[derive(Serialize)]
+pub struct SessionView {
+    #[serde(flatten)]
+    pub session: WorkoutSession,
+    pub server_time: DateTime<Utc>,
+    pub is_paused: bool,
+    pub elapsed_seconds: i64, // Training time since start, pauses excluded
+    pub rest_remaining_seconds: Option<i64>,
+}
+
+impl SessionView {
+    pub fn new(session: WorkoutSession, started_at: DateTime<Utc>) -> Self {
+        let now = Utc::now();
+        // While paused the clock is frozen at the moment of pausing
+        let clock = session.paused_at.unwrap_or(now);
+
+        let elapsed_seconds = ((clock - started_at).num_seconds() - session.paused_seconds).max(0);
+        let rest_remaining_seconds = session.rest_ends_at
+            .map(|ends| (ends - clock).num_seconds().max(0));
+
+        SessionView {
+            is_paused: session.paused_at.is_some(),
+            session,
+            server_time: now,
+            elapsed_seconds,
+            rest_remaining_seconds,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SessionWorkout {
+    user_id: Uuid,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+/// Load the session of an unfinished workout owned by `user_id`, creating it on first access.
+/// Returns the session and the moment the workout started.
+pub async fn load_session(
+    db: &PgPool,
+    workout_id: Uuid,
+    user_id: Uuid,
+) -> Result<(WorkoutSession, DateTime<Utc>), (StatusCode, String)> {
+    let workout = sqlx::query_as::<_, SessionWorkout>(
+        "SELECT user_id, start_time, end_time, created_at FROM workouts WHERE id = $1"
+    )
+    .bind(workout_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch workout: {}", e)))?
+    .filter(|w| w.user_id == user_id)
+    .ok_or((StatusCode::NOT_FOUND, "Workout not found".to_string()))?;
+
+    if workout.end_time.is_some() {
+        return Err((StatusCode::CONFLICT, "Workout is already finished".to_string()));
+    }
+
+    sqlx::query("INSERT INTO workout_sessions (workout_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(workout_id)
+        .bind(user_id)
+        .execute(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create session: {}", e)))?;
+
+    let session = sqlx::query_as::<_, WorkoutSession>("SELECT * FROM workout_sessions WHERE workout_id = $1")
+        .bind(workout_id)
+        .fetch_one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch session: {}", e)))?;
+
+    Ok((session, workout.start_time.unwrap_or(workout.created_at)))
+}
+
+pub async fn get_session(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(workout_id): Path<Uuid>,
+) -> Result<Json<SessionView>, (StatusCode, String)> {
+    let (session, started_at) = load_session(&state.db, workout_id, user_id).await?;
+    Ok(Json(SessionView::new(session, started_at)))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateSessionRequest {
+    pub version: Option<i32>, // If given, the update is rejected when another device wrote first
+    pub current_exercise_id: Option<Uuid>,
+    pub next_set: Option<PlannedSet>,
+}
+
+pub async fn update_session_plan(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(workout_id): Path<Uuid>,
+    Json(payload): Json<UpdateSessionRequest>,
+) -> Result<Json<SessionView>, (StatusCode, String)> {
+    let (_, started_at) = load_session(&state.db, workout_id, user_id).await?;
+
+    let session = sqlx::query_as::<_, WorkoutSession>(
+        r#"
+        UPDATE workout_sessions
+        SET current_exercise_id = $2, next_set = $3, version = version + 1, updated_at = NOW()
+        WHERE workout_id = $1 AND ($4::int IS NULL OR version = $4)
+        RETURNING *
+        "#
+    )
+    .bind(workout_id)
+    .bind(payload.current_exercise_id)
+    .bind(payload.next_set.map(sqlx::types::Json))
+    .bind(payload.version)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::CONFLICT, "Session was changed on another device".to_string()))?;
+
+    Ok(Json(SessionView::new(session, started_at)))
+}
+
+// Longest rest timer a client can start
+const MAX_REST_SECONDS: i64 = 60 * 60;
+
+#[derive(Deserialize)]
+pub struct StartRestRequest {
+    pub seconds: i64,
+}
+
+pub async fn start_rest(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(workout_id): Path<Uuid>,
+    Json(payload): Json<StartRestRequest>,
+) -> Result<Json<SessionView>, (StatusCode, String)> {
+    if !(1..=MAX_REST_SECONDS).contains(&payload.seconds) {
+        return Err((StatusCode::BAD_REQUEST, format!("seconds must be between 1 and {}", MAX_REST_SECONDS)));
+    }
+
+    let (_, started_at) = load_session(&state.db, workout_id, user_id).await?;
+
+    let now = Utc::now();
+    let ends = now + chrono::Duration::seconds(payload.seconds);
+    let session = sqlx::query_as::<_, WorkoutSession>(
+        r#"
+        UPDATE workout_sessions
+        SET rest_started_at = $2, rest_ends_at = $3, version = version + 1, updated_at = NOW()
+        WHERE workout_id = $1
+        RETURNING *
+        "#
+    )
+    .bind(workout_id)
+    .bind(now)
+    .bind(ends)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(SessionView::new(session, started_at)))
+}
+
+pub async fn stop_rest(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(workout_id): Path<Uuid>,
+) -> Result<Json<SessionView>, (StatusCode, String)> {
+    let (_, started_at) = load_session(&state.db, workout_id, user_id).await?;
+
+    let session = sqlx::query_as::<_, WorkoutSession>(
+        r#"
+        UPDATE workout_sessions
+        SET rest_started_at = NULL, rest_ends_at = NULL, version = version + 1, updated_at = NOW()
+        WHERE workout_id = $1
+        RETURNING *
+        "#
+    )
+    .bind(workout_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(SessionView::new(session, started_at)))
+}
+
+pub async fn pause_session(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(workout_id): Path<Uuid>,
+) -> Result<Json<SessionView>, (StatusCode, String)> {
+    let (session, started_at) = load_session(&state.db, workout_id, user_id).await?;
+
+    if session.paused_at.is_some() {
+        return Ok(Json(SessionView::new(session, started_at)));
+    }
+
+    let session = sqlx::query_as::<_, WorkoutSession>(
+        r#"
+        UPDATE workout_sessions
+        SET paused_at = NOW(), version = version + 1, updated_at = NOW()
+        WHERE workout_id = $1
+        RETURNING *
+        "#
+    )
+    .bind(workout_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(SessionView::new(session, started_at)))
+}
+
+pub async fn resume_session(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(workout_id): Path<Uuid>,
+) -> Result<Json<SessionView>, (StatusCode, String)> {
+    let (_, started_at) = load_session(&state.db, workout_id, user_id).await?;
+
+    // Bank the pause and push a running rest timer back by the same amount, in
+    // one statement so two devices resuming at once can't bank it twice
+    let resumed = sqlx::query_as::<_, WorkoutSession>(
+        r#"
+        UPDATE workout_sessions
+        SET paused_seconds = paused_seconds + GREATEST(EXTRACT(EPOCH FROM NOW() - paused_at), 0)::bigint,
+            rest_ends_at = rest_ends_at + GREATEST(NOW() - paused_at, INTERVAL '0'),
+            paused_at = NULL,
+            version = version + 1,
+            updated_at = NOW()
+        WHERE workout_id = $1 AND paused_at IS NOT NULL
+        RETURNING *
+        "#
+    )
+    .bind(workout_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error)?;
+
+    // Not paused (or another device just resumed it): return it as it is now
+    let session = match resumed {
+        Some(session) => session,
+        None => load_session(&state.db, workout_id, user_id).await?.0,
+    };
+
+    Ok(Json(SessionView::new(session, started_at)))
+}
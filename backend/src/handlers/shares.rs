@@ -0,0 +1,274 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    card,
+    handlers::db_error,
+    handlers::privacy::{access_to, require},
+    privacy::Field,
+    shares::{self, Share},
+    summary::{self, WorkoutCard},
+    AppState,
+};
+
+/// Only the owner manages a workout's link.
+async fn require_owner(state: &AppState, workout_id: Uuid, user_id: Uuid) -> Result<bool, (StatusCode, String)> {
+    let workout = sqlx::query_as::<_, (Uuid, bool)>("SELECT user_id, end_time IS NOT NULL FROM workouts WHERE id = $1")
+        .bind(workout_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(db_error)?;
+
+    match workout {
+        Some((owner, finished)) if owner == user_id => Ok(finished),
+        _ => Err((StatusCode::NOT_FOUND, "Workout not found".to_string())),
+    }
+}
+
+#[derive(Serialize)]
+pub struct ShareLink {
+    pub token: String,
+    pub url: String,       // Public JSON
+    pub card_url: String,  // Public HTML card
+    pub image_url: String, // Public PNG card
+    pub created_at: DateTime<Utc>,
+}
+
+impl ShareLink {
+    fn new(state: &AppState, share: &Share) -> Self {
+        let token = state.signer.token(share.id);
+        ShareLink {
+            url: format!("/api/shared/{}", token),
+            card_url: format!("/api/shared/{}/card", token),
+            image_url: format!("/api/shared/{}/card.png", token),
+            token,
+            created_at: share.created_at,
+        }
+    }
+}
+
+/// Share a finished workout. Sharing again returns the same link until it's revoked.
+pub async fn share_workout(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ShareLink>, (StatusCode, String)> {
+    if !require_owner(&state, id, user_id).await? {
+        return Err((StatusCode::CONFLICT, "Finish the workout before sharing it".to_string()));
+    }
+    let share = shares::share(&state.db, id, user_id).await.map_err(db_error)?;
+    Ok(Json(ShareLink::new(&state, &share)))
+}
+
+pub async fn get_workout_share(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ShareLink>, (StatusCode, String)> {
+    require_owner(&state, id, user_id).await?;
+    let share = shares::active_share(&state.db, id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "This workout isn't shared".to_string()))?;
+    Ok(Json(ShareLink::new(&state, &share)))
+}
+
+/// Revoke the link. Sharing the workout again makes a new one.
+pub async fn revoke_workout_share(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_owner(&state, id, user_id).await?;
+    if shares::revoke(&state.db, id).await.map_err(db_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "This workout isn't shared".to_string()))
+    }
+}
+
+/// The card behind a link. Bad signatures, revoked links and deleted
+/// workouts all look the same.
+async fn shared_card(state: &AppState, token: &str) -> Result<WorkoutCard, (StatusCode, String)> {
+    let not_found = || (StatusCode::NOT_FOUND, "Link not found".to_string());
+
+    let share_id = state.signer.verify(token).ok_or_else(not_found)?;
+    let workout_id = shares::shared_workout(&state.db, share_id).await.map_err(db_error)?.ok_or_else(not_found)?;
+    summary::workout_card(&state.db, workout_id).await.map_err(db_error)?.ok_or_else(not_found)
+}
+
+/// Public: no login needed.
+pub async fn get_shared_workout(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<WorkoutCard>, (StatusCode, String)> {
+    shared_card(&state, &token).await.map(Json)
+}
+
+/// Public: the same card as a standalone page.
+pub async fn get_shared_workout_card(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Html<String>, (StatusCode, String)> {
+    let card = shared_card(&state, &token).await?;
+    Ok(Html(render_card(&card, &format!("/api/shared/{}/card.png", token))))
+}
+
+/// Render off the async runtime; a card takes a few tens of milliseconds.
+async fn png_response(card: WorkoutCard) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let png = tokio::task::spawn_blocking(move || card::render_png(&card))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to render image: {}", e)))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to render image: {}", e)))?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "image/png"), (header::CACHE_CONTROL, "private, max-age=300")],
+        png,
+    ))
+}
+
+/// Public: the card as an image, for link previews.
+pub async fn get_shared_workout_image(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    png_response(shared_card(&state, &token).await?).await
+}
+
+/// The card as an image, to post on Instagram or Twitter. Anyone who can see
+/// the owner's workout history can render it.
+pub async fn get_workout_image(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let not_found = || (StatusCode::NOT_FOUND, "Workout not found".to_string());
+
+    let owner = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM workouts WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(not_found)?;
+    let access = access_to(&state, user_id, owner).await?;
+    require(&access, Field::WorkoutHistory)?;
+
+    let card = summary::workout_card(&state.db, id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::CONFLICT, "Finish the workout to get its card".to_string()))?;
+    png_response(card).await
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_card(card: &WorkoutCard, image_url: &str) -> String {
+    let title = escape(card.name.as_deref().unwrap_or("Workout"));
+    let unit = card.unit.as_str();
+
+    let exercises: String = card.exercises.iter().map(|exercise| {
+        let top_set = match &exercise.top_set {
+            Some(top) => format!(
+                "{} {} &times; {}{}",
+                top.weight,
+                unit,
+                top.reps,
+                if top.pr_kind.is_some() { " <span class=\"pr\">PR</span>" } else { "" },
+            ),
+            None => String::new(),
+        };
+        format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{} {}</td></tr>",
+            escape(&exercise.name),
+            exercise.set_count,
+            top_set,
+            exercise.volume,
+            unit,
+        )
+    }).collect();
+
+    let badges = match card.badges.as_slice() {
+        [] => String::new(),
+        badges => format!(
+            "<ul class=\"badges\">{}</ul>",
+            badges.iter().map(|b| format!("<li>{}</li>", escape(b))).collect::<String>(),
+        ),
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title} by {username} · TitanLift</title>
+<meta property="og:title" content="{title} by {username}">
+<meta property="og:description" content="{exercise_count} exercises · {volume} {unit} · {duration} min">
+<meta property="og:image" content="{image_url}">
+<meta name="twitter:card" content="summary_large_image">
+<style>
+body {{ font-family: system-ui, sans-serif; background: #0f172a; color: #e2e8f0; margin: 0; padding: 2rem 1rem; }}
+.card {{ max-width: 36rem; margin: 0 auto; background: #1e293b; border-radius: 1rem; padding: 1.5rem; }}
+h1 {{ margin: 0; font-size: 1.5rem; }}
+.meta {{ color: #94a3b8; margin: 0.25rem 0 1rem; }}
+.stats {{ display: flex; gap: 1.5rem; margin-bottom: 1rem; }}
+.stats b {{ display: block; font-size: 1.25rem; }}
+table {{ width: 100%; border-collapse: collapse; }}
+th, td {{ text-align: left; padding: 0.4rem 0.25rem; border-top: 1px solid #334155; }}
+.pr {{ background: #f59e0b; color: #0f172a; border-radius: 0.25rem; padding: 0 0.3rem; font-size: 0.75rem; font-weight: bold; }}
+.badges {{ list-style: none; padding: 0; display: flex; flex-wrap: wrap; gap: 0.5rem; }}
+.badges li {{ background: #334155; border-radius: 1rem; padding: 0.2rem 0.75rem; }}
+</style>
+</head>
+<body>
+<div class="card">
+<h1>{title}</h1>
+<p class="meta">{username} · {date}</p>
+<div class="stats">
+<div><b>{duration}</b>minutes</div>
+<div><b>{volume} {unit}</b>volume</div>
+<div><b>{pr_count}</b>PRs</div>
+</div>
+<table>
+<tr><th>Exercise</th><th>Sets</th><th>Top set</th><th>Volume</th></tr>
+{exercises}
+</table>
+{badges}
+</div>
+</body>
+</html>
+"#,
+        title = title,
+        username = escape(&card.username),
+        date = card.start_time.format("%B %-d, %Y"),
+        duration = card.duration_minutes.round(),
+        volume = card.total_volume,
+        unit = unit,
+        pr_count = card.pr_count,
+        exercise_count = card.exercises.len(),
+        exercises = exercises,
+        badges = badges,
+        image_url = escape(image_url),
+    )
+}
@@ -4,11 +4,24 @@ use axum::{
     response::IntoResponse,
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::AppState;
+use crate::{
+    auth::CurrentUser,
+    blocks::{self, RestrictedUser},
+    follows::{self, Connection, Direction, FollowStatus},
+    groups,
+    handlers::db_error,
+    handlers::privacy::{access_to, require},
+    leaderboards::{self, BoardKey, LeaderboardEntry, LeaderboardMetric, LeaderboardPeriod},
+    notifications::NotificationKind,
+    privacy::Field,
+    search::{self, Suggestion, UserResult},
+    scoring::StrengthBasis,
+    AppState,
+};
 
 #[derive(Serialize)]
 pub struct FollowStats {
@@ -23,9 +36,11 @@ pub struct UserProfileSocial {
     pub bio: Option<String>,
     pub instagram: Option<String>,
     pub twitter: Option<String>,
+    pub is_private: bool, // Followers need approval
     pub followers: i64,
     pub following: i64,
     pub is_following: bool, // Does the requester follow this user?
+    pub follow_status: Option<String>, // "pending" while the requester's request awaits approval
 }
 
 // Request payload for update profile
@@ -36,55 +51,226 @@ pub struct UpdateSocialProfileRequest {
     pub twitter: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct FollowResponse {
+    pub status: FollowStatus,
+}
+
+/// Follow a user, or request to if their account is private.
 pub async fn follow_user(
     State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
     Path(target_id): Path<Uuid>,
-    // In a real app, user_id comes from Auth middleware
-    // We'll simulate auth via a header or just hardcode for demo if needed
-    // queries override for now
-) -> impl IntoResponse {
-    // HARDCODED DEMO USER ID for now
-    let user_id = Uuid::parse_str("763b9c95-4bae-4044-9d30-7ae513286b37").unwrap();
+) -> Result<Json<FollowResponse>, (StatusCode, String)> {
+    if target_id == user_id {
+        return Err((StatusCode::BAD_REQUEST, "You cannot follow yourself".to_string()));
+    }
+    if blocks::is_blocked(&state.db, user_id, target_id).await.map_err(db_error)? {
+        return Err((StatusCode::FORBIDDEN, "You cannot follow this user".to_string()));
+    }
 
-    let result = sqlx::query!(
-        "INSERT INTO follows (follower_id, following_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
-        user_id,
-        target_id
-    )
-    .execute(&state.db)
-    .await;
+    let (status, new) = follows::follow(&state.db, user_id, target_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
 
-    match result {
-        Ok(_) => StatusCode::OK,
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    if new {
+        let kind = match status {
+            FollowStatus::Accepted => NotificationKind::Follow,
+            FollowStatus::Pending => NotificationKind::FollowRequest,
+        };
+        state.notifier.send(target_id, kind, Some(user_id), serde_json::json!({}));
     }
+
+    Ok(Json(FollowResponse { status }))
 }
 
+/// Unfollow a user, or withdraw a pending request.
 pub async fn unfollow_user(
     State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
     Path(target_id): Path<Uuid>,
-) -> impl IntoResponse {
-    let user_id = Uuid::parse_str("763b9c95-4bae-4044-9d30-7ae513286b37").unwrap();
+) -> Result<StatusCode, (StatusCode, String)> {
+    follows::unfollow(&state.db, user_id, target_id).await.map_err(db_error)?;
+    Ok(StatusCode::OK)
+}
 
-    let result = sqlx::query!(
-        "DELETE FROM follows WHERE follower_id = $1 AND following_id = $2",
-        user_id,
-        target_id
-    )
-    .execute(&state.db)
-    .await;
+#[derive(Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
 
-    match result {
-        Ok(_) => StatusCode::OK,
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+#[derive(Serialize)]
+pub struct ConnectionsPage {
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub users: Vec<Connection>,
+}
+
+async fn connections_page(
+    state: &AppState,
+    user_id: Uuid,
+    direction: Direction,
+    viewer: Uuid,
+    query: PageQuery,
+) -> Result<ConnectionsPage, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let (users, total) = follows::connections(&state.db, user_id, direction, viewer, limit, offset)
+        .await
+        .map_err(db_error)?;
+
+    Ok(ConnectionsPage { total, limit, offset, users })
+}
+
+async fn check_connections_visible(state: &AppState, viewer: Uuid, user_id: Uuid) -> Result<(), (StatusCode, String)> {
+    if follows::can_see_connections(&state.db, viewer, user_id).await.map_err(db_error)? {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "This account is private".to_string()))
+    }
+}
+
+pub async fn list_followers(
+    State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
+    Path(user_id): Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<PageQuery>,
+) -> Result<Json<ConnectionsPage>, (StatusCode, String)> {
+    check_connections_visible(&state, viewer, user_id).await?;
+    Ok(Json(connections_page(&state, user_id, Direction::Followers, viewer, query).await?))
+}
+
+pub async fn list_following(
+    State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
+    Path(user_id): Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<PageQuery>,
+) -> Result<Json<ConnectionsPage>, (StatusCode, String)> {
+    check_connections_visible(&state, viewer, user_id).await?;
+    Ok(Json(connections_page(&state, user_id, Direction::Following, viewer, query).await?))
+}
+
+/// Pending requests to follow the caller.
+pub async fn list_follow_requests(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<PageQuery>,
+) -> Result<Json<ConnectionsPage>, (StatusCode, String)> {
+    Ok(Json(connections_page(&state, user_id, Direction::Requests, user_id, query).await?))
+}
+
+pub async fn accept_follow_request(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(follower_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if follows::accept_request(&state.db, user_id, follower_id).await.map_err(db_error)? {
+        state.notifier.send(follower_id, NotificationKind::FollowAccepted, Some(user_id), serde_json::json!({}));
+        Ok(StatusCode::OK)
+    } else {
+        Err((StatusCode::NOT_FOUND, "No pending request from this user".to_string()))
+    }
+}
+
+pub async fn reject_follow_request(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(follower_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if follows::reject_request(&state.db, user_id, follower_id).await.map_err(db_error)? {
+        Ok(StatusCode::OK)
+    } else {
+        Err((StatusCode::NOT_FOUND, "No pending request from this user".to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PrivacyRequest {
+    pub is_private: bool,
+}
+
+/// Make the caller's account private (followers need approval) or public.
+pub async fn update_privacy(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<PrivacyRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    follows::set_private(&state.db, user_id, payload.is_private).await.map_err(db_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Block a user. Follows between the two are removed and neither sees the other.
+pub async fn block_user(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(target_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if target_id == user_id {
+        return Err((StatusCode::BAD_REQUEST, "You cannot block yourself".to_string()));
+    }
+    blocks::block(&state.db, user_id, target_id).await.map_err(db_error)?;
+    Ok(StatusCode::OK)
+}
+
+pub async fn unblock_user(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(target_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    blocks::unblock(&state.db, user_id, target_id).await.map_err(db_error)?;
+    Ok(StatusCode::OK)
+}
+
+/// Mute a user, hiding their posts from the feed without unfollowing.
+pub async fn mute_user(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(target_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if target_id == user_id {
+        return Err((StatusCode::BAD_REQUEST, "You cannot mute yourself".to_string()));
     }
+    blocks::mute(&state.db, user_id, target_id).await.map_err(db_error)?;
+    Ok(StatusCode::OK)
+}
+
+pub async fn unmute_user(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(target_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    blocks::unmute(&state.db, user_id, target_id).await.map_err(db_error)?;
+    Ok(StatusCode::OK)
+}
+
+pub async fn list_blocked(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<RestrictedUser>>, (StatusCode, String)> {
+    blocks::blocked_users(&state.db, user_id).await.map(Json).map_err(db_error)
+}
+
+pub async fn list_muted(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<Vec<RestrictedUser>>, (StatusCode, String)> {
+    blocks::muted_users(&state.db, user_id).await.map(Json).map_err(db_error)
 }
 
 pub async fn get_profile(
     State(state): State<AppState>,
+    CurrentUser(current_user_id): CurrentUser,
     Path(target_id): Path<Uuid>,
 ) -> impl IntoResponse {
-    let current_user_id = Uuid::parse_str("763b9c95-4bae-4044-9d30-7ae513286b37").unwrap();
+    match blocks::is_blocked(&state.db, current_user_id, target_id).await {
+        Ok(false) => {}
+        Ok(true) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
 
     // Fetch user details + social stats
     let user = sqlx::query_as::<_, UserProfileSocial>(
@@ -95,9 +281,11 @@ pub async fn get_profile(
             u.bio, 
             u.instagram_handle as instagram, 
             u.twitter_handle as twitter,
-            COALESCE((SELECT COUNT(*) FROM follows WHERE following_id = u.id), 0) as followers,
-            COALESCE((SELECT COUNT(*) FROM follows WHERE follower_id = u.id), 0) as following,
-            COALESCE(EXISTS (SELECT 1 FROM follows WHERE follower_id = $2 AND following_id = u.id), false) as is_following
+            u.is_private,
+            COALESCE((SELECT COUNT(*) FROM follows WHERE following_id = u.id AND status = 'accepted'), 0) as followers,
+            COALESCE((SELECT COUNT(*) FROM follows WHERE follower_id = u.id AND status = 'accepted'), 0) as following,
+            COALESCE(EXISTS (SELECT 1 FROM follows WHERE follower_id = $2 AND following_id = u.id AND status = 'accepted'), false) as is_following,
+            (SELECT status FROM follows WHERE follower_id = $2 AND following_id = u.id) as follow_status
         FROM users u
         WHERE u.id = $1
         "#
@@ -138,13 +326,22 @@ pub async fn update_social_profile(
 
 pub async fn get_workout_history(
     State(state): State<AppState>,
+    CurrentUser(viewer): CurrentUser,
     Path(target_id): Path<Uuid>,
 ) -> impl IntoResponse {
+    let access = match access_to(&state, viewer, target_id).await {
+        Ok(access) => access,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = require(&access, Field::WorkoutHistory) {
+        return e.into_response();
+    }
+
     let history = sqlx::query!(
         r#"
         SELECT id, name, start_time, end_time, 
-        COALESCE((SELECT SUM(weight_kg * reps) FROM sets WHERE workout_id = w.id), 0.0) as volume,
-        (SELECT COUNT(*) FROM sets WHERE workout_id = w.id) as exercise_count
+        COALESCE((SELECT SUM(weight_kg * reps) FROM sets WHERE workout_id = w.id AND set_type = 'working'), 0)::float8 as volume,
+        (SELECT COUNT(*) FROM sets WHERE workout_id = w.id AND set_type = 'working') as exercise_count
         FROM workouts w
         WHERE w.user_id = $1 AND w.end_time IS NOT NULL
         ORDER BY w.start_time DESC
@@ -173,122 +370,163 @@ pub async fn get_workout_history(
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderboardScope {
+    #[default]
+    Global,
+    Following, // The caller and everyone they follow
+    Group,     // The members of `group_id`, or the caller and the users in `user_ids`
+}
+
 #[derive(Deserialize)]
 pub struct LeaderboardQuery {
-    pub period: Option<String>,        // "weekly", "monthly", "all" (default)
-    pub muscle_group: Option<String>,  // "chest", "back", "legs", etc.
+    #[serde(default)]
+    pub scope: LeaderboardScope,
+    #[serde(default)]
+    pub metric: LeaderboardMetric,
+    #[serde(default)]
+    pub period: LeaderboardPeriod,
+    pub muscle_group: Option<String>,  // "chest", "back", "legs", etc. (volume only)
+    pub exercise_id: Option<Uuid>,     // Required for e1rm
+    #[serde(default)]
+    pub basis: StrengthBasis,          // For wilks, dots and ipf_gl
+    pub user_ids: Option<String>,      // Comma-separated, for the group scope
+    pub group_id: Option<Uuid>,        // For the group scope; the caller must be a member
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct LeaderboardResponse {
+    pub scope: LeaderboardScope,
+    pub metric: LeaderboardMetric,
+    pub period: LeaderboardPeriod,
+    pub muscle_group: Option<String>,
+    pub exercise_id: Option<Uuid>,
+    pub total: i64, // Ranked users
+    pub limit: i64,
+    pub offset: i64,
+    pub entries: Vec<LeaderboardEntry>,
+    pub me: Option<LeaderboardEntry>, // The caller's row, even outside this page
+    pub snapshot_at: Option<DateTime<Utc>>, // When the ranked values were computed
 }
 
 pub async fn get_leaderboard(
     State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
     axum::extract::Query(query): axum::extract::Query<LeaderboardQuery>,
-) -> impl IntoResponse {
-    let period = query.period.unwrap_or_else(|| "all".to_string());
-    let muscle_group = query.muscle_group;
-
-    // Build date filter based on period
-    let date_filter = match period.as_str() {
-        "weekly" => "AND s.created_at >= NOW() - INTERVAL '7 days'",
-        "monthly" => "AND s.created_at >= NOW() - INTERVAL '30 days'",
-        _ => "", // "all" - no date filter
-    };
+) -> Result<Json<LeaderboardResponse>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
 
-    // Build muscle group filter (use LOWER for case-insensitive match)
-    let muscle_filter = if muscle_group.is_some() {
-        "AND LOWER(e.muscle_group) = LOWER($1)"
-    } else {
-        ""
-    };
+    let muscle_group = query.muscle_group.clone().filter(|m| !m.is_empty());
+    let key = BoardKey::new(query.metric, query.period, muscle_group.as_deref(), query.exercise_id, query.basis)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
 
-    // Dynamic query with optional filters
-    let query_str = format!(
-        r#"
-        SELECT u.id, u.username, 
-        COALESCE(SUM(s.weight_kg * s.reps), 0.0) as total_volume
-        FROM users u
-        LEFT JOIN workouts w ON u.id = w.user_id
-        LEFT JOIN sets s ON w.id = s.workout_id
-        LEFT JOIN exercises e ON s.exercise_id = e.id
-        WHERE 1=1 {} {}
-        GROUP BY u.id, u.username
-        ORDER BY total_volume DESC
-        LIMIT 10
-        "#,
-        date_filter, muscle_filter
-    );
-
-    // Execute query based on whether we have muscle filter
-    let result = if let Some(ref mg) = muscle_group {
-        sqlx::query_as::<_, (uuid::Uuid, String, Option<f64>)>(&query_str)
-            .bind(mg)
-            .fetch_all(&state.db)
-            .await
-    } else {
-        sqlx::query_as::<_, (uuid::Uuid, String, Option<f64>)>(&query_str)
-            .fetch_all(&state.db)
-            .await
+    let users = match query.scope {
+        LeaderboardScope::Global => None,
+        LeaderboardScope::Following => {
+            let mut following = follows::following_ids(&state.db, user_id)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch follows: {}", e)))?;
+            following.push(user_id);
+            Some(following)
+        }
+        LeaderboardScope::Group => match query.group_id {
+            Some(group_id) => {
+                let membership = groups::membership(&state.db, group_id, user_id).await.map_err(db_error)?;
+                if !membership.is_some_and(|m| m.joined) {
+                    return Err((StatusCode::NOT_FOUND, "Group not found".to_string()));
+                }
+                Some(groups::member_ids(&state.db, group_id).await.map_err(db_error)?)
+            }
+            None => {
+                let mut members = query.user_ids.as_deref().unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(Uuid::parse_str)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| (StatusCode::BAD_REQUEST, "user_ids must be comma-separated UUIDs".to_string()))?;
+                members.push(user_id);
+                Some(members)
+            }
+        },
     };
 
-    match result {
-        Ok(recs) => {
-            let response = recs.into_iter().enumerate().map(|(idx, (id, username, total_volume))| {
-                serde_json::json!({
-                    "id": id,
-                    "username": username,
-                    "total_volume_kg": total_volume.unwrap_or(0.0),
-                    "rank": idx + 1,
-                    "period": period,
-                    "muscle_group": muscle_group
-                })
-            }).collect::<Vec<_>>();
-            Json(response).into_response()
-        },
-        Err(e) => {
+    // Nobody sees the users they blocked or were blocked by
+    let excluded = blocks::hidden_users(&state.db, user_id).await.map_err(db_error)?;
+
+    let board = leaderboards::read_board(&state.db, &key, users.as_deref(), &excluded, offset, limit, user_id)
+        .await
+        .map_err(|e| {
             tracing::error!("Leaderboard query failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
-        }
-    }
+            (StatusCode::INTERNAL_SERVER_ERROR, "Leaderboard query failed".to_string())
+        })?;
+
+    Ok(Json(LeaderboardResponse {
+        scope: query.scope,
+        metric: query.metric,
+        period: key.period,
+        muscle_group,
+        exercise_id: query.exercise_id,
+        total: board.total,
+        limit,
+        offset,
+        entries: board.entries,
+        me: board.me,
+        snapshot_at: board.snapshot_at,
+    }))
 }
 
 #[derive(Deserialize)]
 pub struct SearchUserQuery {
     pub q: String,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
+#[derive(Serialize)]
+pub struct SearchPage {
+    pub users: Vec<UserResult>,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+}
+
+/// Find users by username or bio.
 pub async fn search_users(
     State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
     axum::extract::Query(query): axum::extract::Query<SearchUserQuery>,
-) -> impl IntoResponse {
-    let current_user_id = Uuid::parse_str("763b9c95-4bae-4044-9d30-7ae513286b37").unwrap();
-    let search_term = format!("%{}%", query.q);
+) -> Result<Json<SearchPage>, (StatusCode, String)> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "q cannot be empty".to_string()));
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
 
-    let users = sqlx::query!(
-        r#"
-        SELECT u.id, u.username, u.bio, u.instagram_handle, u.twitter_handle,
-        EXISTS (SELECT 1 FROM follows WHERE follower_id = $2 AND following_id = u.id) as is_following
-        FROM users u
-        WHERE u.username ILIKE $1 AND u.id != $2
-        LIMIT 10
-        "#,
-        search_term,
-        current_user_id
-    )
-    .fetch_all(&state.db)
-    .await;
+    // One extra row tells whether there is another page
+    let mut users = search::users(&state.db, user_id, q, limit + 1, offset).await.map_err(db_error)?;
+    let has_more = users.len() as i64 > limit;
+    users.truncate(limit as usize);
 
-    match users {
-        Ok(recs) => {
-            let response = recs.into_iter().map(|r| {
-                // Map to same structure as profile or specific search result
-                serde_json::json!({
-                    "id": r.id,
-                    "username": r.username,
-                    "bio": r.bio,
-                    "is_following": r.is_following.unwrap_or(false)
-                })
-            }).collect::<Vec<_>>();
-            Json(response).into_response()
-        },
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response()
-    }
+    Ok(Json(SearchPage { users, limit, offset, has_more }))
+}
+
+#[derive(Deserialize)]
+pub struct SuggestionsQuery {
+    pub limit: Option<i64>,
+}
+
+/// People to follow, from the caller's follows-of-follows and shared templates.
+pub async fn suggested_users(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<SuggestionsQuery>,
+) -> Result<Json<Vec<Suggestion>>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+    search::suggestions(&state.db, user_id, limit).await.map(Json).map_err(db_error)
 }
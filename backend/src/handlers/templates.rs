@@ -1,9 +1,11 @@
 use axum::{
     extract::{State, Path},
+    http::StatusCode,
     Json,
 };
-use crate::{AppState, models::{WorkoutTemplate, TemplateExercise}};
+use crate::{AppState, auth::CurrentUser, coaching, handlers::{db_error, privacy::require_workouts}, models::{WorkoutTemplate, TemplateExercise, Set}, plates, units::{self, EnteredWeight, WeightUnit}};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use uuid::Uuid;
 
 #[derive(Deserialize)]
@@ -16,7 +18,7 @@ pub struct CreateTemplateRequest {
 pub async fn create_template(
     State(state): State<AppState>,
     Json(payload): Json<CreateTemplateRequest>,
-) -> Json<WorkoutTemplate> {
+) -> Result<Json<WorkoutTemplate>, (StatusCode, String)> {
     let template = sqlx::query_as!(
         WorkoutTemplate,
         "INSERT INTO workout_templates (user_id, name, description) VALUES ($1, $2, $3) RETURNING *",
@@ -26,9 +28,12 @@ pub async fn create_template(
     )
     .fetch_one(&state.db)
     .await
-    .unwrap();
+    .map_err(|e| match &e {
+        sqlx::Error::Database(d) if d.is_foreign_key_violation() => (StatusCode::BAD_REQUEST, "Unknown user".to_string()),
+        _ => db_error(e),
+    })?;
 
-    Json(template)
+    Ok(Json(template))
 }
 
 pub async fn list_templates(
@@ -51,29 +56,100 @@ pub struct AddTemplateExerciseRequest {
     pub order_index: i32,
     pub target_sets: i32,
     pub target_reps: i32,
-    pub target_weight_kg: Option<f32>,
+    pub target_weight_kg: Option<f32>, // Legacy metric field, used when `target_weight` is absent
+    pub target_weight: Option<f64>,
+    pub unit: Option<WeightUnit>, // Defaults to the template owner's preference
 }
 
-pub async fn add_template_exercise(
-    State(state): State<AppState>,
-    Path(template_id): Path<Uuid>,
-    Json(payload): Json<AddTemplateExerciseRequest>,
-) -> Json<TemplateExercise> {
-    let exercise = sqlx::query_as!(
+/// A template exercise with its target weight rendered in the owner's unit.
+#[derive(Serialize)]
+pub struct TemplateExerciseView {
+    #[serde(flatten)]
+    pub exercise: TemplateExercise,
+    pub target_weight: Option<f64>,
+    pub unit: WeightUnit,
+}
+
+impl TemplateExerciseView {
+    pub fn new(exercise: TemplateExercise, unit: WeightUnit) -> Self {
+        let target_weight = exercise.target_weight_kg.map(|kg| {
+            units::display(kg as f64, exercise.target_weight_entered, exercise.target_weight_unit.as_deref(), unit)
+        });
+        TemplateExerciseView { exercise, target_weight, unit }
+    }
+}
+
+async fn template_owner_unit(db: &PgPool, template_id: Uuid) -> Result<WeightUnit, (StatusCode, String)> {
+    let owner = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM workout_templates WHERE id = $1")
+        .bind(template_id)
+        .fetch_optional(db)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Template not found".to_string()))?;
+
+    Ok(units::preferred_unit(db, owner).await)
+}
+
+async fn insert_template_exercise<'e, E>(
+    executor: E,
+    template_id: Uuid,
+    ex: &AddTemplateExerciseRequest,
+    preferred: WeightUnit,
+) -> Result<TemplateExercise, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    let entered = EnteredWeight::resolve(ex.target_weight, ex.unit, ex.target_weight_kg.map(|w| w as f64), preferred);
+
+    sqlx::query_as!(
         TemplateExercise,
-        "INSERT INTO template_exercises (template_id, exercise_id, order_index, target_sets, target_reps, target_weight_kg) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *",
+        "INSERT INTO template_exercises (template_id, exercise_id, order_index, target_sets, target_reps, target_weight_kg, target_weight_entered, target_weight_unit) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *",
         template_id,
-        payload.exercise_id,
-        payload.order_index,
-        payload.target_sets,
-        payload.target_reps,
-        payload.target_weight_kg
+        ex.exercise_id,
+        ex.order_index,
+        ex.target_sets,
+        ex.target_reps,
+        entered.map(|e| e.kg() as f32),
+        entered.map(|e| e.value),
+        entered.map(|e| e.unit.as_str())
     )
-    .fetch_one(&state.db)
+    .fetch_one(executor)
     .await
-    .unwrap();
+}
 
-    Json(exercise)
+// An exercise_id that doesn't exist is the caller's mistake
+fn insert_error(e: sqlx::Error) -> (StatusCode, String) {
+    match &e {
+        sqlx::Error::Database(d) if d.is_foreign_key_violation() => (StatusCode::BAD_REQUEST, "Unknown exercise".to_string()),
+        _ => db_error(e),
+    }
+}
+
+/// Templates are changed by their owner, or a coach the owner gave template access.
+async fn check_can_edit(state: &AppState, user_id: Uuid, template_id: Uuid) -> Result<(), (StatusCode, String)> {
+    let allowed = coaching::can_edit_template(&state.db, user_id, template_id).await.map_err(db_error)?;
+
+    match allowed {
+        Some(true) => Ok(()),
+        Some(false) => Err((StatusCode::FORBIDDEN, "You cannot edit this template".to_string())),
+        None => Err((StatusCode::NOT_FOUND, "Template not found".to_string())),
+    }
+}
+
+pub async fn add_template_exercise(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(template_id): Path<Uuid>,
+    Json(payload): Json<AddTemplateExerciseRequest>,
+) -> Result<Json<TemplateExerciseView>, (StatusCode, String)> {
+    check_can_edit(&state, user_id, template_id).await?;
+
+    let preferred = template_owner_unit(&state.db, template_id).await?;
+    let exercise = insert_template_exercise(&state.db, template_id, &payload, preferred)
+        .await
+        .map_err(insert_error)?;
+
+    Ok(Json(TemplateExerciseView::new(exercise, preferred)))
 }
 
 #[derive(Serialize)] 
@@ -90,49 +166,98 @@ pub struct TemplateExerciseDetails {
     pub target_sets: i32,
     pub target_reps: i32,
     pub target_weight_kg: Option<f32>,
+    pub target_weight: Option<f64>,
+    pub achievable_target_weight: Option<f64>, // Rounded to what the owner's plates can build
+    pub unit: WeightUnit,
 }
 
-pub async fn get_template(
-    State(state): State<AppState>,
-    Path(id): Path<Uuid>,
-) -> Json<TemplateWithExercises> {
-    let template = sqlx::query_as!(
-        WorkoutTemplate,
-        "SELECT * FROM workout_templates WHERE id = $1",
-        id
-    )
-    .fetch_one(&state.db)
-    .await
-    .unwrap();
+struct TemplateExerciseRow {
+    exercise: TemplateExercise,
+    exercise_name: String,
+    exercise_equipment: Option<String>,
+}
 
+async fn fetch_template_exercises(db: &PgPool, template_id: Uuid) -> Result<Vec<TemplateExerciseRow>, sqlx::Error> {
     // Join with exercises table to get names
-    let exercises = sqlx::query!(
+    let rows = sqlx::query!(
         r#"
-        SELECT te.*, e.name as exercise_name 
+        SELECT te.*, e.name as exercise_name, e.equipment as exercise_equipment
         FROM template_exercises te
         JOIN exercises e ON te.exercise_id = e.id
         WHERE te.template_id = $1
         ORDER BY te.order_index
         "#,
+        template_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows.into_iter().map(|rec| TemplateExerciseRow {
+        exercise: TemplateExercise {
+            id: rec.id,
+            template_id: rec.template_id,
+            exercise_id: rec.exercise_id,
+            order_index: rec.order_index,
+            target_sets: rec.target_sets,
+            target_reps: rec.target_reps,
+            target_weight_kg: rec.target_weight_kg,
+            target_weight_entered: rec.target_weight_entered,
+            target_weight_unit: rec.target_weight_unit,
+        },
+        exercise_name: rec.exercise_name,
+        exercise_equipment: rec.exercise_equipment,
+    }).collect())
+}
+
+/// A template with its exercises. Templates are read by whoever can see the
+/// owner's workouts, as for progression suggestions.
+pub async fn get_template(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TemplateWithExercises>, (StatusCode, String)> {
+    let template = sqlx::query_as!(
+        WorkoutTemplate,
+        "SELECT * FROM workout_templates WHERE id = $1",
         id
     )
-    .fetch_all(&state.db)
+    .fetch_optional(&state.db)
     .await
-    .unwrap();
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Template not found".to_string()))?;
+    require_workouts(&state, user_id, template.user_id).await?;
 
-    let exercise_details = exercises.into_iter().map(|rec| TemplateExerciseDetails {
-        id: rec.id,
-        exercise_id: rec.exercise_id,
-        exercise_name: rec.exercise_name,
-        target_sets: rec.target_sets,
-        target_reps: rec.target_reps,
-        target_weight_kg: rec.target_weight_kg,
+    let unit = units::preferred_unit(&state.db, template.user_id).await;
+    let equipment = plates::load_equipment(&state.db, template.user_id).await;
+
+    let exercises = fetch_template_exercises(&state.db, id).await.map_err(db_error)?;
+
+    let exercise_details = exercises.into_iter().map(|rec| {
+        let view = TemplateExerciseView::new(rec.exercise, unit);
+        let achievable_target_weight = view.target_weight.map(|w| {
+            if plates::is_plate_loaded(rec.exercise_equipment.as_deref()) {
+                equipment.round_in(w, unit)
+            } else {
+                w
+            }
+        });
+        TemplateExerciseDetails {
+            id: view.exercise.id,
+            exercise_id: view.exercise.exercise_id,
+            exercise_name: rec.exercise_name,
+            target_sets: view.exercise.target_sets,
+            target_reps: view.exercise.target_reps,
+            target_weight_kg: view.exercise.target_weight_kg,
+            target_weight: view.target_weight,
+            achievable_target_weight,
+            unit,
+        }
     }).collect();
 
-    Json(TemplateWithExercises {
+    Ok(Json(TemplateWithExercises {
         template,
         exercises: exercise_details,
-    })
+    }))
 }
 
 #[derive(Deserialize)]
@@ -142,11 +267,16 @@ pub struct UpdateTemplateExercisesRequest {
 
 pub async fn update_template_exercises(
     State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateTemplateExercisesRequest>,
-) -> Json<Vec<TemplateExercise>> {
+) -> Result<Json<Vec<TemplateExerciseView>>, (StatusCode, String)> {
+    check_can_edit(&state, user_id, id).await?;
+
+    let preferred = template_owner_unit(&state.db, id).await?;
+
     // Transaction to ensure atomicity
-    let mut tx = state.db.begin().await.unwrap();
+    let mut tx = state.db.begin().await.map_err(db_error)?;
 
     // 1. Delete existing exercises for this template
     sqlx::query!(
@@ -155,28 +285,123 @@ pub async fn update_template_exercises(
     )
     .execute(&mut *tx)
     .await
-    .unwrap();
+    .map_err(db_error)?;
 
     // 2. Insert new exercises
     let mut new_exercises = Vec::new();
-    for ex in payload.exercises {
-        let inserted = sqlx::query_as!(
-            TemplateExercise,
-            "INSERT INTO template_exercises (template_id, exercise_id, order_index, target_sets, target_reps, target_weight_kg) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *",
-            id,
-            ex.exercise_id,
-            ex.order_index,
-            ex.target_sets,
-            ex.target_reps,
-            ex.target_weight_kg
-        )
-        .fetch_one(&mut *tx)
-        .await
-        .unwrap();
-        new_exercises.push(inserted);
+    for ex in &payload.exercises {
+        let inserted = insert_template_exercise(&mut *tx, id, ex, preferred)
+            .await
+            .map_err(insert_error)?;
+        new_exercises.push(TemplateExerciseView::new(inserted, preferred));
     }
 
-    tx.commit().await.unwrap();
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(Json(new_exercises))
+}
+
+#[derive(Serialize)]
+pub struct ProgressionSuggestion {
+    pub template_exercise_id: Uuid,
+    pub exercise_id: Uuid,
+    pub exercise_name: String,
+    pub target_reps: i32,
+    pub last_weight: Option<f64>,
+    pub hit_target: bool,
+    pub suggested_weight: Option<f64>,
+    pub unit: WeightUnit,
+}
+
+/// Next-session loads for each template exercise, based on the last finished
+/// workout started from this template. If every target set was completed at the
+/// top weight we add the smallest jump, otherwise the weight is repeated. The
+/// last workout is the owner's, so the caller must be able to see it.
+pub async fn get_progression_suggestions(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<ProgressionSuggestion>>, (StatusCode, String)> {
+    let owner = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM workout_templates WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch template: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "Template not found".to_string()))?;
+    require_workouts(&state, user_id, owner).await?;
+
+    let unit = units::preferred_unit(&state.db, owner).await;
+    let equipment = plates::load_equipment(&state.db, owner).await;
+
+    let exercises = fetch_template_exercises(&state.db, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch template exercises: {}", e)))?;
+
+    let last_sets = sqlx::query_as::<_, Set>(
+        r#"
+        SELECT s.* FROM sets s
+        WHERE s.set_type = 'working' AND s.workout_id = (
+            SELECT w.id FROM workouts w
+            WHERE w.template_id = $1 AND w.user_id = $2 AND w.end_time IS NOT NULL
+            ORDER BY w.end_time DESC
+            LIMIT 1
+        )
+        "#
+    )
+    .bind(id)
+    .bind(owner)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch last session: {}", e)))?;
+
+    // Smallest jump the owner's plates allow, one pair of the lightest plate
+    let smallest_jump_kg = equipment.plates.iter()
+        .map(|p| p.weight)
+        .filter(|w| *w > 0.0)
+        .min_by(|a, b| a.total_cmp(b))
+        .map(|w| equipment.unit.to_kg(2.0 * w))
+        .unwrap_or(2.5);
+
+    let suggestions = exercises.into_iter().map(|rec| {
+        let te = &rec.exercise;
+        let plate_loaded = plates::is_plate_loaded(rec.exercise_equipment.as_deref());
+        let sets: Vec<&Set> = last_sets.iter().filter(|s| s.exercise_id == te.exercise_id).collect();
+
+        let top = sets.iter().copied().max_by(|a, b| a.weight_kg.total_cmp(&b.weight_kg));
+        let last_weight = top.map(|s| units::display(s.weight_kg as f64, s.entered_weight, s.entered_unit.as_deref(), unit));
+
+        let hit_target = top.is_some_and(|top| {
+            let completed = sets.iter()
+                .filter(|s| s.weight_kg >= top.weight_kg && s.reps >= te.target_reps)
+                .count();
+            completed as i32 >= te.target_sets
+        });
+
+        let suggested = match top {
+            // Not there yet, repeat exactly what was lifted
+            Some(_) if !hit_target => last_weight,
+            Some(top) => {
+                let jump_kg = if plate_loaded { smallest_jump_kg } else { top.weight_kg as f64 * 0.025 };
+                Some(units::display(top.weight_kg as f64 + jump_kg, None, None, unit))
+            }
+            // No history yet, start from the template target
+            None => te.target_weight_kg.map(|kg| {
+                units::display(kg as f64, te.target_weight_entered, te.target_weight_unit.as_deref(), unit)
+            }),
+        };
+        let suggested_weight = suggested.map(|w| if plate_loaded { equipment.round_in(w, unit) } else { w });
+
+        ProgressionSuggestion {
+            template_exercise_id: te.id,
+            exercise_id: te.exercise_id,
+            exercise_name: rec.exercise_name.clone(),
+            target_reps: te.target_reps,
+            last_weight,
+            hit_target,
+            suggested_weight,
+            unit,
+        }
+    }).collect();
 
-    Json(new_exercises)
+    Ok(Json(suggestions))
 }
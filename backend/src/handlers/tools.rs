@@ -0,0 +1,272 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    auth::CurrentUser,
+    events::LiveEvent,
+    handlers::workouts::SetView,
+    models::{Set, SetType},
+    plates::{self, EquipmentProfile, PlateBreakdown, PlateStock},
+    units::{self, WeightUnit},
+    warmups::{self, WarmupScheme, WarmupSet, WarmupStep},
+    AppState,
+};
+
+/// Equipment is only visible to its owner; anyone else gets a 404.
+fn require_self(user_id: Uuid, caller: Uuid) -> Result<(), (StatusCode, String)> {
+    if user_id == caller {
+        Ok(())
+    } else {
+        Err((StatusCode::NOT_FOUND, "Equipment profile not found".to_string()))
+    }
+}
+
+pub async fn get_equipment(
+    State(state): State<AppState>,
+    CurrentUser(caller): CurrentUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<EquipmentProfile>, (StatusCode, String)> {
+    require_self(user_id, caller)?;
+    Ok(Json(plates::load_equipment(&state.db, user_id).await))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateEquipmentRequest {
+    pub unit: Option<WeightUnit>,
+    pub bar_weight: Option<f64>,
+    pub collar_weight: Option<f64>,
+    pub plates: Option<Vec<PlateStock>>,
+}
+
+pub async fn update_equipment(
+    State(state): State<AppState>,
+    CurrentUser(caller): CurrentUser,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<UpdateEquipmentRequest>,
+) -> Result<Json<EquipmentProfile>, (StatusCode, String)> {
+    require_self(user_id, caller)?;
+    let current = plates::load_equipment(&state.db, user_id).await;
+
+    let profile = EquipmentProfile {
+        unit: payload.unit.unwrap_or(current.unit),
+        bar_weight: payload.bar_weight.unwrap_or(current.bar_weight),
+        collar_weight: payload.collar_weight.unwrap_or(current.collar_weight),
+        plates: payload.plates.unwrap_or(current.plates),
+    };
+
+    if profile.bar_weight < 0.0 || profile.collar_weight < 0.0 || profile.plates.iter().any(|p| p.weight <= 0.0) {
+        return Err((StatusCode::BAD_REQUEST, "Bar, collar and plate weights must be positive".to_string()));
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO equipment_profiles (user_id, unit, bar_weight, collar_weight, plates, updated_at)
+        VALUES ($1, $2, $3, $4, $5, NOW())
+        ON CONFLICT (user_id)
+        DO UPDATE SET
+            unit = EXCLUDED.unit,
+            bar_weight = EXCLUDED.bar_weight,
+            collar_weight = EXCLUDED.collar_weight,
+            plates = EXCLUDED.plates,
+            updated_at = EXCLUDED.updated_at
+        "#
+    )
+    .bind(user_id)
+    .bind(profile.unit.as_str())
+    .bind(profile.bar_weight)
+    .bind(profile.collar_weight)
+    .bind(sqlx::types::Json(&profile.plates))
+    .execute(&state.db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save equipment: {}", e)))?;
+
+    Ok(Json(profile))
+}
+
+#[derive(Deserialize)]
+pub struct PlatesQuery {
+    pub target: f64,
+    pub bar: Option<f64>,         // Overrides the profile's bar weight
+    pub unit: Option<WeightUnit>, // Unit of `target` and `bar`, defaults to the profile's
+}
+
+pub async fn get_plate_breakdown(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Query(query): Query<PlatesQuery>,
+) -> Result<Json<PlateBreakdown>, (StatusCode, String)> {
+    if query.target <= 0.0 {
+        return Err((StatusCode::BAD_REQUEST, "target must be positive".to_string()));
+    }
+
+    let equipment = plates::load_equipment(&state.db, user_id).await;
+
+    // Plate math happens in the profile's unit
+    let to_profile_unit = |w: f64| match query.unit {
+        Some(unit) if unit != equipment.unit => equipment.unit.convert_kg(unit.to_kg(w)),
+        _ => w,
+    };
+
+    let target = to_profile_unit(query.target);
+    let bar = query.bar.map(to_profile_unit).unwrap_or(equipment.bar_weight);
+
+    Ok(Json(equipment.breakdown(target, bar)))
+}
+
+#[derive(Deserialize)]
+pub struct WarmupRequest {
+    pub exercise_id: Option<Uuid>,
+    pub working_weight: Option<f64>,
+    pub template_exercise_id: Option<Uuid>, // Supplies exercise and working weight if not given
+    pub unit: Option<WeightUnit>,           // Defaults to the user's preference
+    #[serde(default)]
+    pub scheme: WarmupScheme,
+    pub steps: Option<Vec<WarmupStep>>,     // Custom ramp, overrides `scheme`
+    #[serde(default)]
+    pub insert: bool,
+    pub workout_id: Option<Uuid>,           // Defaults to the active workout
+}
+
+#[derive(Serialize)]
+pub struct WarmupResponse {
+    pub exercise_id: Uuid,
+    pub working_weight: f64,
+    pub unit: WeightUnit,
+    pub sets: Vec<WarmupSet>,
+    pub inserted: Vec<SetView>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TemplateTarget {
+    exercise_id: Uuid,
+    target_weight_kg: Option<f32>,
+    target_weight_entered: Option<f64>,
+    target_weight_unit: Option<String>,
+}
+
+pub async fn generate_warmups(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<WarmupRequest>,
+) -> Result<Json<WarmupResponse>, (StatusCode, String)> {
+    let unit = match payload.unit {
+        Some(unit) => unit,
+        None => units::preferred_unit(&state.db, user_id).await,
+    };
+
+    let template_target = match payload.template_exercise_id {
+        Some(id) => Some(
+            sqlx::query_as::<_, TemplateTarget>(
+                "SELECT exercise_id, target_weight_kg, target_weight_entered, target_weight_unit FROM template_exercises WHERE id = $1"
+            )
+            .bind(id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch template exercise: {}", e)))?
+            .ok_or((StatusCode::NOT_FOUND, "Template exercise not found".to_string()))?,
+        ),
+        None => None,
+    };
+
+    let exercise_id = payload.exercise_id
+        .or(template_target.as_ref().map(|t| t.exercise_id))
+        .ok_or((StatusCode::BAD_REQUEST, "exercise_id or template_exercise_id is required".to_string()))?;
+
+    let working_weight = payload.working_weight
+        .or_else(|| {
+            let t = template_target.as_ref()?;
+            t.target_weight_kg.map(|kg| units::display(kg as f64, t.target_weight_entered, t.target_weight_unit.as_deref(), unit))
+        })
+        .filter(|w| *w > 0.0)
+        .ok_or((StatusCode::BAD_REQUEST, "A positive working weight is required".to_string()))?;
+
+    let equipment = sqlx::query_scalar::<_, Option<String>>("SELECT equipment FROM exercises WHERE id = $1")
+        .bind(exercise_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch exercise: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "Exercise not found".to_string()))?;
+
+    let steps = payload.steps.unwrap_or_else(|| payload.scheme.steps());
+
+    let sets = if plates::is_plate_loaded(equipment.as_deref()) {
+        let gear = plates::load_equipment(&state.db, user_id).await;
+        let empty_bar = gear.round_in(unit.convert_kg(gear.unit.to_kg(gear.bar_weight)), unit);
+        warmups::generate(working_weight, empty_bar, &steps, |w| gear.round_in(w, unit))
+    } else {
+        // Dumbbells and machines move in fixed jumps
+        let increment = match unit {
+            WeightUnit::Kg => 2.5,
+            WeightUnit::Lb => 5.0,
+        };
+        warmups::generate(working_weight, 0.0, &steps, |w| (w / increment).round() * increment)
+    };
+
+    let mut inserted = Vec::new();
+    if payload.insert && !sets.is_empty() {
+        let workout_id = match payload.workout_id {
+            Some(id) => {
+                let workout = sqlx::query!("SELECT user_id, end_time FROM workouts WHERE id = $1", id)
+                    .fetch_optional(&state.db)
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch workout: {}", e)))?;
+                match workout {
+                    Some(w) if w.user_id == user_id && w.end_time.is_none() => id,
+                    Some(w) if w.user_id == user_id => {
+                        return Err((StatusCode::CONFLICT, "Workout is already finished".to_string()));
+                    }
+                    _ => return Err((StatusCode::NOT_FOUND, "Workout not found".to_string())),
+                }
+            }
+            None => sqlx::query_scalar::<_, Uuid>(
+                "SELECT id FROM workouts WHERE user_id = $1 AND end_time IS NULL ORDER BY start_time DESC LIMIT 1"
+            )
+            .bind(user_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch active workout: {}", e)))?
+            .ok_or((StatusCode::NOT_FOUND, "No active workout to add warm-ups to".to_string()))?,
+        };
+
+        let mut tx = state.db.begin().await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+        for warmup in &sets {
+            let set = sqlx::query_as::<_, Set>(
+                "INSERT INTO sets (workout_id, exercise_id, weight_kg, reps, entered_weight, entered_unit, set_type) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *"
+            )
+            .bind(workout_id)
+            .bind(exercise_id)
+            .bind(unit.to_kg(warmup.weight) as f32)
+            .bind(warmup.reps)
+            .bind(warmup.weight)
+            .bind(unit.as_str())
+            .bind(SetType::Warmup.as_str())
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to insert warm-up set: {}", e)))?;
+            inserted.push(SetView::new(set, unit));
+        }
+
+        tx.commit().await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit warm-up sets: {}", e)))?;
+
+        for view in &inserted {
+            state.events.publish(user_id, LiveEvent::SetLogged {
+                workout_id: view.set.workout_id,
+                set_id: view.set.id,
+                exercise_id: view.set.exercise_id,
+                weight_kg: view.set.weight_kg,
+                reps: view.set.reps,
+                set_type: view.set.set_type.clone(),
+            });
+        }
+    }
+
+    Ok(Json(WarmupResponse { exercise_id, working_weight, unit, sets, inserted }))
+}
@@ -1,9 +1,24 @@
 use axum::{
-    extract::{State, Path},
+    extract::{State, Path, Query},
     Json,
     http::StatusCode,
 };
-use crate::{AppState, models::{Workout, Set}};
+use crate::{
+    AppState,
+    achievements,
+    auth::CurrentUser,
+    badges,
+    compare::{self, ComparedWorkout, ExerciseComparison, TotalsDelta},
+    events::LiveEvent,
+    feed,
+    handlers::db_error,
+    handlers::privacy::require_workouts,
+    handlers::sessions::{load_session, SessionView},
+    models::{Workout, Set, SetType},
+    notifications::NotificationKind,
+    units::{self, EnteredWeight, UnitQuery, WeightUnit},
+    xp::{self, LevelProgress, XpBreakdown},
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -34,9 +49,25 @@ pub async fn create_workout(
     Json(workout)
 }
 
+/// A set with its weight rendered in the requested unit.
 #[derive(Serialize)]
-pub struct LogSetResponse {
+pub struct SetView {
+    #[serde(flatten)]
     pub set: Set,
+    pub weight: f64,
+    pub unit: WeightUnit,
+}
+
+impl SetView {
+    pub fn new(set: Set, unit: WeightUnit) -> Self {
+        let weight = units::display(set.weight_kg as f64, set.entered_weight, set.entered_unit.as_deref(), unit);
+        SetView { set, weight, unit }
+    }
+}
+
+#[derive(Serialize)]
+pub struct LogSetResponse {
+    pub set: SetView,
     pub is_new_1rm: bool,
     pub is_vol_pr: bool,
 }
@@ -45,33 +76,46 @@ pub struct LogSetResponse {
 pub struct LogSetRequest {
     pub workout_id: Uuid,
     pub exercise_id: Uuid,
-    pub weight_kg: f32,
+    pub weight_kg: Option<f32>, // Legacy metric field, used when `weight` is absent
+    pub weight: Option<f64>,
+    pub unit: Option<WeightUnit>, // Defaults to the user's preference
     pub reps: i32,
     pub rpe: Option<f32>,
+    #[serde(default)]
+    pub set_type: SetType,
 }
 
 pub async fn log_set(
     State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
     Json(payload): Json<LogSetRequest>,
 ) -> Result<Json<LogSetResponse>, (StatusCode, String)> {
-    // 1. Get User ID from workout
+    // 1. Sets go into the caller's own, unfinished workouts
     let workout = sqlx::query!(
-        "SELECT user_id FROM workouts WHERE id = $1",
+        "SELECT user_id, end_time FROM workouts WHERE id = $1",
         payload.workout_id
     )
-    .fetch_one(&state.db)
+    .fetch_optional(&state.db)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch workout user: {}", e)))?;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch workout user: {}", e)))?
+    .filter(|w| w.user_id == user_id)
+    .ok_or((StatusCode::NOT_FOUND, "Workout not found".to_string()))?;
+    if workout.end_time.is_some() {
+        return Err((StatusCode::CONFLICT, "Workout is already finished".to_string()));
+    }
 
-    let user_id = workout.user_id;
+    let preferred = units::preferred_unit(&state.db, user_id).await;
+    let entered = EnteredWeight::resolve(payload.weight, payload.unit, payload.weight_kg.map(|w| w as f64), preferred)
+        .ok_or((StatusCode::BAD_REQUEST, "Either weight or weight_kg is required".to_string()))?;
+    let weight_kg = entered.kg() as f32;
 
     // 2. Check previous max WEIGHT for this exercise/user
-    let prev_max_weight = sqlx::query!(
+    let prev_max_weight = sqlx::query_scalar!(
         r#"
         SELECT MAX(s.weight_kg) as max_val 
         FROM sets s
         JOIN workouts w ON s.workout_id = w.id
-        WHERE s.exercise_id = $1 AND w.user_id = $2
+        WHERE s.exercise_id = $1 AND w.user_id = $2 AND s.set_type = 'working'
         "#,
         payload.exercise_id,
         user_id
@@ -79,12 +123,11 @@ pub async fn log_set(
     .fetch_one(&state.db)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch max weight: {}", e)))?
-    .max_val
     .unwrap_or(0.0);
 
     // 3. Check previous max REPS at this weight (or higher)
     // "Volume PR" (Rep PR): Most reps ever done at this weight or heavier.
-    let prev_max_reps = sqlx::query!(
+    let prev_max_reps = sqlx::query_scalar!(
         r#"
         SELECT MAX(s.reps) as max_val 
         FROM sets s
@@ -92,65 +135,152 @@ pub async fn log_set(
         WHERE s.exercise_id = $1 
         AND w.user_id = $2
         AND s.weight_kg >= $3
+        AND s.set_type = 'working'
         "#,
         payload.exercise_id,
         user_id,
-        payload.weight_kg
+        weight_kg
     )
     .fetch_one(&state.db)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch max reps: {}", e)))?
-    .max_val
     .unwrap_or(0);
 
-    // 4. Insert Set
+    // 4. Determine Rewards (warm-ups never count)
+    let pr_kind = match payload.set_type {
+        SetType::Working => badges::classify_pr(weight_kg, payload.reps, prev_max_weight, prev_max_reps),
+        SetType::Warmup => None,
+    };
+
+    // 5. Insert Set
     let set = sqlx::query_as!(
         Set,
-        "INSERT INTO sets (workout_id, exercise_id, weight_kg, reps, rpe) VALUES ($1, $2, $3, $4, $5) RETURNING *",
+        "INSERT INTO sets (workout_id, exercise_id, weight_kg, reps, rpe, entered_weight, entered_unit, set_type, pr_kind) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *",
         payload.workout_id,
         payload.exercise_id,
-        payload.weight_kg,
+        weight_kg,
         payload.reps,
-        payload.rpe
+        payload.rpe,
+        entered.value,
+        entered.unit.as_str(),
+        payload.set_type.as_str(),
+        pr_kind
     )
     .fetch_one(&state.db)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to insert set: {}", e)))?;
 
-    // 5. Determine Rewards
-    let is_new_1rm = set.weight_kg > prev_max_weight;
-    let is_vol_pr = !is_new_1rm && set.reps > prev_max_reps && set.reps > 5; // Arbitrary min reps to avoid trivial PRs
+    // 6. Push to the user's other devices
+    state.events.publish(user_id, LiveEvent::SetLogged {
+        workout_id: set.workout_id,
+        set_id: set.id,
+        exercise_id: set.exercise_id,
+        weight_kg: set.weight_kg,
+        reps: set.reps,
+        set_type: set.set_type.clone(),
+    });
+    if let Some(kind) = pr_kind {
+        state.events.publish(user_id, LiveEvent::PersonalRecord {
+            workout_id: set.workout_id,
+            set_id: set.id,
+            exercise_id: set.exercise_id,
+            kind: kind.to_string(),
+            weight_kg: set.weight_kg,
+            reps: set.reps,
+        });
+        state.notifier.send(user_id, NotificationKind::PersonalRecord, None, serde_json::json!({
+            "workout_id": set.workout_id,
+            "set_id": set.id,
+            "exercise_id": set.exercise_id,
+            "kind": kind,
+            "weight_kg": set.weight_kg,
+            "reps": set.reps,
+        }));
+        if let Err(e) = feed::post_personal_record(&state.db, user_id, &set).await {
+            tracing::error!("Failed to post PR for set {}: {}", set.id, e);
+        }
+    }
+    state.leaderboards.schedule(user_id);
 
-    Ok(Json(LogSetResponse { set, is_new_1rm, is_vol_pr }))
+    Ok(Json(LogSetResponse {
+        set: SetView::new(set, preferred),
+        is_new_1rm: pr_kind == Some("1rm"),
+        is_vol_pr: pr_kind == Some("volume"),
+    }))
 }
 
+/// The caller's own sets, newest first. Other people's sets are read through
+/// their workouts, which check what the caller may see.
 pub async fn list_sets(
     State(state): State<AppState>,
-) -> Json<Vec<Set>> {
-    let sets = sqlx::query_as!(
-        Set,
-        "SELECT * FROM sets ORDER BY created_at DESC LIMIT 1000" // Limit for safety
+    CurrentUser(user_id): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<UnitQuery>,
+) -> Json<Vec<SetView>> {
+    let sets = sqlx::query!(
+        r#"
+        SELECT s.*, u.unit_system
+        FROM sets s
+        JOIN workouts w ON s.workout_id = w.id
+        JOIN users u ON w.user_id = u.id
+        WHERE w.user_id = $1
+        ORDER BY s.created_at DESC
+        LIMIT 1000
+        "#, // Limit for safety
+        user_id
     )
     .fetch_all(&state.db)
     .await
     .unwrap_or(vec![]);
 
-    Json(sets)
+    // Explicit ?unit= wins, otherwise each set is shown in its owner's unit
+    let views = sets.into_iter().map(|row| {
+        let unit = query.unit
+            .or_else(|| WeightUnit::parse(&row.unit_system))
+            .unwrap_or_default();
+        let set = Set {
+            id: row.id,
+            workout_id: row.workout_id,
+            exercise_id: row.exercise_id,
+            weight_kg: row.weight_kg,
+            reps: row.reps,
+            rpe: row.rpe,
+            created_at: row.created_at,
+            entered_weight: row.entered_weight,
+            entered_unit: row.entered_unit,
+            set_type: row.set_type,
+            pr_kind: row.pr_kind,
+        };
+        SetView::new(set, unit)
+    }).collect();
+
+    Json(views)
 }
 
 pub async fn delete_set(
     State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
     Path(id): Path<Uuid>,
 ) -> StatusCode {
-    let result = sqlx::query!(
-        "DELETE FROM sets WHERE id = $1",
-        id
+    let result = sqlx::query_scalar!(
+        r#"
+        DELETE FROM sets s
+        USING workouts w
+        WHERE s.id = $1 AND s.workout_id = w.id AND w.user_id = $2
+        RETURNING s.workout_id
+        "#,
+        id,
+        user_id
     )
-    .execute(&state.db)
+    .fetch_optional(&state.db)
     .await;
 
     match result {
-        Ok(_) => StatusCode::NO_CONTENT,
+        Ok(Some(workout_id)) => {
+            state.events.publish(user_id, LiveEvent::SetDeleted { workout_id, set_id: id });
+            state.leaderboards.schedule(user_id);
+            StatusCode::NO_CONTENT
+        }
+        Ok(None) => StatusCode::NOT_FOUND,
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
@@ -160,44 +290,42 @@ pub struct FinishWorkoutResponse {
     pub id: Uuid,
     pub end_time: chrono::DateTime<chrono::Utc>,
     pub badges: Vec<String>,
+    pub xp: XpBreakdown,
+    pub level: LevelProgress,
 }
 
 pub async fn finish_workout(
     State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
     Path(id): Path<Uuid>,
-) -> Json<FinishWorkoutResponse> {
+) -> Result<Json<FinishWorkoutResponse>, (StatusCode, String)> {
     let now = chrono::Utc::now();
-    
-    // 1. Fetch workout details, volume, and user weight
-    let workout_data = sqlx::query!(
-        r#"
-        SELECT 
-            w.start_time, 
-            w.user_id,
-            u.current_weight_kg,
-            COALESCE(SUM(s.weight_kg * s.reps), 0) as volume,
-            COUNT(s.id) as set_count
-        FROM workouts w
-        JOIN users u ON w.user_id = u.id
-        LEFT JOIN sets s ON w.id = s.workout_id
-        WHERE w.id = $1
-        GROUP BY w.id, w.start_time, w.user_id, u.current_weight_kg
-        "#,
-        id
-    )
-    .fetch_one(&state.db)
-    .await
-    .unwrap();
+    let not_found = || (StatusCode::NOT_FOUND, "Workout not found".to_string());
+    let already_finished = || (StatusCode::CONFLICT, "Workout is already finished".to_string());
 
-    // 2. Calculate Stats
-    let duration_minutes = if let Some(start) = workout_data.start_time {
-        (now - start).num_minutes() as f64
-    } else {
-        60.0 // Default to 1 hour if start time missing
-    };
+    let workout = sqlx::query!("SELECT user_id, end_time FROM workouts WHERE id = $1", id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(db_error)?
+        .filter(|w| w.user_id == user_id)
+        .ok_or_else(not_found)?;
+    if workout.end_time.is_some() {
+        return Err(already_finished());
+    }
+
+    // Finishing and everything it awards are written together, as in a recompute
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+
+    // 1. Gather workout facts: volume, duration, bodyweight at the time, streak
+    let facts = badges::gather_facts(&mut tx, id, now).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => not_found(),
+        e => db_error(e),
+    })?;
 
-    let weight = workout_data.current_weight_kg.unwrap_or(75.0) as f64; // Default 75kg
-    let volume = workout_data.volume.unwrap_or(0.0) as f64;
+    // 2. Calculate Stats
+    let duration_minutes = facts.duration_minutes;
+    let weight = facts.bodyweight_kg.unwrap_or(75.0); // Default 75kg
+    let volume = facts.volume_kg;
     
     // MET Calculation (Metabolic Equivalent of Task)
     // Circuit training/vigorous lifting is ~6.0 METs
@@ -217,69 +345,83 @@ pub async fn finish_workout(
     let duration_hours = duration_minutes / 60.0;
     let calories_burned = (met * weight * duration_hours) as i32;
 
-    // 3. Determine Badges
-    let mut badges = Vec::new();
-    if volume >= 10000.0 {
-        badges.push("Titan Volume".to_string());
-    } else if volume >= 5000.0 {
-        badges.push("Heavy Lifter".to_string());
+    // 3. Update Workout, unless another request finished it in the meantime
+    let finished = sqlx::query!(
+        "UPDATE workouts SET end_time = $1, calories_burned = $2 WHERE id = $3 AND end_time IS NULL",
+        now,
+        calories_burned,
+        id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .rows_affected();
+    if finished == 0 {
+        return Err(already_finished());
     }
 
-    if duration_minutes >= 90.0 {
-        badges.push("Marathoner".to_string());
-    } else if duration_minutes <= 30.0 && volume > 2000.0 {
-        badges.push("Speed Demon".to_string());
-    }
-    
-    if workout_data.set_count.unwrap_or(0) >= 20 {
-         badges.push("Volume Warrior".to_string());
+    // 4. Award badges from badge_definitions
+    // user_badges is unique per (user, badge, workout), so a badge can be
+    // earned again in every workout that qualifies.
+    let mut badges = badges::award_for_workout(&mut tx, id, &facts).await.map_err(db_error)?;
+
+    // 5. Strength milestones, earned once ever
+    badges.extend(achievements::award_milestones(&mut tx, user_id, id, now).await.map_err(db_error)?);
+
+    // 6. Experience points
+    let xp_earned = xp::for_workout(&facts);
+    let total_xp = xp::record_workout_xp(&mut tx, id, user_id, &xp_earned).await.map_err(db_error)?;
+
+    // The live session ends with the workout
+    sqlx::query("DELETE FROM workout_sessions WHERE workout_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    if let Err(e) = feed::post_workout_finished(&state.db, user_id, id, &badges).await {
+        tracing::error!("Failed to post workout {}: {}", id, e);
     }
 
-    // Insert badges into DB (ignore duplicates via ON CONFLICT usually, but migration has UNIQUE)
-    // We use ON CONFLICT DO NOTHING to avoid errors if they re-earn the same badge in the same workout (unlikely/redundant but safe)
-    // Actually our unique constraint is (user_id, badge_name, workout_id).
-    // So if they do a new workout, they can earn "Heavy Lifter" again?
-    // The requirement implies a Trophy Case. Usually you earn a badge once, or multiple times.
-    // Let's assume for now we collect them. If we want unique per user (earned once ever), we'd enforce that.
-    // The current migration enforces unique per *workout*. So they can earn "Heavy Lifter" multiple times (one per workout).
-    // This is good for "Activity" feed, but for "Trophy Case" we might want to group them.
-    
     for badge in &badges {
-        let _ = sqlx::query!(
-            "INSERT INTO user_badges (user_id, workout_id, badge_name) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
-            workout_data.user_id,
-            id,
-            badge
-        )
-        .execute(&state.db)
-        .await;
+        state.events.publish(user_id, LiveEvent::BadgeEarned {
+            workout_id: Some(id),
+            badge_name: badge.clone(),
+        });
+        state.notifier.send(user_id, NotificationKind::Badge, None, serde_json::json!({
+            "workout_id": id,
+            "badge_name": badge,
+        }));
     }
+    state.events.publish(user_id, LiveEvent::WorkoutFinished {
+        workout_id: id,
+        badges: badges.clone(),
+    });
+    state.leaderboards.schedule(user_id);
 
-    // 4. Update Workout
-    let _ = sqlx::query!(
-        "UPDATE workouts SET end_time = $1, calories_burned = $2 WHERE id = $3",
-        now,
-        calories_burned,
-        id
-    )
-    .execute(&state.db)
-    .await
-    .unwrap();
-
-    Json(FinishWorkoutResponse {
+    Ok(Json(FinishWorkoutResponse {
         id,
         end_time: now,
         badges,
-    })
+        xp: xp_earned,
+        level: LevelProgress::new(total_xp),
+    }))
+}
+
+#[derive(Serialize)]
+pub struct ActiveWorkoutResponse {
+    #[serde(flatten)]
+    pub workout: Workout,
+    pub session: SessionView,
 }
 
 pub async fn get_active_workout(
     State(state): State<AppState>,
-) -> Result<Json<Workout>, StatusCode> {
-    // Find the most recent workout for DEMO_USER_ID that hasn't ended
-    // Ideally user_id comes from auth, but for now hardcoded or passed in query
-    let user_id = Uuid::parse_str("763b9c95-4bae-4044-9d30-7ae513286b37").unwrap(); // Demo User
-
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<ActiveWorkoutResponse>, (StatusCode, String)> {
+    // Find the most recent workout for the current user that hasn't ended
     let workout = sqlx::query_as!(
         Workout,
         "SELECT * FROM workouts WHERE user_id = $1 AND end_time IS NULL ORDER BY start_time DESC LIMIT 1",
@@ -287,10 +429,68 @@ pub async fn get_active_workout(
     )
     .fetch_optional(&state.db)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch active workout: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "No active workout".to_string()))?;
 
-    match workout {
-        Some(w) => Ok(Json(w)),
-        None => Err(StatusCode::NOT_FOUND),
-    }
+    // Every device resumes from the same session state
+    let (session, started_at) = load_session(&state.db, workout.id, user_id).await?;
+
+    Ok(Json(ActiveWorkoutResponse {
+        workout,
+        session: SessionView::new(session, started_at),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct CompareQuery {
+    pub a: Uuid,
+    pub b: Option<Uuid>, // Defaults to the previous workout from the same template
+    pub unit: Option<WeightUnit>,
+}
+
+#[derive(Serialize)]
+pub struct WorkoutComparison {
+    pub a: ComparedWorkout,
+    pub b: ComparedWorkout,
+    pub unit: WeightUnit,
+    pub exercises: Vec<ExerciseComparison>,
+    pub totals: TotalsDelta,
+}
+
+/// A workout the viewer may read: their own, an athlete's they coach, or one
+/// in a history they can see.
+async fn visible_workout(state: &AppState, viewer: Uuid, id: Uuid) -> Result<ComparedWorkout, (StatusCode, String)> {
+    let workout = compare::get_workout(&state.db, id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Workout not found".to_string()))?;
+    require_workouts(state, viewer, workout.user_id).await?;
+    Ok(workout)
+}
+
+/// Session-over-session progress: `a` against `b`, exercise by exercise.
+pub async fn compare_workouts(
+    State(state): State<AppState>,
+    CurrentUser(user_id): CurrentUser,
+    Query(query): Query<CompareQuery>,
+) -> Result<Json<WorkoutComparison>, (StatusCode, String)> {
+    let a = visible_workout(&state, user_id, query.a).await?;
+    let b = match query.b {
+        Some(b) if b == a.id => return Err((StatusCode::BAD_REQUEST, "Pick two different workouts".to_string())),
+        Some(b) => visible_workout(&state, user_id, b).await?,
+        None => compare::previous_instance(&state.db, &a)
+            .await
+            .map_err(db_error)?
+            .ok_or((StatusCode::NOT_FOUND, "No earlier workout from the same template to compare with".to_string()))?,
+    };
+
+    let sets_a = compare::working_sets(&state.db, a.id).await.map_err(db_error)?;
+    let sets_b = compare::working_sets(&state.db, b.id).await.map_err(db_error)?;
+    let unit = match query.unit {
+        Some(unit) => unit,
+        None => units::preferred_unit(&state.db, user_id).await,
+    };
+    let (exercises, totals) = compare::compare(&sets_a, &sets_b, unit);
+
+    Ok(Json(WorkoutComparison { a, b, unit, exercises, totals }))
 }
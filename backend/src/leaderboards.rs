@@ -0,0 +1,740 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    achievements::Lift,
+    badges,
+    notifications::{NotificationKind, Notifier},
+    privacy::{self, Field},
+    scoring::{self, Sex, StrengthBasis},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderboardMetric {
+    #[default]
+    Volume,   // kg, working sets
+    E1rm,     // Best estimated 1RM on one exercise, kg
+    Wilks,    // Best squat + bench + deadlift against current bodyweight
+    Dots,
+    #[serde(rename = "ipf_gl")]
+    IpfGl,    // IPF GL points
+    Workouts, // Finished workouts
+    Streak,   // Current run of consecutive training days
+}
+
+impl LeaderboardMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LeaderboardMetric::Volume => "volume",
+            LeaderboardMetric::E1rm => "e1rm",
+            LeaderboardMetric::Wilks => "wilks",
+            LeaderboardMetric::Dots => "dots",
+            LeaderboardMetric::IpfGl => "ipf_gl",
+            LeaderboardMetric::Workouts => "workouts",
+            LeaderboardMetric::Streak => "streak",
+        }
+    }
+
+    /// Boards counting training rather than lifting.
+    const ACTIVITY: [LeaderboardMetric; 2] = [LeaderboardMetric::Workouts, LeaderboardMetric::Streak];
+
+    /// The part of a profile a board gives away. Only users who made it public
+    /// are ranked on it.
+    pub fn privacy_field(self) -> Field {
+        if LeaderboardMetric::ACTIVITY.contains(&self) {
+            Field::Activity
+        } else {
+            Field::Lifts
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderboardPeriod {
+    #[default]
+    All,
+    Weekly,
+    Monthly,
+}
+
+impl LeaderboardPeriod {
+    pub const ALL: [LeaderboardPeriod; 3] = [LeaderboardPeriod::All, LeaderboardPeriod::Weekly, LeaderboardPeriod::Monthly];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LeaderboardPeriod::All => "all",
+            LeaderboardPeriod::Weekly => "weekly",
+            LeaderboardPeriod::Monthly => "monthly",
+        }
+    }
+
+    pub fn since(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            LeaderboardPeriod::All => None,
+            LeaderboardPeriod::Weekly => Some(now - Duration::days(7)),
+            LeaderboardPeriod::Monthly => Some(now - Duration::days(30)),
+        }
+    }
+}
+
+fn basis_dimension(basis: StrengthBasis) -> &'static str {
+    match basis {
+        StrengthBasis::E1rm => "e1rm",
+        StrengthBasis::Actual => "actual",
+    }
+}
+
+/// One materialized board in `leaderboard_stats`.
+#[derive(Debug)]
+pub struct BoardKey {
+    pub metric: LeaderboardMetric,
+    pub period: LeaderboardPeriod,
+    pub dimension: String, // Muscle group (volume), exercise id (e1RM), basis (scores) or empty
+}
+
+impl BoardKey {
+    pub fn new(
+        metric: LeaderboardMetric,
+        period: LeaderboardPeriod,
+        muscle_group: Option<&str>,
+        exercise_id: Option<Uuid>,
+        basis: StrengthBasis,
+    ) -> Result<Self, &'static str> {
+        let (period, dimension) = match metric {
+            LeaderboardMetric::Volume => (period, muscle_group.unwrap_or_default().trim().to_lowercase()),
+            LeaderboardMetric::E1rm => {
+                let exercise_id = exercise_id.ok_or("exercise_id is required for the e1rm metric")?;
+                (period, exercise_id.to_string())
+            }
+            LeaderboardMetric::Wilks | LeaderboardMetric::Dots | LeaderboardMetric::IpfGl => {
+                (period, basis_dimension(basis).to_string())
+            }
+            LeaderboardMetric::Workouts => (period, String::new()),
+            // A streak is always the current one
+            LeaderboardMetric::Streak => (LeaderboardPeriod::All, String::new()),
+        };
+        Ok(BoardKey { metric, period, dimension })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    pub id: Uuid,
+    pub username: String,
+    pub value: f64,
+}
+
+#[derive(Debug)]
+pub struct Board {
+    pub total: i64,
+    pub entries: Vec<LeaderboardEntry>,
+    pub me: Option<LeaderboardEntry>,
+    pub snapshot_at: Option<DateTime<Utc>>, // Oldest refresh among the ranked rows
+}
+
+#[derive(sqlx::FromRow)]
+struct BoardRow {
+    #[sqlx(flatten)]
+    entry: LeaderboardEntry,
+    position: i64,
+}
+
+/// A page of a materialized board, restricted to `users` if given and without
+/// the `excluded` users, plus the row of `me` wherever it ranks. Users without
+/// a positive value, and other users who don't show the board's field to
+/// everyone, are left out. Ties share a rank.
+pub async fn read_board(
+    db: &PgPool,
+    key: &BoardKey,
+    users: Option<&[Uuid]>,
+    excluded: &[Uuid],
+    offset: i64,
+    limit: i64,
+    me: Uuid,
+) -> Result<Board, sqlx::Error> {
+    // `me` is the parameter holding the viewer, who always sees their own row
+    let visibility = privacy::visibility_sql(key.metric.privacy_field(), "ls.user_id");
+    let public = |me: &str| format!("(ls.user_id = {me} OR {visibility} = 'public')");
+
+    let rows = sqlx::query_as::<_, BoardRow>(&format!(
+        r#"
+        WITH ranked AS (
+            SELECT
+                ls.user_id as id,
+                u.username,
+                ls.value,
+                RANK() OVER (ORDER BY ls.value DESC) as rank,
+                ROW_NUMBER() OVER (ORDER BY ls.value DESC, u.username, ls.user_id) as position
+            FROM leaderboard_stats ls
+            JOIN users u ON ls.user_id = u.id
+            WHERE ls.period = $1 AND ls.metric = $2 AND ls.dimension = $3 AND ls.value > 0
+              AND ($4::uuid[] IS NULL OR ls.user_id = ANY($4))
+              AND ls.user_id <> ALL($8)
+              AND {}
+        )
+        SELECT id, username, value, rank, position
+        FROM ranked
+        WHERE (position > $5 AND position <= $5 + $6) OR id = $7
+        ORDER BY position
+        "#,
+        public("$7"),
+    ))
+    .bind(key.period.as_str())
+    .bind(key.metric.as_str())
+    .bind(&key.dimension)
+    .bind(users)
+    .bind(offset)
+    .bind(limit)
+    .bind(me)
+    .bind(excluded)
+    .fetch_all(db)
+    .await?;
+
+    let (total, snapshot_at) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(&format!(
+        r#"
+        SELECT COUNT(*), MIN(ls.refreshed_at)
+        FROM leaderboard_stats ls
+        WHERE ls.period = $1 AND ls.metric = $2 AND ls.dimension = $3 AND ls.value > 0
+          AND ($4::uuid[] IS NULL OR ls.user_id = ANY($4))
+          AND ls.user_id <> ALL($5)
+          AND {}
+        "#,
+        public("$6"),
+    ))
+    .bind(key.period.as_str())
+    .bind(key.metric.as_str())
+    .bind(&key.dimension)
+    .bind(users)
+    .bind(excluded)
+    .bind(me)
+    .fetch_one(db)
+    .await?;
+
+    let me = rows.iter().find(|row| row.entry.id == me).map(|row| row.entry.clone());
+    let entries = rows.into_iter()
+        .filter(|row| row.position > offset && row.position <= offset + limit)
+        .map(|row| row.entry)
+        .collect();
+
+    Ok(Board { total, entries, me, snapshot_at })
+}
+
+const UPSERT: &str =
+    "ON CONFLICT (period, metric, dimension, user_id) DO UPDATE SET value = EXCLUDED.value, refreshed_at = EXCLUDED.refreshed_at";
+
+/// Sums and maxima computed in SQL, per period. `$1` scopes the users, `$2`
+/// is the period start, `$3` the period and `$4` the refresh time.
+fn aggregate_queries() -> [String; 3] {
+    [
+        // Volume overall ('') and per muscle group
+        format!(
+            r#"
+            INSERT INTO leaderboard_stats (user_id, period, metric, dimension, value, refreshed_at)
+            SELECT w.user_id, $3, 'volume', COALESCE(LOWER(e.muscle_group), ''), SUM(s.weight_kg * s.reps)::float8, $4
+            FROM sets s
+            JOIN workouts w ON s.workout_id = w.id
+            JOIN exercises e ON s.exercise_id = e.id
+            WHERE s.set_type = 'working'
+              AND ($1::uuid[] IS NULL OR w.user_id = ANY($1))
+              AND ($2::timestamptz IS NULL OR s.created_at >= $2)
+            GROUP BY GROUPING SETS ((w.user_id), (w.user_id, LOWER(e.muscle_group)))
+            {UPSERT}
+            "#
+        ),
+        // e1RM per exercise
+        format!(
+            r#"
+            INSERT INTO leaderboard_stats (user_id, period, metric, dimension, value, refreshed_at)
+            SELECT w.user_id, $3, 'e1rm', s.exercise_id::text,
+                MAX(CASE WHEN s.reps = 1 THEN s.weight_kg ELSE s.weight_kg * (1 + s.reps / 30.0) END)::float8, $4
+            FROM sets s
+            JOIN workouts w ON s.workout_id = w.id
+            WHERE s.set_type = 'working'
+              AND s.reps BETWEEN 1 AND 10
+              AND ($1::uuid[] IS NULL OR w.user_id = ANY($1))
+              AND ($2::timestamptz IS NULL OR s.created_at >= $2)
+            GROUP BY w.user_id, s.exercise_id
+            {UPSERT}
+            "#
+        ),
+        format!(
+            r#"
+            INSERT INTO leaderboard_stats (user_id, period, metric, dimension, value, refreshed_at)
+            SELECT w.user_id, $3, 'workouts', '', COUNT(*)::float8, $4
+            FROM workouts w
+            WHERE w.end_time IS NOT NULL
+              AND ($1::uuid[] IS NULL OR w.user_id = ANY($1))
+              AND ($2::timestamptz IS NULL OR w.end_time >= $2)
+            GROUP BY w.user_id
+            {UPSERT}
+            "#
+        ),
+    ]
+}
+
+struct StatRow {
+    user_id: Uuid,
+    period: LeaderboardPeriod,
+    metric: LeaderboardMetric,
+    dimension: &'static str,
+    value: f64,
+}
+
+/// Recompute the materialized boards of `users`, or of everyone.
+pub async fn refresh(db: &PgPool, users: Option<&[Uuid]>) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+
+    // Streaks and scores are computed here, before the write transaction
+    let mut computed = Vec::new();
+    for (user_id, streak) in streaks(db, users, now).await? {
+        computed.push(StatRow {
+            user_id,
+            period: LeaderboardPeriod::All,
+            metric: LeaderboardMetric::Streak,
+            dimension: "",
+            value: streak,
+        });
+    }
+    for period in LeaderboardPeriod::ALL {
+        for basis in [StrengthBasis::E1rm, StrengthBasis::Actual] {
+            for (user_id, sex, bodyweight, total) in strength_totals(db, users, period.since(now), basis).await? {
+                let scores = scoring::Scores::new(sex, bodyweight, total);
+                for (metric, value) in [
+                    (LeaderboardMetric::Wilks, scores.wilks),
+                    (LeaderboardMetric::Dots, scores.dots),
+                    (LeaderboardMetric::IpfGl, scores.ipf_gl),
+                ] {
+                    computed.push(StatRow { user_id, period, metric, dimension: basis_dimension(basis), value });
+                }
+            }
+        }
+    }
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM leaderboard_stats WHERE ($1::uuid[] IS NULL OR user_id = ANY($1))")
+        .bind(users)
+        .execute(&mut *tx)
+        .await?;
+
+    let queries = aggregate_queries();
+    for period in LeaderboardPeriod::ALL {
+        for sql in &queries {
+            sqlx::query(sql)
+                .bind(users)
+                .bind(period.since(now))
+                .bind(period.as_str())
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    let user_ids: Vec<Uuid> = computed.iter().map(|row| row.user_id).collect();
+    let periods: Vec<&str> = computed.iter().map(|row| row.period.as_str()).collect();
+    let metrics: Vec<&str> = computed.iter().map(|row| row.metric.as_str()).collect();
+    let dimensions: Vec<&str> = computed.iter().map(|row| row.dimension).collect();
+    let values: Vec<f64> = computed.iter().map(|row| row.value).collect();
+
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO leaderboard_stats (user_id, period, metric, dimension, value, refreshed_at)
+        SELECT user_id, period, metric, dimension, value, $6
+        FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::text[], $5::float8[]) AS t(user_id, period, metric, dimension, value)
+        {UPSERT}
+        "#
+    ))
+    .bind(&user_ids)
+    .bind(&periods)
+    .bind(&metrics)
+    .bind(&dimensions)
+    .bind(&values)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// How long a user's refresh waits for more sets before it runs.
+const REFRESH_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Refreshes one user's rows in the background, so logging isn't held up, and
+/// tells the people they passed on an all-time board. Requests for a user
+/// whose refresh is waiting or running are folded into it: a burst of sets
+/// costs one refresh, and refreshes of the same user never overlap.
+#[derive(Clone)]
+pub struct Refresher {
+    db: PgPool,
+    notifier: Notifier,
+    pending: Arc<Mutex<HashMap<Uuid, bool>>>, // Users with a refresh under way, and whether they changed since it started
+}
+
+impl Refresher {
+    pub fn new(db: PgPool, notifier: Notifier) -> Self {
+        Refresher { db, notifier, pending: Arc::default() }
+    }
+
+    pub fn schedule(&self, user_id: Uuid) {
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if let Some(dirty) = pending.get_mut(&user_id) {
+                *dirty = true;
+                return;
+            }
+            pending.insert(user_id, false);
+        }
+
+        let refresher = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(REFRESH_DEBOUNCE).await;
+                refresher.pending.lock().unwrap().insert(user_id, false);
+
+                if let Err(e) = refresher.refresh_user(user_id).await {
+                    tracing::error!("Failed to refresh leaderboards for {}: {}", user_id, e);
+                }
+
+                // Go again if more came in while this one ran
+                let mut pending = refresher.pending.lock().unwrap();
+                if pending.get(&user_id) != Some(&true) {
+                    pending.remove(&user_id);
+                    return;
+                }
+            }
+        });
+    }
+
+    async fn refresh_user(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        let before = all_time_values(&self.db, user_id).await?;
+        refresh(&self.db, Some(&[user_id])).await?;
+        let passed = passed_users(&self.db, user_id, &before).await?;
+
+        let mut boards: HashMap<Uuid, Vec<serde_json::Value>> = HashMap::new();
+        for (other, metric, dimension) in passed {
+            boards.entry(other).or_default().push(serde_json::json!({ "metric": metric, "dimension": dimension }));
+        }
+        for (other, boards) in boards {
+            self.notifier
+                .notify(other, NotificationKind::RankBeaten, Some(user_id), serde_json::json!({ "boards": boards }))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// `user_id`'s (metric, dimension, value) on every all-time board.
+async fn all_time_values(db: &PgPool, user_id: Uuid) -> Result<Vec<(String, String, f64)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, String, f64)>(
+        "SELECT metric, dimension, value FROM leaderboard_stats WHERE period = 'all' AND user_id = $1 AND value > 0"
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Users `user_id` has moved ahead of since `before`, with the board. A board
+/// missing from `before` counts as 0, so a first entry passes everyone below
+/// it. Only people they follow or who follow them count; strangers aren't told,
+/// and neither is anyone the board's field is hidden from.
+async fn passed_users(
+    db: &PgPool,
+    user_id: Uuid,
+    before: &[(String, String, f64)],
+) -> Result<Vec<(Uuid, String, String)>, sqlx::Error> {
+    let metrics: Vec<&str> = before.iter().map(|(metric, _, _)| metric.as_str()).collect();
+    let dimensions: Vec<&str> = before.iter().map(|(_, dimension, _)| dimension.as_str()).collect();
+    let values: Vec<f64> = before.iter().map(|(_, _, value)| *value).collect();
+
+    let activity = LeaderboardMetric::ACTIVITY.map(LeaderboardMetric::as_str);
+    let visible = format!(
+        "CASE WHEN mine.metric = ANY($5) THEN {} ELSE {} END",
+        privacy::can_see_sql(Field::Activity, "other.user_id", "$1"),
+        privacy::can_see_sql(Field::Lifts, "other.user_id", "$1"),
+    );
+
+    sqlx::query_as::<_, (Uuid, String, String)>(&format!(
+        r#"
+        SELECT other.user_id, other.metric, other.dimension
+        FROM leaderboard_stats mine
+        LEFT JOIN UNNEST($2::text[], $3::text[], $4::float8[]) AS before(metric, dimension, value)
+          ON before.metric = mine.metric AND before.dimension = mine.dimension
+        JOIN leaderboard_stats other
+          ON other.period = 'all' AND other.user_id <> $1
+         AND other.metric = mine.metric AND other.dimension = mine.dimension
+        WHERE mine.period = 'all' AND mine.user_id = $1
+          AND other.value > 0
+          AND other.value >= COALESCE(before.value, 0) AND other.value < mine.value
+          AND other.user_id IN (
+              SELECT following_id FROM follows WHERE follower_id = $1 AND status = 'accepted'
+              UNION
+              SELECT follower_id FROM follows WHERE following_id = $1 AND status = 'accepted'
+          )
+          AND {visible}
+        "#
+    ))
+    .bind(user_id)
+    .bind(&metrics)
+    .bind(&dimensions)
+    .bind(&values)
+    .bind(&activity[..])
+    .fetch_all(db)
+    .await
+}
+
+/// Users whose boards may have moved since `since`: they logged sets, finished
+/// workouts or weighed in, a set or workout slid out of a weekly or monthly
+/// window, or a running streak was last counted before today.
+async fn changed_users(db: &PgPool, since: DateTime<Utc>, now: DateTime<Utc>) -> Result<Vec<Uuid>, sqlx::Error> {
+    let week = Duration::days(7);
+    let month = Duration::days(30);
+    let today = now.date_naive().and_time(NaiveTime::MIN).and_utc();
+
+    let users = sqlx::query_scalar!(
+        r#"
+        SELECT w.user_id as "user_id!"
+        FROM sets s
+        JOIN workouts w ON s.workout_id = w.id
+        WHERE s.created_at >= $1
+           OR (s.created_at >= $3 AND s.created_at < $4)
+           OR (s.created_at >= $5 AND s.created_at < $6)
+        UNION
+        SELECT user_id
+        FROM workouts
+        WHERE end_time >= $1
+           OR (end_time >= $3 AND end_time < $4)
+           OR (end_time >= $5 AND end_time < $6)
+        UNION
+        SELECT user_id FROM weight_logs WHERE logged_at >= $1
+        UNION
+        SELECT user_id
+        FROM leaderboard_stats
+        WHERE metric = 'streak' AND value > 0 AND refreshed_at < $2
+        "#,
+        since,
+        today,
+        since - week,
+        now - week,
+        since - month,
+        now - month,
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(users)
+}
+
+/// Background task keeping the boards current. The first run rebuilds every
+/// board; after that only users changed since the previous run are refreshed.
+pub async fn run_refresher(db: PgPool, every: std::time::Duration) {
+    let mut interval = tokio::time::interval(every);
+    let mut watermark: Option<DateTime<Utc>> = None;
+    loop {
+        interval.tick().await;
+        let started = Utc::now();
+        let result = match watermark {
+            None => refresh(&db, None).await.map(|()| None),
+            Some(since) => match changed_users(&db, since, started).await {
+                Ok(users) if users.is_empty() => Ok(Some(0)),
+                Ok(users) => refresh(&db, Some(&users)).await.map(|()| Some(users.len())),
+                Err(e) => Err(e),
+            },
+        };
+        match result {
+            Ok(refreshed) => {
+                match refreshed {
+                    None => tracing::info!("Refreshed leaderboards"),
+                    Some(count) => tracing::debug!("Refreshed leaderboards for {} users", count),
+                }
+                watermark = Some(started);
+            }
+            Err(e) => tracing::error!("Failed to refresh leaderboards: {}", e),
+        }
+    }
+}
+
+/// Current run of consecutive training days per user.
+async fn streaks(db: &PgPool, users: Option<&[Uuid]>, now: DateTime<Utc>) -> Result<Vec<(Uuid, f64)>, sqlx::Error> {
+    let days = sqlx::query_as::<_, (Uuid, NaiveDate)>(
+        r#"
+        SELECT DISTINCT w.user_id, DATE(COALESCE(w.start_time, w.created_at)) as day
+        FROM workouts w
+        WHERE w.end_time IS NOT NULL
+          AND ($1::uuid[] IS NULL OR w.user_id = ANY($1))
+        ORDER BY w.user_id, day DESC
+        "#
+    )
+    .bind(users)
+    .fetch_all(db)
+    .await?;
+
+    let mut by_user: HashMap<Uuid, Vec<NaiveDate>> = HashMap::new();
+    for (id, day) in days {
+        by_user.entry(id).or_default().push(day);
+    }
+
+    // A streak is still alive if the last session was today or yesterday
+    let yesterday = now.date_naive() - Duration::days(1);
+    Ok(by_user.into_iter()
+        .map(|(id, days)| {
+            let alive = days.first().is_some_and(|last| *last >= yesterday);
+            let streak = if alive { badges::consecutive_days(&days) } else { 0 };
+            (id, streak as f64)
+        })
+        .collect())
+}
+
+#[derive(sqlx::FromRow)]
+struct LifterRow {
+    id: Uuid,
+    gender: Option<String>,
+    bodyweight_kg: Option<f64>,
+    exercise_name: String,
+    best_kg: f64,
+}
+
+/// Each user's total (best e1RM or single per lift, per `basis`) with their
+/// sex and current bodyweight. Users missing a lift, their sex or their
+/// bodyweight are skipped.
+async fn strength_totals(
+    db: &PgPool,
+    users: Option<&[Uuid]>,
+    since: Option<DateTime<Utc>>,
+    basis: StrengthBasis,
+) -> Result<Vec<(Uuid, Sex, f64, f64)>, sqlx::Error> {
+    let names: Vec<&str> = Lift::ALL.iter().flat_map(|lift| lift.exercise_names().iter().copied()).collect();
+
+    let rows = sqlx::query_as::<_, LifterRow>(
+        r#"
+        SELECT
+            u.id,
+            u.gender,
+            COALESCE(
+                (SELECT wl.weight_kg FROM weight_logs wl WHERE wl.user_id = u.id ORDER BY wl.logged_at DESC LIMIT 1),
+                u.current_weight_kg
+            ) as bodyweight_kg,
+            e.name as exercise_name,
+            MAX(CASE WHEN s.reps = 1 THEN s.weight_kg ELSE s.weight_kg * (1 + s.reps / 30.0) END)::float8 as best_kg
+        FROM users u
+        JOIN workouts w ON u.id = w.user_id
+        JOIN sets s ON w.id = s.workout_id AND s.set_type = 'working'
+        JOIN exercises e ON s.exercise_id = e.id
+        WHERE ($1::uuid[] IS NULL OR u.id = ANY($1))
+          AND ($2::timestamptz IS NULL OR s.created_at >= $2)
+          AND s.reps BETWEEN 1 AND 10
+          AND ($4 OR s.reps = 1)
+          AND e.name = ANY($3)
+        GROUP BY u.id, u.gender, e.name
+        "#
+    )
+    .bind(users)
+    .bind(since)
+    .bind(&names)
+    .bind(basis == StrengthBasis::E1rm)
+    .fetch_all(db)
+    .await?;
+
+    let mut lifters: HashMap<Uuid, (LifterRow, HashMap<Lift, f64>)> = HashMap::new();
+    for row in rows {
+        let Some(lift) = Lift::classify(&row.exercise_name) else {
+            continue;
+        };
+        let best_kg = row.best_kg;
+        let (_, lifts) = lifters.entry(row.id).or_insert_with(|| (row, HashMap::new()));
+        let best = lifts.entry(lift).or_insert(0.0);
+        *best = best.max(best_kg);
+    }
+
+    Ok(lifters.into_values()
+        .filter_map(|(row, lifts)| {
+            let sex = Sex::parse(row.gender.as_deref()?)?;
+            let bodyweight = row.bodyweight_kg.filter(|w| *w > 0.0)?;
+            if lifts.len() < Lift::ALL.len() {
+                return None;
+            }
+            Some((row.id, sex, bodyweight, lifts.values().sum()))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::RecordingDelivery;
+
+    async fn user(db: &PgPool, username: &str) -> Uuid {
+        sqlx::query_scalar("INSERT INTO users (username, password_hash) VALUES ($1, '') RETURNING id")
+            .bind(username)
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
+
+    async fn log_volume(db: &PgPool, user_id: Uuid, exercise_id: Uuid, weight_kg: f32, reps: i32) {
+        let workout_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO workouts (user_id, start_time, end_time) VALUES ($1, NOW(), NOW()) RETURNING id"
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO sets (workout_id, exercise_id, weight_kg, reps) VALUES ($1, $2, $3, $4)")
+            .bind(workout_id)
+            .bind(exercise_id)
+            .bind(weight_kg)
+            .bind(reps)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a Postgres DATABASE_URL"]
+    async fn first_entry_notifies_each_passed_friend_once(db: PgPool) {
+        let recording = RecordingDelivery::default();
+        let refresher = Refresher::new(db.clone(), Notifier::new(db.clone()).with_delivery(recording.clone()));
+
+        let newcomer = user(&db, "newcomer").await;
+        let friend = user(&db, "friend").await;
+        let stranger = user(&db, "stranger").await;
+        sqlx::query("INSERT INTO follows (follower_id, following_id) VALUES ($1, $2)")
+            .bind(newcomer)
+            .bind(friend)
+            .execute(&db)
+            .await
+            .unwrap();
+        let exercise_id: Uuid =
+            sqlx::query_scalar("INSERT INTO exercises (name, muscle_group) VALUES ('Test Press', 'Chest') RETURNING id")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+
+        log_volume(&db, friend, exercise_id, 50.0, 10).await;
+        log_volume(&db, stranger, exercise_id, 50.0, 10).await;
+        refresh(&db, None).await.unwrap();
+
+        // No rows of their own yet: every board counts as 0 before this
+        log_volume(&db, newcomer, exercise_id, 100.0, 10).await;
+        refresher.refresh_user(newcomer).await.unwrap();
+
+        let delivered = recording.delivered.lock().unwrap().clone();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].user_id, friend);
+        assert_eq!(delivered[0].kind, "rank_beaten");
+        assert_eq!(delivered[0].actor_id, Some(newcomer));
+
+        // Nothing changed, so nobody is passed again
+        refresher.refresh_user(newcomer).await.unwrap();
+        assert_eq!(recording.delivered.lock().unwrap().len(), 1);
+    }
+}
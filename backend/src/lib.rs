@@ -0,0 +1,26 @@
+//! Domain logic shared by the API server and the maintenance binaries in `src/bin`.
+
+pub mod achievements;
+pub mod badges;
+pub mod blocks;
+pub mod card;
+pub mod challenges;
+pub mod coaching;
+pub mod compare;
+pub mod feed;
+pub mod follows;
+pub mod groups;
+pub mod leaderboards;
+pub mod models;
+pub mod notifications;
+pub mod plates;
+pub mod privacy;
+pub mod reactions;
+pub mod recompute;
+pub mod scoring;
+pub mod search;
+pub mod shares;
+pub mod summary;
+pub mod units;
+pub mod warmups;
+pub mod xp;
@@ -1,22 +1,28 @@
 use axum::{
     routing::get,
     Router,
-    extract::State,
 };
 use std::net::SocketAddr;
 use tower_http::trace::TraceLayer;
 use sqlx::postgres::{PgPool, PgPoolOptions};
-use std::sync::Arc;
 use dotenvy::dotenv;
 use std::env;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
+    pub events: events::EventHub,
+    pub notifier: notifications::Notifier,
+    pub signer: shares::ShareSigner,
+    pub live_tokens: auth::LiveTokens,
+    pub leaderboards: leaderboards::Refresher,
 }
 
-mod models;
 mod handlers;
+mod auth;
+mod events;
+
+use backend::{achievements, badges, blocks, challenges, coaching, compare, feed, follows, groups, leaderboards, models, notifications, plates, privacy, reactions, scoring, search, card, shares, summary, units, warmups, xp};
 
 
 #[tokio::main]
@@ -84,31 +90,99 @@ async fn main() {
         }
     }
 
-    let state = AppState { db: pool };
+    let events = events::EventHub::new();
+    let notifier = notifications::Notifier::new(pool.clone()).with_delivery(events.clone());
+    let signer = shares::ShareSigner::from_env();
+    let live_tokens = auth::LiveTokens::from_env();
+    let leaderboards = leaderboards::Refresher::new(pool.clone(), notifier.clone());
+    let state = AppState { db: pool, events, notifier, signer, live_tokens, leaderboards };
+
+    tokio::spawn(handlers::challenges::run_finalizer(state.clone()));
+    tokio::spawn(leaderboards::run_refresher(state.db.clone(), std::time::Duration::from_secs(10 * 60)));
 
     // build our application
     let app = Router::new()
         .route("/", get(root))
+        .route("/api/events", get(handlers::live::stream_events))
+        .route("/api/events/token", axum::routing::post(handlers::live::issue_live_token))
         .route("/api/exercises", get(handlers::exercises::list_exercises))
         .route("/api/workouts", axum::routing::post(handlers::workouts::create_workout))
         .route("/api/sets", axum::routing::post(handlers::workouts::log_set).get(handlers::workouts::list_sets))
         .route("/api/sets/:id", axum::routing::delete(handlers::workouts::delete_set))
+        .route("/api/sets/:id/comments", axum::routing::get(handlers::reactions::list_set_comments).post(handlers::reactions::add_set_comment))
         .route("/api/templates", axum::routing::get(handlers::templates::list_templates).post(handlers::templates::create_template))
         .route("/api/templates/:id", axum::routing::get(handlers::templates::get_template))
         .route("/api/templates/:id/exercises", axum::routing::post(handlers::templates::add_template_exercise).put(handlers::templates::update_template_exercises))
+        .route("/api/templates/:id/suggestions", axum::routing::get(handlers::templates::get_progression_suggestions))
         .route("/api/workouts/:id/finish", axum::routing::post(handlers::workouts::finish_workout))
         .route("/api/workouts/active", axum::routing::get(handlers::workouts::get_active_workout))
+        .route("/api/workouts/compare", axum::routing::get(handlers::workouts::compare_workouts))
+        .route("/api/workouts/:id/session", axum::routing::get(handlers::sessions::get_session).put(handlers::sessions::update_session_plan))
+        .route("/api/workouts/:id/session/rest", axum::routing::post(handlers::sessions::start_rest).delete(handlers::sessions::stop_rest))
+        .route("/api/workouts/:id/session/pause", axum::routing::post(handlers::sessions::pause_session))
+        .route("/api/workouts/:id/session/resume", axum::routing::post(handlers::sessions::resume_session))
         .route("/api/profile/:id", axum::routing::get(handlers::profile::get_full_profile))
         .route("/api/profile/:id/history", axum::routing::get(handlers::social::get_workout_history))
         .route("/api/profile/:id/stats", axum::routing::get(handlers::profile::get_physical_stats).post(handlers::profile::update_physical_stats))
         .route("/api/profile/:id/weight", axum::routing::get(handlers::profile::get_weight_history))
         .route("/api/profile/:id/nutrition", axum::routing::get(handlers::profile::get_nutrition_log).post(handlers::profile::log_nutrition))
+        .route("/api/profile/:id/strength", axum::routing::get(handlers::profile::get_strength_scores))
+        .route("/api/profile/:id/equipment", axum::routing::get(handlers::tools::get_equipment).put(handlers::tools::update_equipment))
+        .route("/api/tools/plates", axum::routing::get(handlers::tools::get_plate_breakdown))
+        .route("/api/tools/warmups", axum::routing::post(handlers::tools::generate_warmups))
+        .route("/api/challenges", axum::routing::get(handlers::challenges::list_challenges).post(handlers::challenges::create_challenge))
+        .route("/api/challenges/:id", axum::routing::get(handlers::challenges::get_challenge))
+        .route("/api/challenges/:id/invite", axum::routing::post(handlers::challenges::invite_to_challenge))
+        .route("/api/challenges/:id/join", axum::routing::post(handlers::challenges::join_challenge))
+        .route("/api/challenges/:id/leave", axum::routing::post(handlers::challenges::leave_challenge))
+        .route("/api/posts", axum::routing::post(handlers::feed::create_post))
+        .route("/api/feed", axum::routing::get(handlers::feed::get_feed))
+        .route("/api/posts/:id/kudos", axum::routing::post(handlers::reactions::give_post_kudos).delete(handlers::reactions::remove_post_kudos))
+        .route("/api/posts/:id/comments", axum::routing::get(handlers::reactions::list_post_comments).post(handlers::reactions::add_post_comment))
+        .route("/api/workouts/:id/share", axum::routing::get(handlers::shares::get_workout_share).post(handlers::shares::share_workout).delete(handlers::shares::revoke_workout_share))
+        .route("/api/shared/:token", axum::routing::get(handlers::shares::get_shared_workout))
+        .route("/api/shared/:token/card", axum::routing::get(handlers::shares::get_shared_workout_card))
+        .route("/api/shared/:token/card.png", axum::routing::get(handlers::shares::get_shared_workout_image))
+        .route("/api/workouts/:id/card.png", axum::routing::get(handlers::shares::get_workout_image))
+        .route("/api/workouts/:id/kudos", axum::routing::post(handlers::reactions::give_workout_kudos).delete(handlers::reactions::remove_workout_kudos))
+        .route("/api/workouts/:id/comments", axum::routing::get(handlers::reactions::list_workout_comments).post(handlers::reactions::add_workout_comment))
+        .route("/api/comments/:id", axum::routing::put(handlers::reactions::edit_comment).delete(handlers::reactions::delete_comment))
         .route("/api/leaderboard", axum::routing::get(handlers::social::get_leaderboard))
         .route("/api/profile/:id/badges", axum::routing::get(handlers::gamification::get_user_badges))
+        .route("/api/profile/:id/badges/milestones", axum::routing::get(handlers::gamification::get_strength_milestones))
+        .route("/api/profile/:id/trophies", axum::routing::get(handlers::gamification::get_trophies))
         .route("/api/social/follow/:id", axum::routing::post(handlers::social::follow_user).delete(handlers::social::unfollow_user))
         .route("/api/social/profile", axum::routing::put(handlers::social::update_social_profile))
         .route("/api/social/profile/:id", axum::routing::get(handlers::social::get_profile))
         .route("/api/social/search", axum::routing::get(handlers::social::search_users))
+        .route("/api/social/suggestions", axum::routing::get(handlers::social::suggested_users))
+        .route("/api/privacy", axum::routing::get(handlers::privacy::get_privacy_settings).put(handlers::privacy::update_privacy_settings))
+        .route("/api/notifications", axum::routing::get(handlers::notifications::list_notifications))
+        .route("/api/notifications/read", axum::routing::post(handlers::notifications::mark_read))
+        .route("/api/groups", axum::routing::get(handlers::groups::list_groups).post(handlers::groups::create_group))
+        .route("/api/groups/:id", axum::routing::get(handlers::groups::get_group))
+        .route("/api/groups/:id/invites", axum::routing::post(handlers::groups::invite_to_group))
+        .route("/api/groups/:id/join", axum::routing::post(handlers::groups::join_group))
+        .route("/api/groups/:id/leave", axum::routing::post(handlers::groups::leave_group))
+        .route("/api/groups/:id/members/:user_id", axum::routing::put(handlers::groups::update_member_role).delete(handlers::groups::remove_member))
+        .route("/api/groups/:id/feed", axum::routing::get(handlers::feed::get_group_feed))
+        .route("/api/coaching/coaches", axum::routing::get(handlers::coaching::list_coaches))
+        .route("/api/coaching/coaches/:id", axum::routing::put(handlers::coaching::grant_coach).delete(handlers::coaching::remove_coach))
+        .route("/api/coaching/athletes", axum::routing::get(handlers::coaching::list_athletes))
+        .route("/api/coaching/athletes/:id", axum::routing::delete(handlers::coaching::remove_athlete))
+        .route("/api/coaching/athletes/:id/workouts", axum::routing::get(handlers::coaching::athlete_workouts))
+        .route("/api/coaching/athletes/:id/workouts/:workout_id/sets", axum::routing::get(handlers::coaching::athlete_workout_sets))
+        .route("/api/coaching/athletes/:id/templates", axum::routing::post(handlers::coaching::assign_template))
+        .route("/api/social/privacy", axum::routing::put(handlers::social::update_privacy))
+        .route("/api/social/block/:id", axum::routing::post(handlers::social::block_user).delete(handlers::social::unblock_user))
+        .route("/api/social/blocked", axum::routing::get(handlers::social::list_blocked))
+        .route("/api/social/mute/:id", axum::routing::post(handlers::social::mute_user).delete(handlers::social::unmute_user))
+        .route("/api/social/muted", axum::routing::get(handlers::social::list_muted))
+        .route("/api/social/requests", axum::routing::get(handlers::social::list_follow_requests))
+        .route("/api/social/requests/:id/accept", axum::routing::post(handlers::social::accept_follow_request))
+        .route("/api/social/requests/:id/reject", axum::routing::post(handlers::social::reject_follow_request))
+        .route("/api/social/:id/followers", axum::routing::get(handlers::social::list_followers))
+        .route("/api/social/:id/following", axum::routing::get(handlers::social::list_following))
 
         .layer(
             tower_http::cors::CorsLayer::new()
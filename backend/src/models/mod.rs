@@ -18,6 +18,11 @@ pub struct User {
     pub twitter_handle: Option<String>,
     pub activity_level: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub unit_system: String,
+    pub current_weight_entered: Option<f64>,
+    pub current_weight_unit: Option<String>,
+    pub xp: i64,
+    pub is_private: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -42,6 +47,7 @@ pub struct Workout {
     pub template_id: Option<Uuid>,
     pub calories_burned: Option<i32>,
     pub created_at: DateTime<Utc>,
+    pub xp_earned: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -53,6 +59,27 @@ pub struct Set {
     pub reps: i32,
     pub rpe: Option<f32>,
     pub created_at: DateTime<Utc>,
+    pub entered_weight: Option<f64>,
+    pub entered_unit: Option<String>,
+    pub set_type: String,
+    pub pr_kind: Option<String>, // "1rm" or "volume" if this set was a PR when logged
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SetType {
+    #[default]
+    Working,
+    Warmup, // Excluded from PRs and volume
+}
+
+impl SetType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SetType::Working => "working",
+            SetType::Warmup => "warmup",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -62,6 +89,7 @@ pub struct WorkoutTemplate {
     pub name: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub assigned_by: Option<Uuid>, // The coach who assigned it
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -73,6 +101,8 @@ pub struct TemplateExercise {
     pub target_sets: i32,
     pub target_reps: i32,
     pub target_weight_kg: Option<f32>,
+    pub target_weight_entered: Option<f64>,
+    pub target_weight_unit: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -81,6 +111,8 @@ pub struct WeightLog {
     pub user_id: Uuid,
     pub weight_kg: f64,
     pub logged_at: DateTime<Utc>,
+    pub entered_weight: Option<f64>,
+    pub entered_unit: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -101,4 +133,107 @@ pub struct UserBadge {
     pub workout_id: Option<Uuid>,
     pub badge_name: String,
     pub earned_at: DateTime<Utc>,
+    pub source: String, // "rule" (badge_definitions) or "challenge"
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct EquipmentProfileRow {
+    pub user_id: Uuid,
+    pub unit: String,
+    pub bar_weight: f64,
+    pub collar_weight: f64,
+    pub plates: sqlx::types::Json<Vec<crate::plates::PlateStock>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedSet {
+    pub exercise_id: Uuid,
+    pub weight: Option<f64>,
+    pub unit: Option<crate::units::WeightUnit>,
+    pub reps: Option<i32>,
+    #[serde(default)]
+    pub set_type: SetType,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct WorkoutSession {
+    pub workout_id: Uuid,
+    pub user_id: Uuid,
+    pub current_exercise_id: Option<Uuid>,
+    pub next_set: Option<sqlx::types::Json<PlannedSet>>,
+    pub rest_started_at: Option<DateTime<Utc>>,
+    pub rest_ends_at: Option<DateTime<Utc>>,
+    pub paused_at: Option<DateTime<Utc>>,
+    pub paused_seconds: i64,
+    pub version: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct BadgeDefinition {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub rule_type: String,
+    pub params: sqlx::types::Json<serde_json::Value>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub silver_count: i32, // Earns needed for silver, bronze is the first earn
+    pub gold_count: i32,
+    pub category: String,  // "workout" (earned per workout) or "milestone" (earned once)
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Challenge {
+    pub id: Uuid,
+    pub creator_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub metric: String, // "reps", "volume", "sets" or "workouts"
+    pub exercise_id: Option<Uuid>,
+    pub goal: Option<f64>,
+    pub period: String, // "total" or "daily"
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub badge_name: String,
+    pub finalized_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub group_id: Option<Uuid>, // Open to the group's members
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Post {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub workout_id: Option<Uuid>,
+    pub content: Option<String>,
+    pub media_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub kind: String, // "manual", "workout" (finished) or "pr"
+    pub set_id: Option<Uuid>, // The set behind a "pr" post
+    pub group_id: Option<Uuid>, // Shared to this group only
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Group {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Comment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub post_id: Option<Uuid>,
+    pub workout_id: Option<Uuid>,
+    pub parent_id: Option<Uuid>,
+    pub body: String, // Empty once deleted
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub set_id: Option<Uuid>,
 }
@@ -0,0 +1,182 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{types::Json, PgPool};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Follow,         // Someone followed you
+    FollowRequest,  // Someone asked to follow your private account
+    FollowAccepted, // Your request was accepted
+    Badge,
+    PersonalRecord,
+    Kudos,
+    Comment,        // On your post or workout, or a reply to your comment
+    RankBeaten,     // Someone you know passed you on a leaderboard
+    GroupInvite,
+}
+
+impl NotificationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationKind::Follow => "follow",
+            NotificationKind::FollowRequest => "follow_request",
+            NotificationKind::FollowAccepted => "follow_accepted",
+            NotificationKind::Badge => "badge",
+            NotificationKind::PersonalRecord => "personal_record",
+            NotificationKind::Kudos => "kudos",
+            NotificationKind::Comment => "comment",
+            NotificationKind::RankBeaten => "rank_beaten",
+            NotificationKind::GroupInvite => "group_invite",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: String,
+    pub actor_id: Option<Uuid>,
+    pub actor_username: Option<String>,
+    pub data: Json<Value>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Somewhere a stored notification is pushed to: the live event stream, and
+/// later email or push. Delivery can't fail the caller; implementations doing
+/// I/O spawn it and log their own errors.
+pub trait Delivery: Send + Sync {
+    fn deliver(&self, notification: &Notification);
+}
+
+/// Logs every notification instead of sending it anywhere.
+pub struct LogDelivery;
+
+impl Delivery for LogDelivery {
+    fn deliver(&self, notification: &Notification) {
+        tracing::info!("Notification {} ({}) for {}", notification.id, notification.kind, notification.user_id);
+    }
+}
+
+/// Keeps every delivered notification, for checking producers locally.
+#[derive(Clone, Default)]
+pub struct RecordingDelivery {
+    pub delivered: Arc<Mutex<Vec<Notification>>>,
+}
+
+impl Delivery for RecordingDelivery {
+    fn deliver(&self, notification: &Notification) {
+        self.delivered.lock().unwrap().push(notification.clone());
+    }
+}
+
+/// Stores notifications and hands them to every delivery.
+#[derive(Clone)]
+pub struct Notifier {
+    db: PgPool,
+    deliveries: Vec<Arc<dyn Delivery>>,
+}
+
+impl Notifier {
+    pub fn new(db: PgPool) -> Self {
+        Notifier { db, deliveries: Vec::new() }
+    }
+
+    pub fn with_delivery(mut self, delivery: impl Delivery + 'static) -> Self {
+        self.deliveries.push(Arc::new(delivery));
+        self
+    }
+
+    pub async fn notify(
+        &self,
+        user_id: Uuid,
+        kind: NotificationKind,
+        actor_id: Option<Uuid>,
+        data: Value,
+    ) -> Result<Notification, sqlx::Error> {
+        let notification = sqlx::query_as::<_, Notification>(
+            r#"
+            WITH n AS (
+                INSERT INTO notifications (user_id, kind, actor_id, data)
+                VALUES ($1, $2, $3, $4)
+                RETURNING *
+            )
+            SELECT n.id, n.user_id, n.kind, n.actor_id, u.username as actor_username, n.data, n.read_at, n.created_at
+            FROM n
+            LEFT JOIN users u ON n.actor_id = u.id
+            "#
+        )
+        .bind(user_id)
+        .bind(kind.as_str())
+        .bind(actor_id)
+        .bind(Json(&data))
+        .fetch_one(&self.db)
+        .await?;
+
+        for delivery in &self.deliveries {
+            delivery.deliver(&notification);
+        }
+        Ok(notification)
+    }
+
+    /// Notify in the background, so a failure never fails the request that caused it.
+    pub fn send(&self, user_id: Uuid, kind: NotificationKind, actor_id: Option<Uuid>, data: Value) {
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = notifier.notify(user_id, kind, actor_id, data).await {
+                tracing::error!("Failed to notify {} ({}): {}", user_id, kind.as_str(), e);
+            }
+        });
+    }
+}
+
+/// Newest first, optionally only the unread ones.
+pub async fn list(
+    db: &PgPool,
+    user_id: Uuid,
+    unread_only: bool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Notification>, sqlx::Error> {
+    sqlx::query_as::<_, Notification>(
+        r#"
+        SELECT n.id, n.user_id, n.kind, n.actor_id, u.username as actor_username, n.data, n.read_at, n.created_at
+        FROM notifications n
+        LEFT JOIN users u ON n.actor_id = u.id
+        WHERE n.user_id = $1 AND (NOT $2 OR n.read_at IS NULL)
+        ORDER BY n.created_at DESC, n.id
+        LIMIT $3 OFFSET $4
+        "#
+    )
+    .bind(user_id)
+    .bind(unread_only)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn unread_count(db: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL")
+        .bind(user_id)
+        .fetch_one(db)
+        .await
+}
+
+/// Mark the given notifications, or all of them, as read.
+pub async fn mark_read(db: &PgPool, user_id: Uuid, ids: Option<&[Uuid]>) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL AND ($2::uuid[] IS NULL OR id = ANY($2))"
+    )
+    .bind(user_id)
+    .bind(ids)
+    .execute(db)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{models::EquipmentProfileRow, units::{self, WeightUnit}};
+
+// Guards against float noise when dividing loads by plate weights
+const EPSILON: f64 = 1e-6;
+
+// Loadings tried before settling for the best one found so far
+const SEARCH_BUDGET: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlateStock {
+    pub weight: f64,
+    pub pairs: Option<u32>, // None = as many as needed
+}
+
+/// What a lifter has to load a barbell with. All weights are in `unit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquipmentProfile {
+    pub unit: WeightUnit,
+    pub bar_weight: f64,
+    pub collar_weight: f64, // Per collar, one on each sleeve
+    pub plates: Vec<PlateStock>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlateCount {
+    pub weight: f64,
+    pub count: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlateBreakdown {
+    pub unit: WeightUnit,
+    pub target: f64,
+    pub achieved: f64,
+    pub remainder: f64, // target - achieved, never negative unless the empty bar is already heavier
+    pub exact: bool,    // false when the plates can't build `target` and `achieved` is the nearest load below it
+    pub bar_weight: f64,
+    pub collar_weight: f64,
+    pub per_side: Vec<PlateCount>,
+}
+
+fn round4(v: f64) -> f64 {
+    (v * 10_000.0).round() / 10_000.0
+}
+
+impl EquipmentProfile {
+    /// A standard commercial gym: 20 kg bar with metric plates, or a 45 lb bar with imperial plates.
+    pub fn default_for(unit: WeightUnit) -> Self {
+        let (bar_weight, weights): (f64, &[f64]) = match unit {
+            WeightUnit::Kg => (20.0, &[25.0, 20.0, 15.0, 10.0, 5.0, 2.5, 1.25]),
+            WeightUnit::Lb => (45.0, &[45.0, 35.0, 25.0, 10.0, 5.0, 2.5]),
+        };
+
+        EquipmentProfile {
+            unit,
+            bar_weight,
+            collar_weight: 0.0,
+            plates: weights.iter().map(|&weight| PlateStock { weight, pairs: None }).collect(),
+        }
+    }
+
+    /// Heaviest load that doesn't exceed `target` unless the empty bar and
+    /// collars already do. Largest plates go on first, the way lifters load a
+    /// bar; when limited pairs make that fall short (e.g. one 20 but no 5s for
+    /// 25 a side) smaller combinations are searched instead.
+    pub fn breakdown(&self, target: f64, bar_weight: f64) -> PlateBreakdown {
+        let base = bar_weight + 2.0 * self.collar_weight;
+        let per_side_target = ((target - base) / 2.0).max(0.0);
+
+        let mut plates: Vec<PlateStock> = self.plates.iter().filter(|p| p.weight > 0.0).cloned().collect();
+        plates.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+
+        let mut search = PlateSearch {
+            plates: &plates,
+            target: per_side_target,
+            counts: vec![0; plates.len()],
+            best: vec![0; plates.len()],
+            best_load: 0.0,
+            budget: SEARCH_BUDGET,
+        };
+        search.run(0, 0.0);
+
+        let per_side: Vec<PlateCount> = plates.iter()
+            .zip(&search.best)
+            .filter(|(_, count)| **count > 0)
+            .map(|(plate, count)| PlateCount { weight: plate.weight, count: *count })
+            .collect();
+
+        let achieved = round4(base + 2.0 * search.best_load);
+        let remainder = round4(target - achieved);
+
+        PlateBreakdown {
+            unit: self.unit,
+            target,
+            achieved,
+            remainder,
+            exact: remainder.abs() < EPSILON,
+            bar_weight,
+            collar_weight: self.collar_weight,
+            per_side,
+        }
+    }
+
+    /// Nearest load to `target` (in this profile's unit) that can actually be built on the bar.
+    pub fn round_to_achievable(&self, target: f64) -> f64 {
+        let floor = self.breakdown(target, self.bar_weight).achieved;
+
+        let smallest = self.plates.iter()
+            .map(|p| p.weight)
+            .filter(|w| *w > 0.0)
+            .min_by(|a, b| a.total_cmp(b));
+
+        let Some(smallest) = smallest else {
+            return floor;
+        };
+
+        // One more pair of the smallest plate is the next step up
+        let ceil = self.breakdown(target + 2.0 * smallest, self.bar_weight).achieved;
+        if ceil >= target - EPSILON && ceil - target < target - floor {
+            ceil
+        } else {
+            floor
+        }
+    }
+
+    /// Round a weight expressed in `unit` to an achievable load, returned in `unit`.
+    pub fn round_in(&self, weight: f64, unit: WeightUnit) -> f64 {
+        if unit == self.unit {
+            return self.round_to_achievable(weight);
+        }
+        let in_profile_unit = self.unit.convert_kg(unit.to_kg(weight));
+        let rounded = self.round_to_achievable(in_profile_unit);
+        round4(unit.convert_kg(self.unit.to_kg(rounded)))
+    }
+}
+
+/// Depth-first search over plate counts per side, heaviest plates first and
+/// most of each tried first, so the first loading found is the greedy one.
+struct PlateSearch<'a> {
+    plates: &'a [PlateStock],
+    target: f64,
+    counts: Vec<u32>,
+    best: Vec<u32>,
+    best_load: f64,
+    budget: usize,
+}
+
+impl PlateSearch<'_> {
+    /// Returns true once the search should stop: target hit or budget spent.
+    fn run(&mut self, index: usize, load: f64) -> bool {
+        if load > self.best_load + EPSILON {
+            self.best_load = load;
+            self.best.clone_from(&self.counts);
+        }
+        if self.target - self.best_load < EPSILON || self.budget == 0 {
+            return true;
+        }
+        let Some(plate) = self.plates.get(index) else { return false };
+        self.budget -= 1;
+
+        let mut most = ((self.target - load + EPSILON) / plate.weight).floor() as u32;
+        if let Some(pairs) = plate.pairs {
+            most = most.min(pairs);
+        }
+        for count in (0..=most).rev() {
+            self.counts[index] = count;
+            if self.run(index + 1, load + count as f64 * plate.weight) {
+                self.counts[index] = 0;
+                return true;
+            }
+        }
+        self.counts[index] = 0;
+        false
+    }
+}
+
+impl From<EquipmentProfileRow> for EquipmentProfile {
+    fn from(row: EquipmentProfileRow) -> Self {
+        EquipmentProfile {
+            unit: WeightUnit::parse(&row.unit).unwrap_or_default(),
+            bar_weight: row.bar_weight,
+            collar_weight: row.collar_weight,
+            plates: row.plates.0,
+        }
+    }
+}
+
+/// The user's saved equipment, or the standard gym for their preferred unit.
+pub async fn load_equipment(db: &PgPool, user_id: Uuid) -> EquipmentProfile {
+    let row = sqlx::query_as::<_, EquipmentProfileRow>(
+        "SELECT * FROM equipment_profiles WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+    .ok()
+    .flatten();
+
+    match row {
+        Some(row) => row.into(),
+        None => EquipmentProfile::default_for(units::preferred_unit(db, user_id).await),
+    }
+}
+
+/// Whether plate math applies to this exercise at all.
+pub fn is_plate_loaded(equipment: Option<&str>) -> bool {
+    equipment.is_some_and(|e| e.eq_ignore_ascii_case("barbell"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gym(plates: &[(f64, Option<u32>)]) -> EquipmentProfile {
+        EquipmentProfile {
+            unit: WeightUnit::Kg,
+            bar_weight: 20.0,
+            collar_weight: 0.0,
+            plates: plates.iter().map(|&(weight, pairs)| PlateStock { weight, pairs }).collect(),
+        }
+    }
+
+    #[test]
+    fn standard_gym_loads_greedily() {
+        let b = EquipmentProfile::default_for(WeightUnit::Kg).breakdown(142.5, 20.0);
+        assert!(b.exact);
+        assert_eq!(b.achieved, 142.5);
+        let per_side: Vec<(f64, u32)> = b.per_side.iter().map(|p| (p.weight, p.count)).collect();
+        assert_eq!(per_side, vec![(25.0, 2), (10.0, 1), (1.25, 1)]);
+    }
+
+    #[test]
+    fn limited_pairs_fall_back_to_smaller_plates() {
+        // 25 a side: greedy takes the only 20 and is stuck, 15 + 10 works
+        let b = gym(&[(20.0, Some(1)), (15.0, Some(1)), (10.0, Some(1))]).breakdown(70.0, 20.0);
+        assert!(b.exact);
+        assert_eq!(b.achieved, 70.0);
+        let per_side: Vec<(f64, u32)> = b.per_side.iter().map(|p| (p.weight, p.count)).collect();
+        assert_eq!(per_side, vec![(15.0, 1), (10.0, 1)]);
+    }
+
+    #[test]
+    fn unreachable_target_gives_nearest_load_below() {
+        let b = gym(&[(20.0, Some(1)), (10.0, Some(1))]).breakdown(100.0, 20.0);
+        assert!(!b.exact);
+        assert_eq!(b.achieved, 80.0);
+        assert_eq!(b.remainder, 20.0);
+    }
+
+    #[test]
+    fn target_below_bar_loads_nothing() {
+        for target in [0.0, -10.0, 15.0] {
+            let b = gym(&[(20.0, None)]).breakdown(target, 20.0);
+            assert_eq!(b.achieved, 20.0);
+            assert!(b.per_side.is_empty());
+        }
+    }
+
+    #[test]
+    fn zero_weight_plates_are_ignored() {
+        let b = gym(&[(0.0, None), (5.0, None)]).breakdown(30.0, 20.0);
+        assert!(b.exact);
+        assert_eq!(b.per_side.len(), 1);
+    }
+
+    #[test]
+    fn rounds_to_nearest_achievable() {
+        let profile = EquipmentProfile::default_for(WeightUnit::Kg);
+        assert_eq!(profile.round_to_achievable(101.0), 100.0);
+        assert_eq!(profile.round_to_achievable(102.0), 102.5);
+    }
+}
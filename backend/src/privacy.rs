@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{blocks, follows};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    Public,
+    Followers, // Accepted followers
+    OnlyMe,
+}
+
+impl Visibility {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Followers => "followers",
+            Visibility::OnlyMe => "only_me",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "public" => Some(Visibility::Public),
+            "followers" => Some(Visibility::Followers),
+            "only_me" => Some(Visibility::OnlyMe),
+            _ => None,
+        }
+    }
+}
+
+/// Parts of a profile with their own visibility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Activity,
+    WorkoutHistory,
+    Lifts,
+    BodyWeight,
+    BodyMetrics,
+    DateOfBirth,
+    Nutrition,
+}
+
+impl Field {
+    /// Its column in `privacy_settings`.
+    pub fn column(self) -> &'static str {
+        match self {
+            Field::Activity => "activity",
+            Field::WorkoutHistory => "workout_history",
+            Field::Lifts => "lifts",
+            Field::BodyWeight => "body_weight",
+            Field::BodyMetrics => "body_metrics",
+            Field::DateOfBirth => "date_of_birth",
+            Field::Nutrition => "nutrition",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PrivacySettings {
+    pub activity: Visibility,
+    pub workout_history: Visibility,
+    pub lifts: Visibility,
+    pub body_weight: Visibility,
+    pub body_metrics: Visibility,
+    pub date_of_birth: Visibility,
+    pub nutrition: Visibility,
+}
+
+impl Default for PrivacySettings {
+    // Matches the column defaults
+    fn default() -> Self {
+        PrivacySettings {
+            activity: Visibility::Public,
+            workout_history: Visibility::Public,
+            lifts: Visibility::Public,
+            body_weight: Visibility::Followers,
+            body_metrics: Visibility::Followers,
+            date_of_birth: Visibility::OnlyMe,
+            nutrition: Visibility::OnlyMe,
+        }
+    }
+}
+
+impl PrivacySettings {
+    pub fn get(&self, field: Field) -> Visibility {
+        match field {
+            Field::Activity => self.activity,
+            Field::WorkoutHistory => self.workout_history,
+            Field::Lifts => self.lifts,
+            Field::BodyWeight => self.body_weight,
+            Field::BodyMetrics => self.body_metrics,
+            Field::DateOfBirth => self.date_of_birth,
+            Field::Nutrition => self.nutrition,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SettingsRow {
+    activity: String,
+    workout_history: String,
+    lifts: String,
+    body_weight: String,
+    body_metrics: String,
+    date_of_birth: String,
+    nutrition: String,
+}
+
+pub async fn settings(db: &PgPool, user_id: Uuid) -> Result<PrivacySettings, sqlx::Error> {
+    let row = sqlx::query_as::<_, SettingsRow>(
+        "SELECT activity, workout_history, lifts, body_weight, body_metrics, date_of_birth, nutrition FROM privacy_settings WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+
+    let defaults = PrivacySettings::default();
+    let Some(row) = row else {
+        return Ok(defaults);
+    };
+    let parse = |value: &str, default| Visibility::parse(value).unwrap_or(default);
+
+    Ok(PrivacySettings {
+        activity: parse(&row.activity, defaults.activity),
+        workout_history: parse(&row.workout_history, defaults.workout_history),
+        lifts: parse(&row.lifts, defaults.lifts),
+        body_weight: parse(&row.body_weight, defaults.body_weight),
+        body_metrics: parse(&row.body_metrics, defaults.body_metrics),
+        date_of_birth: parse(&row.date_of_birth, defaults.date_of_birth),
+        nutrition: parse(&row.nutrition, defaults.nutrition),
+    })
+}
+
+pub async fn save_settings(db: &PgPool, user_id: Uuid, settings: &PrivacySettings) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO privacy_settings (user_id, activity, workout_history, lifts, body_weight, body_metrics, date_of_birth, nutrition)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (user_id) DO UPDATE SET
+            activity = EXCLUDED.activity,
+            workout_history = EXCLUDED.workout_history,
+            lifts = EXCLUDED.lifts,
+            body_weight = EXCLUDED.body_weight,
+            body_metrics = EXCLUDED.body_metrics,
+            date_of_birth = EXCLUDED.date_of_birth,
+            nutrition = EXCLUDED.nutrition,
+            updated_at = NOW()
+        "#
+    )
+    .bind(user_id)
+    .bind(settings.activity.as_str())
+    .bind(settings.workout_history.as_str())
+    .bind(settings.lifts.as_str())
+    .bind(settings.body_weight.as_str())
+    .bind(settings.body_metrics.as_str())
+    .bind(settings.date_of_birth.as_str())
+    .bind(settings.nutrition.as_str())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// How the viewer relates to the owner of the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Audience {
+    Owner,
+    Follower,
+    Public,
+    Blocked, // Either user blocked the other; sees nothing
+}
+
+/// What one viewer may see of one user's profile.
+#[derive(Debug, Clone, Copy)]
+pub struct Access {
+    pub audience: Audience,
+    pub settings: PrivacySettings,
+}
+
+impl Access {
+    pub fn owner() -> Self {
+        Access { audience: Audience::Owner, settings: PrivacySettings::default() }
+    }
+
+    pub fn can_see(&self, field: Field) -> bool {
+        matches!(
+            (self.audience, self.settings.get(field)),
+            (Audience::Owner, _)
+                | (Audience::Follower | Audience::Public, Visibility::Public)
+                | (Audience::Follower, Visibility::Followers)
+        )
+    }
+
+    /// `value` if the field is visible.
+    pub fn filter<T>(&self, field: Field, value: Option<T>) -> Option<T> {
+        value.filter(|_| self.can_see(field))
+    }
+}
+
+/// SQL for the visibility the user in the SQL expression `owner` chose for
+/// `field`, falling back to the default like [`settings`].
+pub fn visibility_sql(field: Field, owner: &str) -> String {
+    format!(
+        "COALESCE((SELECT ps.{} FROM privacy_settings ps WHERE ps.user_id = {owner}), '{}')",
+        field.column(),
+        PrivacySettings::default().get(field).as_str(),
+    )
+}
+
+/// SQL condition for [`Access::can_see`], for queries over many owners. Blocks
+/// are left to the query.
+pub fn can_see_sql(field: Field, viewer: &str, owner: &str) -> String {
+    let visibility = visibility_sql(field, owner);
+    format!(
+        r#"({owner} = {viewer} OR {visibility} = 'public' OR ({visibility} = 'followers' AND EXISTS (
+            SELECT 1 FROM follows f WHERE f.follower_id = {viewer} AND f.following_id = {owner} AND f.status = 'accepted'
+        )))"#
+    )
+}
+
+pub async fn access(db: &PgPool, viewer: Uuid, owner: Uuid) -> Result<Access, sqlx::Error> {
+    if viewer == owner {
+        return Ok(Access::owner());
+    }
+
+    let audience = if blocks::is_blocked(db, viewer, owner).await? {
+        Audience::Blocked
+    } else if follows::is_follower(db, viewer, owner).await? {
+        Audience::Follower
+    } else {
+        Audience::Public
+    };
+    let settings = settings(db, owner).await?;
+
+    Ok(Access { audience, settings })
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Comment;
+
+pub const MAX_COMMENT_LENGTH: usize = 2000;
+
+/// What kudos and comments are attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "id", rename_all = "lowercase")]
+pub enum Target {
+    Post(Uuid),
+    Workout(Uuid),
+    Set(Uuid),
+}
+
+impl Target {
+    pub fn id(self) -> Uuid {
+        match self {
+            Target::Post(id) | Target::Workout(id) | Target::Set(id) => id,
+        }
+    }
+
+    /// Column of `kudos` and `comments` holding the target.
+    fn column(self) -> &'static str {
+        match self {
+            Target::Post(_) => "post_id",
+            Target::Workout(_) => "workout_id",
+            Target::Set(_) => "set_id",
+        }
+    }
+
+    pub fn of(comment: &Comment) -> Option<Target> {
+        match (comment.post_id, comment.workout_id, comment.set_id) {
+            (Some(id), _, _) => Some(Target::Post(id)),
+            (None, Some(id), _) => Some(Target::Workout(id)),
+            (None, None, Some(id)) => Some(Target::Set(id)),
+            (None, None, None) => None,
+        }
+    }
+
+    /// The user who owns the post, workout or set, `None` if it doesn't exist.
+    /// Only finished workouts can be reacted to.
+    pub async fn owner(self, db: &PgPool) -> Result<Option<Uuid>, sqlx::Error> {
+        let sql = match self {
+            Target::Post(_) => "SELECT user_id FROM posts WHERE id = $1",
+            Target::Workout(_) => "SELECT user_id FROM workouts WHERE id = $1 AND end_time IS NOT NULL",
+            Target::Set(_) => "SELECT w.user_id FROM sets s JOIN workouts w ON s.workout_id = w.id WHERE s.id = $1",
+        };
+        sqlx::query_scalar::<_, Uuid>(sql)
+            .bind(self.id())
+            .fetch_optional(db)
+            .await
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReactionCounts {
+    pub kudos_count: i64,
+    pub comment_count: i64, // Not counting deleted comments
+    pub has_kudos: bool,    // From the viewer
+}
+
+pub async fn counts(db: &PgPool, target: Target, viewer: Uuid) -> Result<ReactionCounts, sqlx::Error> {
+    let column = target.column();
+    sqlx::query_as::<_, ReactionCounts>(&format!(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM kudos WHERE {column} = $1) as kudos_count,
+            (SELECT COUNT(*) FROM comments WHERE {column} = $1 AND deleted_at IS NULL) as comment_count,
+            EXISTS (SELECT 1 FROM kudos WHERE {column} = $1 AND user_id = $2) as has_kudos
+        "#
+    ))
+    .bind(target.id())
+    .bind(viewer)
+    .fetch_one(db)
+    .await
+}
+
+/// Give kudos once. Returns whether they were new.
+pub async fn give_kudos(db: &PgPool, target: Target, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let inserted = sqlx::query(&format!(
+        "INSERT INTO kudos (user_id, {column}) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        column = target.column()
+    ))
+    .bind(user_id)
+    .bind(target.id())
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    Ok(inserted > 0)
+}
+
+pub async fn remove_kudos(db: &PgPool, target: Target, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!("DELETE FROM kudos WHERE user_id = $1 AND {column} = $2", column = target.column()))
+        .bind(user_id)
+        .bind(target.id())
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn add_comment(
+    db: &PgPool,
+    target: Target,
+    user_id: Uuid,
+    parent_id: Option<Uuid>,
+    body: &str,
+) -> Result<Comment, sqlx::Error> {
+    sqlx::query_as::<_, Comment>(&format!(
+        "INSERT INTO comments (user_id, {column}, parent_id, body) VALUES ($1, $2, $3, $4) RETURNING *",
+        column = target.column()
+    ))
+    .bind(user_id)
+    .bind(target.id())
+    .bind(parent_id)
+    .bind(body)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn get_comment(db: &PgPool, id: Uuid) -> Result<Option<Comment>, sqlx::Error> {
+    sqlx::query_as::<_, Comment>("SELECT * FROM comments WHERE id = $1")
+        .bind(id)
+        .fetch_optional(db)
+        .await
+}
+
+/// Edit a live comment of `user_id`. `None` if there is no such comment.
+pub async fn edit_comment(db: &PgPool, id: Uuid, user_id: Uuid, body: &str) -> Result<Option<Comment>, sqlx::Error> {
+    sqlx::query_as::<_, Comment>(
+        "UPDATE comments SET body = $3, edited_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL RETURNING *"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(body)
+    .fetch_optional(db)
+    .await
+}
+
+/// Delete a comment of `user_id`, keeping it as a placeholder so replies stay
+/// in their thread. Returns whether a comment was deleted.
+pub async fn delete_comment(db: &PgPool, id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query(
+        "UPDATE comments SET body = '', deleted_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    Ok(deleted > 0)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentNode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub parent_id: Option<Uuid>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted: bool,
+    pub replies: Vec<CommentNode>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CommentRow {
+    #[sqlx(flatten)]
+    comment: Comment,
+    username: String,
+}
+
+/// Every comment on `target` as threads, oldest first at each level.
+pub async fn threads(db: &PgPool, target: Target) -> Result<Vec<CommentNode>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, CommentRow>(&format!(
+        r#"
+        SELECT c.*, u.username
+        FROM comments c
+        JOIN users u ON c.user_id = u.id
+        WHERE c.{column} = $1
+        ORDER BY c.created_at, c.id
+        "#,
+        column = target.column()
+    ))
+    .bind(target.id())
+    .fetch_all(db)
+    .await?;
+
+    let mut children: HashMap<Option<Uuid>, Vec<CommentRow>> = HashMap::new();
+    for row in rows {
+        children.entry(row.comment.parent_id).or_default().push(row);
+    }
+
+    fn build(parent: Option<Uuid>, children: &mut HashMap<Option<Uuid>, Vec<CommentRow>>) -> Vec<CommentNode> {
+        children.remove(&parent).unwrap_or_default()
+            .into_iter()
+            .map(|row| {
+                let replies = build(Some(row.comment.id), children);
+                CommentNode {
+                    id: row.comment.id,
+                    user_id: row.comment.user_id,
+                    username: row.username,
+                    parent_id: row.comment.parent_id,
+                    body: row.comment.body,
+                    created_at: row.comment.created_at,
+                    edited_at: row.comment.edited_at,
+                    deleted: row.comment.deleted_at.is_some(),
+                    replies,
+                }
+            })
+            .collect()
+    }
+
+    Ok(build(None, &mut children))
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{achievements, badges, xp};
+
+#[derive(Debug, Default)]
+pub struct RecomputeSummary {
+    pub workouts: usize,
+    pub prs: usize,
+    pub badges: usize,
+    pub xp: i64,
+}
+
+#[derive(sqlx::FromRow)]
+struct HistorySet {
+    id: Uuid,
+    exercise_id: Uuid,
+    weight_kg: f32,
+    reps: i32,
+    set_type: String,
+}
+
+/// Rebuild everything derived from a user's history: PR flags on sets, badges
+/// and milestones backed by `badge_definitions`, and XP. Challenge rewards are
+/// left alone. Runs in one transaction, so nobody sees the badges gone before
+/// they are awarded again.
+pub async fn recompute_user(db: &PgPool, user_id: Uuid) -> Result<RecomputeSummary, sqlx::Error> {
+    let mut summary = RecomputeSummary::default();
+    let mut tx = db.begin().await?;
+
+    // 1. Replay PR detection in the order the sets were logged
+    let sets = sqlx::query_as::<_, HistorySet>(
+        r#"
+        SELECT s.id, s.exercise_id, s.weight_kg, s.reps, s.set_type
+        FROM sets s
+        JOIN workouts w ON s.workout_id = w.id
+        WHERE w.user_id = $1
+        ORDER BY s.created_at, s.id
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut history: HashMap<Uuid, Vec<(f32, i32)>> = HashMap::new();
+    let mut ids = Vec::with_capacity(sets.len());
+    let mut kinds: Vec<Option<&str>> = Vec::with_capacity(sets.len());
+    for set in &sets {
+        let kind = if set.set_type == "working" {
+            let previous = history.entry(set.exercise_id).or_default();
+            let prev_max_weight = previous.iter().map(|(w, _)| *w).fold(0.0, f32::max);
+            let prev_max_reps = previous.iter()
+                .filter(|(w, _)| *w >= set.weight_kg)
+                .map(|(_, r)| *r)
+                .max()
+                .unwrap_or(0);
+            previous.push((set.weight_kg, set.reps));
+            badges::classify_pr(set.weight_kg, set.reps, prev_max_weight, prev_max_reps)
+        } else {
+            None
+        };
+        summary.prs += kind.is_some() as usize;
+        ids.push(set.id);
+        kinds.push(kind);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE sets SET pr_kind = v.kind
+        FROM UNNEST($1::uuid[], $2::text[]) AS v(id, kind)
+        WHERE sets.id = v.id
+        "#
+    )
+    .bind(&ids)
+    .bind(&kinds)
+    .execute(&mut *tx)
+    .await?;
+
+    // 2. Drop the badges the rules engine owns and award them again
+    sqlx::query("DELETE FROM user_badges WHERE user_id = $1 AND source = 'rule' AND badge_name IN (SELECT name FROM badge_definitions)")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let workouts = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+        "SELECT id, end_time FROM workouts WHERE user_id = $1 AND end_time IS NOT NULL ORDER BY end_time, id"
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for (workout_id, ended_at) in workouts {
+        let facts = badges::gather_facts(&mut tx, workout_id, ended_at).await?;
+        summary.badges += badges::award_for_workout(&mut tx, workout_id, &facts).await?.len();
+        summary.badges += achievements::award_milestones(&mut tx, user_id, workout_id, ended_at).await?.len();
+
+        // 3. XP, stored per workout
+        sqlx::query("UPDATE workouts SET xp_earned = $2 WHERE id = $1")
+            .bind(workout_id)
+            .bind(xp::for_workout(&facts).total as i32)
+            .execute(&mut *tx)
+            .await?;
+        summary.workouts += 1;
+    }
+
+    summary.xp = xp::refresh_user_xp(&mut *tx, user_id).await?;
+
+    tx.commit().await?;
+    Ok(summary)
+}
@@ -0,0 +1,193 @@
+//! Relative strength scores for a powerlifting total (squat + bench + deadlift, kg).
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::achievements::{self, Lift};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+impl Sex {
+    /// From `users.gender`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "male" => Some(Sex::Male),
+            "female" => Some(Sex::Female),
+            _ => None,
+        }
+    }
+}
+
+fn polynomial(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+/// Wilks (original coefficients).
+pub fn wilks(sex: Sex, bodyweight_kg: f64, total_kg: f64) -> f64 {
+    let (coefficients, bw): (&[f64], f64) = match sex {
+        Sex::Male => (
+            &[-216.0475144, 16.2606339, -0.002388645, -0.00113732, 7.01863e-06, -1.291e-08],
+            bodyweight_kg.clamp(40.0, 201.9),
+        ),
+        Sex::Female => (
+            &[594.31747775582, -27.23842536447, 0.82112226871, -0.00930733913, 4.731582e-05, -9.054e-08],
+            bodyweight_kg.clamp(26.51, 154.53),
+        ),
+    };
+    total_kg * 500.0 / polynomial(coefficients, bw)
+}
+
+/// DOTS, the successor to Wilks used by most federations outside the IPF.
+pub fn dots(sex: Sex, bodyweight_kg: f64, total_kg: f64) -> f64 {
+    let (coefficients, bw): (&[f64], f64) = match sex {
+        Sex::Male => (
+            &[-307.75076, 24.0900756, -0.1918759221, 0.0007391293, -0.000001093],
+            bodyweight_kg.clamp(40.0, 210.0),
+        ),
+        Sex::Female => (
+            &[-57.96288, 13.6175032, -0.1126655495, 0.0005158568, -0.0000010706],
+            bodyweight_kg.clamp(40.0, 150.0),
+        ),
+    };
+    total_kg * 500.0 / polynomial(coefficients, bw)
+}
+
+/// IPF GL points (2020 coefficients, classic/raw powerlifting).
+pub fn ipf_gl(sex: Sex, bodyweight_kg: f64, total_kg: f64) -> f64 {
+    let (a, b, c) = match sex {
+        Sex::Male => (1199.72839, 1025.18162, 0.00921),
+        Sex::Female => (610.32796, 1045.59282, 0.03048),
+    };
+    let bw = bodyweight_kg.max(35.0);
+    total_kg * 100.0 / (a - b * (-c * bw).exp())
+}
+
+/// Which performances make up the total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrengthBasis {
+    #[default]
+    E1rm,   // Best of actual singles and estimated 1RMs from sets of up to 10
+    Actual, // Actual singles only
+}
+
+#[derive(Debug, Serialize)]
+pub struct Scores {
+    pub wilks: f64,
+    pub dots: f64,
+    pub ipf_gl: f64,
+}
+
+impl Scores {
+    pub fn new(sex: Sex, bodyweight_kg: f64, total_kg: f64) -> Self {
+        Scores {
+            wilks: wilks(sex, bodyweight_kg, total_kg),
+            dots: dots(sex, bodyweight_kg, total_kg),
+            ipf_gl: ipf_gl(sex, bodyweight_kg, total_kg),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StrengthProfile {
+    pub basis: StrengthBasis,
+    pub sex: Option<Sex>,
+    pub bodyweight_kg: Option<f64>,
+    pub squat_kg: Option<f64>,
+    pub bench_kg: Option<f64>,
+    pub deadlift_kg: Option<f64>,
+    pub total_kg: Option<f64>, // Once all three lifts are logged
+    pub scores: Option<Scores>, // Also needs sex and bodyweight
+}
+
+/// A user's current total and its scores, against their latest bodyweight.
+pub async fn strength_profile(db: &PgPool, user_id: Uuid, basis: StrengthBasis) -> Result<StrengthProfile, sqlx::Error> {
+    let (gender, bodyweight_kg) = sqlx::query_as::<_, (Option<String>, Option<f64>)>(
+        r#"
+        SELECT
+            u.gender,
+            COALESCE(
+                (SELECT wl.weight_kg FROM weight_logs wl WHERE wl.user_id = u.id ORDER BY wl.logged_at DESC LIMIT 1),
+                u.current_weight_kg
+            )
+        FROM users u
+        WHERE u.id = $1
+        "#
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .unwrap_or_default();
+
+    let strength = achievements::gather_strength(db, user_id, Utc::now()).await?;
+    let best = |lift: Lift| {
+        let lift = strength.lifts.get(&lift)?;
+        let kg = match basis {
+            StrengthBasis::E1rm => lift.best_kg(),
+            StrengthBasis::Actual => lift.single_kg,
+        };
+        Some(kg).filter(|kg| *kg > 0.0)
+    };
+
+    let (squat_kg, bench_kg, deadlift_kg) = (best(Lift::Squat), best(Lift::Bench), best(Lift::Deadlift));
+    let total_kg = match (squat_kg, bench_kg, deadlift_kg) {
+        (Some(squat), Some(bench), Some(deadlift)) => Some(squat + bench + deadlift),
+        _ => None,
+    };
+    let sex = gender.as_deref().and_then(Sex::parse);
+    let bodyweight_kg = bodyweight_kg.filter(|w| *w > 0.0);
+
+    let scores = match (sex, bodyweight_kg, total_kg) {
+        (Some(sex), Some(bw), Some(total)) => Some(Scores::new(sex, bw, total)),
+        _ => None,
+    };
+
+    Ok(StrengthProfile { basis, sex, bodyweight_kg, squat_kg, bench_kg, deadlift_kg, total_kg, scores })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dots_matches_the_published_formula() {
+        assert!((dots(Sex::Male, 100.0, 700.0) - 430.86).abs() < 0.01);
+    }
+
+    #[test]
+    fn zero_total_scores_zero() {
+        let scores = Scores::new(Sex::Female, 60.0, 0.0);
+        assert_eq!((scores.wilks, scores.dots, scores.ipf_gl), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn lighter_lifter_scores_higher_for_the_same_total() {
+        for sex in [Sex::Male, Sex::Female] {
+            let (light, heavy) = (Scores::new(sex, 60.0, 400.0), Scores::new(sex, 90.0, 400.0));
+            assert!(light.wilks > heavy.wilks);
+            assert!(light.dots > heavy.dots);
+            assert!(light.ipf_gl > heavy.ipf_gl);
+        }
+    }
+
+    #[test]
+    fn bodyweight_is_clamped_to_the_formula_range() {
+        assert_eq!(wilks(Sex::Male, 10.0, 300.0), wilks(Sex::Male, 40.0, 300.0));
+        assert_eq!(dots(Sex::Female, 400.0, 300.0), dots(Sex::Female, 150.0, 300.0));
+        assert_eq!(ipf_gl(Sex::Male, 0.0, 300.0), ipf_gl(Sex::Male, 35.0, 300.0));
+    }
+
+    #[test]
+    fn parses_sex_from_the_profile() {
+        assert_eq!(Sex::parse("male"), Some(Sex::Male));
+        assert_eq!(Sex::parse("female"), Some(Sex::Female));
+        assert_eq!(Sex::parse("other"), None);
+    }
+}
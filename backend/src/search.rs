@@ -0,0 +1,147 @@
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Escape `\`, `%` and `_` so user input matches literally in `LIKE` patterns.
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserResult {
+    pub id: Uuid,
+    pub username: String,
+    pub bio: Option<String>,
+    pub is_following: bool, // Does the viewer follow them?
+    pub follows_you: bool,
+}
+
+/// Users whose username or bio contains `query`, or whose username is close
+/// to it. Prefix matches rank first, and people the viewer already knows get a
+/// boost, mutual follows the most. Users blocked either way are left out.
+pub async fn users(
+    db: &PgPool,
+    viewer: Uuid,
+    query: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<UserResult>, sqlx::Error> {
+    let escaped = escape_like(query);
+
+    sqlx::query_as::<_, UserResult>(
+        r#"
+        WITH matches AS (
+            SELECT
+                u.id,
+                u.username,
+                u.bio,
+                EXISTS (SELECT 1 FROM follows WHERE follower_id = $1 AND following_id = u.id AND status = 'accepted') as is_following,
+                EXISTS (SELECT 1 FROM follows WHERE follower_id = u.id AND following_id = $1 AND status = 'accepted') as follows_you,
+                similarity(u.username, $2)
+                    + CASE WHEN u.username ILIKE $4 ESCAPE '\' THEN 1.0 ELSE 0 END
+                    + 0.5 * word_similarity($2, COALESCE(u.bio, '')) as score
+            FROM users u
+            WHERE u.id <> $1
+              AND (u.username ILIKE $3 ESCAPE '\' OR u.bio ILIKE $3 ESCAPE '\' OR u.username % $2)
+              AND NOT EXISTS (
+                  SELECT 1 FROM user_blocks b
+                  WHERE (b.blocker_id = $1 AND b.blocked_id = u.id) OR (b.blocker_id = u.id AND b.blocked_id = $1)
+              )
+        )
+        SELECT id, username, bio, is_following, follows_you
+        FROM matches
+        ORDER BY
+            score
+                + CASE WHEN is_following AND follows_you THEN 1.0
+                       WHEN is_following OR follows_you THEN 0.5
+                       ELSE 0 END DESC,
+            username,
+            id
+        LIMIT $5 OFFSET $6
+        "#
+    )
+    .bind(viewer)
+    .bind(query)
+    .bind(format!("%{}%", escaped))
+    .bind(format!("{}%", escaped))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Suggestion {
+    pub id: Uuid,
+    pub username: String,
+    pub bio: Option<String>,
+    pub mutual_follows: i64,   // People the viewer follows who follow them
+    pub shared_templates: i64, // Templates both have trained from
+}
+
+/// People the viewer might want to follow: followed by people they follow,
+/// or training from the same templates. Users already followed or requested,
+/// and users blocked either way, are left out.
+pub async fn suggestions(db: &PgPool, viewer: Uuid, limit: i64) -> Result<Vec<Suggestion>, sqlx::Error> {
+    sqlx::query_as::<_, Suggestion>(
+        r#"
+        WITH mutual AS (
+            SELECT f2.following_id as id, COUNT(*) as n
+            FROM follows f1
+            JOIN follows f2 ON f2.follower_id = f1.following_id AND f2.status = 'accepted'
+            WHERE f1.follower_id = $1 AND f1.status = 'accepted'
+            GROUP BY f2.following_id
+        ),
+        my_templates AS (
+            SELECT template_id FROM workouts WHERE user_id = $1 AND template_id IS NOT NULL
+            UNION
+            SELECT id FROM workout_templates WHERE user_id = $1
+        ),
+        shared AS (
+            SELECT w.user_id as id, COUNT(DISTINCT w.template_id) as n
+            FROM workouts w
+            JOIN my_templates t ON w.template_id = t.template_id
+            GROUP BY w.user_id
+        ),
+        candidates AS (
+            SELECT COALESCE(m.id, s.id) as id, COALESCE(m.n, 0) as mutual_follows, COALESCE(s.n, 0) as shared_templates
+            FROM mutual m
+            FULL JOIN shared s ON m.id = s.id
+        )
+        SELECT u.id, u.username, u.bio, c.mutual_follows, c.shared_templates
+        FROM candidates c
+        JOIN users u ON c.id = u.id
+        WHERE u.id <> $1
+          AND NOT EXISTS (SELECT 1 FROM follows WHERE follower_id = $1 AND following_id = u.id)
+          AND NOT EXISTS (
+              SELECT 1 FROM user_blocks b
+              WHERE (b.blocker_id = $1 AND b.blocked_id = u.id) OR (b.blocker_id = u.id AND b.blocked_id = $1)
+          )
+        ORDER BY c.mutual_follows * 2 + c.shared_templates DESC, u.username
+        LIMIT $2
+        "#
+    )
+    .bind(viewer)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(escape_like(r"50%_off\"), r"50\%\_off\\");
+        assert_eq!(escape_like("squat king"), "squat king");
+        assert_eq!(escape_like(""), "");
+    }
+}
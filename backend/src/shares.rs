@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Bytes of the HMAC kept in a link; 128 bits is plenty against forgery
+const SIGNATURE_BYTES: usize = 16;
+
+/// Signs share ids into public links and checks links coming back. A link is
+/// `<share id>.<signature>`, so forged or mangled links are turned away
+/// without touching the database.
+#[derive(Clone)]
+pub struct ShareSigner {
+    key: Vec<u8>,
+}
+
+impl ShareSigner {
+    pub fn new(secret: &[u8]) -> Self {
+        ShareSigner { key: secret.to_vec() }
+    }
+
+    /// Use `SHARE_SECRET`. Without it links are signed with a random key and
+    /// stop working when the server restarts.
+    pub fn from_env() -> Self {
+        match std::env::var("SHARE_SECRET") {
+            Ok(secret) if !secret.is_empty() => ShareSigner::new(secret.as_bytes()),
+            _ => {
+                tracing::warn!("SHARE_SECRET is not set; share links will break on restart");
+                let random = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+                ShareSigner::new(random.as_bytes())
+            }
+        }
+    }
+
+    fn mac(&self, share_id: Uuid) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC takes keys of any length");
+        mac.update(share_id.as_bytes());
+        mac
+    }
+
+    pub fn token(&self, share_id: Uuid) -> String {
+        let signature = self.mac(share_id).finalize().into_bytes();
+        let hex: String = signature[..SIGNATURE_BYTES].iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}.{}", share_id.simple(), hex)
+    }
+
+    /// The share id in a link, if the signature matches.
+    pub fn verify(&self, token: &str) -> Option<Uuid> {
+        let (id, hex) = token.split_once('.')?;
+        let share_id = Uuid::parse_str(id).ok()?;
+        if hex.len() != SIGNATURE_BYTES * 2 || !hex.is_ascii() {
+            return None;
+        }
+        let signature = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+
+        self.mac(share_id).verify_truncated_left(&signature).ok()?;
+        Some(share_id)
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Share {
+    pub id: Uuid,
+    pub workout_id: Uuid,
+    pub user_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// The workout's live share, creating one if there is none.
+pub async fn share(db: &PgPool, workout_id: Uuid, user_id: Uuid) -> Result<Share, sqlx::Error> {
+    let created = sqlx::query_as::<_, Share>(
+        r#"
+        INSERT INTO workout_shares (workout_id, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT (workout_id) WHERE revoked_at IS NULL DO NOTHING
+        RETURNING *
+        "#
+    )
+    .bind(workout_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+
+    match created {
+        Some(share) => Ok(share),
+        None => active_share(db, workout_id).await?.ok_or(sqlx::Error::RowNotFound),
+    }
+}
+
+pub async fn active_share(db: &PgPool, workout_id: Uuid) -> Result<Option<Share>, sqlx::Error> {
+    sqlx::query_as::<_, Share>("SELECT * FROM workout_shares WHERE workout_id = $1 AND revoked_at IS NULL")
+        .bind(workout_id)
+        .fetch_optional(db)
+        .await
+}
+
+/// Revoke the workout's live share. Returns whether there was one.
+pub async fn revoke(db: &PgPool, workout_id: Uuid) -> Result<bool, sqlx::Error> {
+    let revoked = sqlx::query("UPDATE workout_shares SET revoked_at = NOW() WHERE workout_id = $1 AND revoked_at IS NULL")
+        .bind(workout_id)
+        .execute(db)
+        .await?
+        .rows_affected();
+
+    Ok(revoked > 0)
+}
+
+/// The workout behind a share, unless it has been revoked.
+pub async fn shared_workout(db: &PgPool, share_id: Uuid) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>("SELECT workout_id FROM workout_shares WHERE id = $1 AND revoked_at IS NULL")
+        .bind(share_id)
+        .fetch_optional(db)
+        .await
+}
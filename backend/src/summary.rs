@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::units::{self, WeightUnit};
+
+/// The heaviest working set of an exercise.
+#[derive(Debug, Clone, Serialize)]
+pub struct TopSet {
+    pub weight: f64,
+    pub reps: i32,
+    pub pr_kind: Option<String>,
+}
+
+/// A set that was a PR when it was logged.
+#[derive(Debug, Clone, Serialize)]
+pub struct PrSet {
+    pub exercise_name: String,
+    pub weight: f64,
+    pub reps: i32,
+    pub kind: String, // "1rm" or "volume"
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExerciseSummary {
+    pub exercise_id: Uuid,
+    pub name: String,
+    pub set_count: i64, // Working sets only
+    pub top_set: Option<TopSet>,
+    pub volume: f64,
+}
+
+/// A finished workout as shown outside the app: no ids or notes beyond what
+/// the card needs, weights in the owner's unit.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkoutCard {
+    pub workout_id: Uuid,
+    pub username: String,
+    pub instagram_handle: Option<String>,
+    pub twitter_handle: Option<String>,
+    pub name: Option<String>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub duration_minutes: f64,
+    pub unit: WeightUnit,
+    pub total_volume: f64,
+    pub pr_count: i64,
+    pub calories_burned: Option<i32>,
+    pub exercises: Vec<ExerciseSummary>,
+    pub prs: Vec<PrSet>,
+    pub badges: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CardWorkout {
+    user_id: Uuid,
+    username: String,
+    instagram_handle: Option<String>,
+    twitter_handle: Option<String>,
+    name: Option<String>,
+    start_time: DateTime<Utc>,
+    end_time: Option<DateTime<Utc>>,
+    calories_burned: Option<i32>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CardSet {
+    exercise_id: Uuid,
+    exercise_name: String,
+    weight_kg: f32,
+    reps: i32,
+    entered_weight: Option<f64>,
+    entered_unit: Option<String>,
+    pr_kind: Option<String>,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Build the card for a finished workout. `None` if it doesn't exist or is
+/// still in progress.
+pub async fn workout_card(db: &PgPool, workout_id: Uuid) -> Result<Option<WorkoutCard>, sqlx::Error> {
+    let workout = sqlx::query_as::<_, CardWorkout>(
+        r#"
+        SELECT w.user_id, u.username, u.instagram_handle, u.twitter_handle, w.name, COALESCE(w.start_time, w.created_at) as start_time, w.end_time, w.calories_burned
+        FROM workouts w
+        JOIN users u ON w.user_id = u.id
+        WHERE w.id = $1
+        "#
+    )
+    .bind(workout_id)
+    .fetch_optional(db)
+    .await?;
+    let Some(workout) = workout else { return Ok(None) };
+    let Some(end_time) = workout.end_time else { return Ok(None) };
+
+    // Working sets in the order the exercises were first done
+    let sets = sqlx::query_as::<_, CardSet>(
+        r#"
+        SELECT s.exercise_id, e.name as exercise_name, s.weight_kg, s.reps, s.entered_weight, s.entered_unit, s.pr_kind
+        FROM sets s
+        JOIN exercises e ON s.exercise_id = e.id
+        WHERE s.workout_id = $1 AND s.set_type = 'working'
+        ORDER BY MIN(s.created_at) OVER (PARTITION BY s.exercise_id), s.created_at
+        "#
+    )
+    .bind(workout_id)
+    .fetch_all(db)
+    .await?;
+
+    let badges = sqlx::query_scalar::<_, String>(
+        "SELECT badge_name FROM user_badges WHERE workout_id = $1 ORDER BY earned_at, badge_name"
+    )
+    .bind(workout_id)
+    .fetch_all(db)
+    .await?;
+
+    let unit = units::preferred_unit(db, workout.user_id).await;
+    let mut exercises: Vec<ExerciseSummary> = Vec::new();
+    let mut best_kg: Vec<(f32, i32)> = Vec::new();
+    let mut total_volume_kg = 0.0;
+    let mut prs = Vec::new();
+
+    for set in sets {
+        let volume_kg = set.weight_kg as f64 * set.reps as f64;
+        total_volume_kg += volume_kg;
+        let weight = units::display(set.weight_kg as f64, set.entered_weight, set.entered_unit.as_deref(), unit);
+        if let Some(kind) = &set.pr_kind {
+            prs.push(PrSet { exercise_name: set.exercise_name.clone(), weight, reps: set.reps, kind: kind.clone() });
+        }
+
+        let index = match exercises.iter().position(|e| e.exercise_id == set.exercise_id) {
+            Some(index) => index,
+            None => {
+                exercises.push(ExerciseSummary {
+                    exercise_id: set.exercise_id,
+                    name: set.exercise_name.clone(),
+                    set_count: 0,
+                    top_set: None,
+                    volume: 0.0,
+                });
+                best_kg.push((f32::MIN, 0));
+                exercises.len() - 1
+            }
+        };
+
+        let exercise = &mut exercises[index];
+        exercise.set_count += 1;
+        exercise.volume += volume_kg;
+        if (set.weight_kg, set.reps) > best_kg[index] {
+            best_kg[index] = (set.weight_kg, set.reps);
+            exercise.top_set = Some(TopSet {
+                weight,
+                reps: set.reps,
+                pr_kind: set.pr_kind,
+            });
+        }
+    }
+
+    for exercise in &mut exercises {
+        exercise.volume = round2(unit.convert_kg(exercise.volume));
+    }
+
+    Ok(Some(WorkoutCard {
+        workout_id,
+        username: workout.username,
+        instagram_handle: workout.instagram_handle,
+        twitter_handle: workout.twitter_handle,
+        name: workout.name,
+        start_time: workout.start_time,
+        end_time,
+        duration_minutes: round2((end_time - workout.start_time).num_seconds().max(0) as f64 / 60.0),
+        unit,
+        total_volume: round2(unit.convert_kg(total_volume_kg)),
+        pr_count: prs.len() as i64,
+        calories_burned: workout.calories_burned,
+        exercises,
+        prs,
+        badges,
+    }))
+}
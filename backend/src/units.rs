@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// Exact by definition (international avoirdupois pound)
+pub const KG_PER_LB: f64 = 0.45359237;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeightUnit {
+    #[default]
+    Kg,
+    Lb,
+}
+
+impl WeightUnit {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WeightUnit::Kg => "kg",
+            WeightUnit::Lb => "lb",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "kg" => Some(WeightUnit::Kg),
+            "lb" => Some(WeightUnit::Lb),
+            _ => None,
+        }
+    }
+
+    pub fn to_kg(self, value: f64) -> f64 {
+        match self {
+            WeightUnit::Kg => value,
+            WeightUnit::Lb => value * KG_PER_LB,
+        }
+    }
+
+    pub fn convert_kg(self, kg: f64) -> f64 {
+        match self {
+            WeightUnit::Kg => kg,
+            WeightUnit::Lb => kg / KG_PER_LB,
+        }
+    }
+}
+
+/// A weight exactly as the user typed it. We keep this next to the
+/// canonical `*_kg` column so it can be echoed back without a round trip
+/// through kilograms (225 lb must not come back as 224.98).
+#[derive(Debug, Clone, Copy)]
+pub struct EnteredWeight {
+    pub value: f64,
+    pub unit: WeightUnit,
+}
+
+impl EnteredWeight {
+    /// Resolve the weight fields of a request. An explicit `value` wins and is
+    /// read in `unit` (or the user's preference); otherwise fall back to the
+    /// legacy `*_kg` field.
+    pub fn resolve(
+        value: Option<f64>,
+        unit: Option<WeightUnit>,
+        legacy_kg: Option<f64>,
+        preferred: WeightUnit,
+    ) -> Option<Self> {
+        match (value, legacy_kg) {
+            (Some(v), _) => Some(EnteredWeight { value: v, unit: unit.unwrap_or(preferred) }),
+            (None, Some(kg)) => Some(EnteredWeight { value: kg, unit: WeightUnit::Kg }),
+            (None, None) => None,
+        }
+    }
+
+    pub fn kg(&self) -> f64 {
+        self.unit.to_kg(self.value)
+    }
+}
+
+/// Render a stored weight in `unit`. If it was entered in that unit the
+/// original value is returned untouched, otherwise the kg value is converted
+/// and rounded to two decimals.
+pub fn display(kg: f64, entered: Option<f64>, entered_unit: Option<&str>, unit: WeightUnit) -> f64 {
+    match (entered, entered_unit.and_then(WeightUnit::parse)) {
+        (Some(v), Some(u)) if u == unit => v,
+        _ => (unit.convert_kg(kg) * 100.0).round() / 100.0,
+    }
+}
+
+/// Optional `?unit=` override for GET endpoints.
+#[derive(Deserialize)]
+pub struct UnitQuery {
+    pub unit: Option<WeightUnit>,
+}
+
+/// The user's preferred unit, defaulting to kilograms if the user is unknown.
+pub async fn preferred_unit(db: &PgPool, user_id: Uuid) -> WeightUnit {
+    sqlx::query_scalar::<_, String>("SELECT unit_system FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|u| WeightUnit::parse(&u))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pounds_round_trip_through_kilograms() {
+        let kg = WeightUnit::Lb.to_kg(225.0);
+        assert!((kg - 102.0582).abs() < 1e-4);
+        assert!((WeightUnit::Lb.convert_kg(kg) - 225.0).abs() < 1e-9);
+        assert_eq!(WeightUnit::Kg.to_kg(0.0), 0.0);
+        assert_eq!(WeightUnit::Lb.convert_kg(0.0), 0.0);
+    }
+
+    #[test]
+    fn display_echoes_the_entered_value_in_its_own_unit() {
+        let kg = WeightUnit::Lb.to_kg(225.0);
+        assert_eq!(display(kg, Some(225.0), Some("lb"), WeightUnit::Lb), 225.0);
+        assert_eq!(display(kg, Some(225.0), Some("lb"), WeightUnit::Kg), 102.06);
+        assert_eq!(display(100.0, None, None, WeightUnit::Lb), 220.46);
+    }
+
+    #[test]
+    fn resolve_prefers_the_explicit_value() {
+        let w = EnteredWeight::resolve(Some(135.0), None, Some(50.0), WeightUnit::Lb).unwrap();
+        assert_eq!((w.value, w.unit), (135.0, WeightUnit::Lb));
+
+        let w = EnteredWeight::resolve(None, Some(WeightUnit::Lb), Some(50.0), WeightUnit::Lb).unwrap();
+        assert_eq!((w.value, w.unit), (50.0, WeightUnit::Kg));
+        assert_eq!(w.kg(), 50.0);
+
+        assert!(EnteredWeight::resolve(None, None, None, WeightUnit::Kg).is_none());
+    }
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WarmupStep {
+    pub percent: f64, // Of the working weight, 0 = empty bar
+    pub reps: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarmupScheme {
+    #[default]
+    Standard, // Empty bar, then 40/60/80%
+    Strength, // Longer ramp ending in heavy singles, for near-max work
+    Quick,    // Two jumps when short on time
+}
+
+impl WarmupScheme {
+    pub fn steps(self) -> Vec<WarmupStep> {
+        let steps: &[(f64, i32)] = match self {
+            WarmupScheme::Standard => &[(0.0, 10), (0.4, 5), (0.6, 3), (0.8, 2)],
+            WarmupScheme::Strength => &[(0.0, 10), (0.4, 5), (0.55, 3), (0.7, 2), (0.8, 1), (0.9, 1)],
+            WarmupScheme::Quick => &[(0.5, 5), (0.75, 2)],
+        };
+        steps.iter().map(|&(percent, reps)| WarmupStep { percent, reps }).collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct WarmupSet {
+    pub weight: f64,
+    pub reps: i32,
+    pub percent: f64,
+}
+
+/// Build the warm-up ramp for `working_weight`. `round` maps a raw weight to
+/// one that can actually be loaded and `min_weight` is the lightest possible
+/// load (the empty bar). Steps that land on the same weight as the previous
+/// one, or reach the working weight, are dropped.
+pub fn generate(
+    working_weight: f64,
+    min_weight: f64,
+    steps: &[WarmupStep],
+    round: impl Fn(f64) -> f64,
+) -> Vec<WarmupSet> {
+    let mut sets: Vec<WarmupSet> = Vec::new();
+
+    for step in steps {
+        if step.percent <= 0.0 && min_weight <= 0.0 {
+            continue; // No empty bar to warm up with
+        }
+
+        let weight = round(working_weight * step.percent).max(min_weight);
+        if weight >= working_weight {
+            break;
+        }
+        if sets.last().is_some_and(|prev| prev.weight >= weight) {
+            continue;
+        }
+
+        sets.push(WarmupSet { weight, reps: step.reps, percent: step.percent });
+    }
+
+    sets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nearest_2_5(weight: f64) -> f64 {
+        (weight / 2.5).round() * 2.5
+    }
+
+    fn ramp(working_weight: f64, min_weight: f64, scheme: WarmupScheme) -> Vec<(f64, i32)> {
+        generate(working_weight, min_weight, &scheme.steps(), nearest_2_5)
+            .iter()
+            .map(|set| (set.weight, set.reps))
+            .collect()
+    }
+
+    #[test]
+    fn standard_ramp_starts_with_the_empty_bar() {
+        assert_eq!(ramp(100.0, 20.0, WarmupScheme::Standard), vec![(20.0, 10), (40.0, 5), (60.0, 3), (80.0, 2)]);
+    }
+
+    #[test]
+    fn light_work_drops_repeated_steps() {
+        // 40% and 60% of 30 both round up to the bar
+        assert_eq!(ramp(30.0, 20.0, WarmupScheme::Standard), vec![(20.0, 10), (25.0, 2)]);
+    }
+
+    #[test]
+    fn nothing_to_warm_up_at_or_below_the_bar() {
+        for working_weight in [20.0, 0.0, -10.0] {
+            assert!(ramp(working_weight, 20.0, WarmupScheme::Standard).is_empty());
+        }
+    }
+
+    #[test]
+    fn no_bar_skips_the_empty_bar_step() {
+        assert_eq!(ramp(100.0, 0.0, WarmupScheme::Standard), vec![(40.0, 5), (60.0, 3), (80.0, 2)]);
+    }
+}
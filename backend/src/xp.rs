@@ -0,0 +1,158 @@
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::badges::WorkoutFacts;
+
+pub const XP_PER_SET: i64 = 10; // Working sets only
+pub const XP_PER_WORKOUT: i64 = 100;
+pub const XP_PER_PR: i64 = 50;
+pub const XP_PER_STREAK_DAY: i64 = 25; // For every consecutive day after the first
+pub const MAX_STREAK_XP: i64 = 150;
+
+const LEVEL_XP_BASE: i64 = 250; // Level n starts at LEVEL_XP_BASE * (n - 1)^2
+
+#[derive(Debug, Default, Serialize)]
+pub struct XpBreakdown {
+    pub sets: i64,
+    pub workout: i64,
+    pub prs: i64,
+    pub streak: i64,
+    pub total: i64,
+}
+
+/// XP granted for finishing the workout described by `facts`.
+pub fn for_workout(facts: &WorkoutFacts) -> XpBreakdown {
+    let sets = facts.set_count * XP_PER_SET;
+    let workout = XP_PER_WORKOUT;
+    let prs = facts.pr_count * XP_PER_PR;
+    let streak = ((facts.streak_days - 1).max(0) * XP_PER_STREAK_DAY).min(MAX_STREAK_XP);
+
+    XpBreakdown { sets, workout, prs, streak, total: sets + workout + prs + streak }
+}
+
+/// Total XP needed to reach `level` (level 1 starts at 0).
+pub fn xp_for_level(level: i32) -> i64 {
+    let n = (level.max(1) - 1) as i64;
+    LEVEL_XP_BASE * n * n
+}
+
+#[derive(Debug, Serialize)]
+pub struct LevelProgress {
+    pub level: i32,
+    pub xp: i64,
+    pub level_xp: i64, // XP where the current level started
+    pub next_level_xp: i64,
+    pub percent: f64,  // Progress through the current level, 0-100
+}
+
+impl LevelProgress {
+    pub fn new(xp: i64) -> Self {
+        let xp = xp.max(0);
+        let level = (xp as f64 / LEVEL_XP_BASE as f64).sqrt() as i32 + 1;
+        let level_xp = xp_for_level(level);
+        let next_level_xp = xp_for_level(level + 1);
+
+        LevelProgress {
+            level,
+            xp,
+            level_xp,
+            next_level_xp,
+            percent: (xp - level_xp) as f64 / (next_level_xp - level_xp) as f64 * 100.0,
+        }
+    }
+}
+
+/// Store the XP a workout granted and refresh the owner's total. Returns the new total.
+pub async fn record_workout_xp(conn: &mut PgConnection, workout_id: Uuid, user_id: Uuid, xp: &XpBreakdown) -> Result<i64, sqlx::Error> {
+    sqlx::query("UPDATE workouts SET xp_earned = $2 WHERE id = $1")
+        .bind(workout_id)
+        .bind(xp.total as i32)
+        .execute(&mut *conn)
+        .await?;
+
+    refresh_user_xp(&mut *conn, user_id).await
+}
+
+/// Recalculate a user's total from their finished workouts. Returns the new total.
+pub async fn refresh_user_xp<'e, E>(db: E, user_id: Uuid) -> Result<i64, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        UPDATE users
+        SET xp = (SELECT COALESCE(SUM(xp_earned), 0) FROM workouts WHERE user_id = $1 AND end_time IS NOT NULL)
+        WHERE id = $1
+        RETURNING xp
+        "#
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn user_xp(db: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT xp FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .map(|xp| xp.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Utc;
+
+    use super::*;
+
+    fn facts(set_count: i64, pr_count: i64, streak_days: i64) -> WorkoutFacts {
+        WorkoutFacts {
+            user_id: Uuid::nil(),
+            started_at: None,
+            ended_at: Utc::now(),
+            duration_minutes: 60.0,
+            volume_kg: 0.0,
+            set_count,
+            pr_count,
+            streak_days,
+            bodyweight_kg: None,
+            best_lifts: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn workout_xp_adds_up() {
+        let xp = for_workout(&facts(12, 2, 3));
+        assert_eq!((xp.sets, xp.workout, xp.prs, xp.streak), (120, 100, 100, 50));
+        assert_eq!(xp.total, 370);
+    }
+
+    #[test]
+    fn streak_xp_starts_on_the_second_day_and_is_capped() {
+        assert_eq!(for_workout(&facts(0, 0, 0)).streak, 0);
+        assert_eq!(for_workout(&facts(0, 0, 1)).streak, 0);
+        assert_eq!(for_workout(&facts(0, 0, 100)).streak, MAX_STREAK_XP);
+    }
+
+    #[test]
+    fn levels_start_on_their_threshold() {
+        assert_eq!(xp_for_level(0), 0);
+        assert_eq!(xp_for_level(1), 0);
+        assert_eq!(xp_for_level(3), 1000);
+
+        assert_eq!(LevelProgress::new(249).level, 1);
+        let progress = LevelProgress::new(250);
+        assert_eq!((progress.level, progress.level_xp, progress.next_level_xp), (2, 250, 1000));
+        assert_eq!(progress.percent, 0.0);
+        assert_eq!(LevelProgress::new(1000).level, 3);
+    }
+
+    #[test]
+    fn negative_xp_counts_as_none() {
+        let progress = LevelProgress::new(-50);
+        assert_eq!((progress.level, progress.xp, progress.percent), (1, 0, 0.0));
+    }
+}
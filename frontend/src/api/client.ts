@@ -1,4 +1,4 @@
-import type { Exercise, CreateWorkoutRequest, Workout, LogSetRequest, Set, CreateTemplateRequest, WorkoutTemplate, AddTemplateExerciseRequest, TemplateExercise, TemplateWithExercises, PhysicalStats, UpdateStatsRequest, WeightHistoryEntry, NutritionLog, LogNutritionRequest, FinishWorkoutResponse, LogSetResponse, UserBadge } from "./types";
+import type { Exercise, CreateWorkoutRequest, Workout, LogSetRequest, Set, CreateTemplateRequest, WorkoutTemplate, AddTemplateExerciseRequest, TemplateExercise, TemplateWithExercises, PhysicalStats, UpdateStatsRequest, WeightHistoryEntry, NutritionLog, LogNutritionRequest, FinishWorkoutResponse, LogSetResponse, UserBadge, BadgeGroup, FeedItem, FeedPage, CommentNode, PrivacySettings, RestrictedUser, NotificationsPage, UserSearchPage, UserSuggestion, GroupSummary, GroupDetails, GroupRole, CoachingLink, ShareLink, WorkoutCard, WorkoutComparison, LiveToken } from "./types";
 
 const API_BASE = "/api";
 
@@ -152,6 +152,12 @@ export async function fetchUserBadges(userId: string): Promise<UserBadge[]> {
     return res.json();
 }
 
+export async function fetchTrophies(userId: string): Promise<BadgeGroup[]> {
+    const res = await fetch(`${API_BASE}/profile/${userId}/trophies`);
+    if (!res.ok) throw new Error("Failed to fetch trophies");
+    return res.json();
+}
+
 export const getSocialProfile = async (targetId: string): Promise<any> => {
     const response = await fetch(`${API_BASE}/social/profile/${targetId}`);
     if (!response.ok) throw new Error('Failed to fetch social profile');
@@ -168,12 +174,18 @@ export const updateSocialProfile = async (data: { user_id: string, bio?: string,
     return response;
 };
 
-export const searchUsers = async (query: string) => {
-    const response = await fetch(`${API_BASE}/social/search?q=${encodeURIComponent(query)}`);
+export const searchUsers = async (query: string, limit = 20, offset = 0): Promise<UserSearchPage> => {
+    const response = await fetch(`${API_BASE}/social/search?q=${encodeURIComponent(query)}&limit=${limit}&offset=${offset}`);
     if (!response.ok) throw new Error('Failed to search users');
     return response.json();
 };
 
+export const fetchSuggestedUsers = async (limit = 10): Promise<UserSuggestion[]> => {
+    const response = await fetch(`${API_BASE}/social/suggestions?limit=${limit}`);
+    if (!response.ok) throw new Error('Failed to fetch suggestions');
+    return response.json();
+};
+
 export const followUser = async (targetId: string) => {
     const response = await fetch(`${API_BASE}/social/follow/${targetId}`, { method: 'POST' });
     if (!response.ok) throw new Error('Failed to follow user');
@@ -185,3 +197,341 @@ export const unfollowUser = async (targetId: string) => {
     if (!response.ok) throw new Error('Failed to unfollow user');
     return response;
 };
+
+export const fetchFeed = async (limit = 20, offset = 0): Promise<FeedPage> => {
+    const response = await fetch(`${API_BASE}/feed?limit=${limit}&offset=${offset}`);
+    if (!response.ok) throw new Error('Failed to fetch feed');
+    return response.json();
+};
+
+export const createPost = async (data: { content?: string, workout_id?: string, media_url?: string, group_id?: string }): Promise<FeedItem> => {
+    const response = await fetch(`${API_BASE}/posts`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(data),
+    });
+    if (!response.ok) throw new Error('Failed to create post');
+    return response.json();
+};
+
+export const giveKudos = async (postId: string) => {
+    const response = await fetch(`${API_BASE}/posts/${postId}/kudos`, { method: 'POST' });
+    if (!response.ok) throw new Error('Failed to give kudos');
+    return response.json();
+};
+
+export const removeKudos = async (postId: string) => {
+    const response = await fetch(`${API_BASE}/posts/${postId}/kudos`, { method: 'DELETE' });
+    if (!response.ok) throw new Error('Failed to remove kudos');
+    return response.json();
+};
+
+export const fetchPostComments = async (postId: string): Promise<{ comments: CommentNode[], comment_count: number }> => {
+    const response = await fetch(`${API_BASE}/posts/${postId}/comments`);
+    if (!response.ok) throw new Error('Failed to fetch comments');
+    return response.json();
+};
+
+export const addPostComment = async (postId: string, body: string, parentId?: string) => {
+    const response = await fetch(`${API_BASE}/posts/${postId}/comments`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ body, parent_id: parentId }),
+    });
+    if (!response.ok) throw new Error('Failed to add comment');
+    return response.json();
+};
+
+export const fetchSetComments = async (setId: string): Promise<{ comments: CommentNode[], comment_count: number }> => {
+    const response = await fetch(`${API_BASE}/sets/${setId}/comments`);
+    if (!response.ok) throw new Error('Failed to fetch comments');
+    return response.json();
+};
+
+export const addSetComment = async (setId: string, body: string, parentId?: string) => {
+    const response = await fetch(`${API_BASE}/sets/${setId}/comments`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ body, parent_id: parentId }),
+    });
+    if (!response.ok) throw new Error('Failed to add comment');
+    return response.json();
+};
+
+export const fetchFollowers = async (userId: string, limit = 20, offset = 0) => {
+    const response = await fetch(`${API_BASE}/social/${userId}/followers?limit=${limit}&offset=${offset}`);
+    if (!response.ok) throw new Error('Failed to fetch followers');
+    return response.json();
+};
+
+export const fetchFollowing = async (userId: string, limit = 20, offset = 0) => {
+    const response = await fetch(`${API_BASE}/social/${userId}/following?limit=${limit}&offset=${offset}`);
+    if (!response.ok) throw new Error('Failed to fetch following');
+    return response.json();
+};
+
+export const fetchFollowRequests = async () => {
+    const response = await fetch(`${API_BASE}/social/requests`);
+    if (!response.ok) throw new Error('Failed to fetch follow requests');
+    return response.json();
+};
+
+export const respondToFollowRequest = async (followerId: string, accept: boolean) => {
+    const response = await fetch(`${API_BASE}/social/requests/${followerId}/${accept ? 'accept' : 'reject'}`, { method: 'POST' });
+    if (!response.ok) throw new Error('Failed to respond to follow request');
+    return response;
+};
+
+export const updatePrivacy = async (isPrivate: boolean) => {
+    const response = await fetch(`${API_BASE}/social/privacy`, {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ is_private: isPrivate }),
+    });
+    if (!response.ok) throw new Error('Failed to update privacy');
+    return response;
+};
+
+export const fetchPrivacySettings = async (): Promise<PrivacySettings> => {
+    const response = await fetch(`${API_BASE}/privacy`);
+    if (!response.ok) throw new Error('Failed to fetch privacy settings');
+    return response.json();
+};
+
+export const updatePrivacySettings = async (data: Partial<PrivacySettings>): Promise<PrivacySettings> => {
+    const response = await fetch(`${API_BASE}/privacy`, {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(data),
+    });
+    if (!response.ok) throw new Error('Failed to update privacy settings');
+    return response.json();
+};
+
+export const blockUser = async (targetId: string) => {
+    const response = await fetch(`${API_BASE}/social/block/${targetId}`, { method: 'POST' });
+    if (!response.ok) throw new Error('Failed to block user');
+    return response;
+};
+
+export const unblockUser = async (targetId: string) => {
+    const response = await fetch(`${API_BASE}/social/block/${targetId}`, { method: 'DELETE' });
+    if (!response.ok) throw new Error('Failed to unblock user');
+    return response;
+};
+
+export const fetchBlockedUsers = async (): Promise<RestrictedUser[]> => {
+    const response = await fetch(`${API_BASE}/social/blocked`);
+    if (!response.ok) throw new Error('Failed to fetch blocked users');
+    return response.json();
+};
+
+export const muteUser = async (targetId: string) => {
+    const response = await fetch(`${API_BASE}/social/mute/${targetId}`, { method: 'POST' });
+    if (!response.ok) throw new Error('Failed to mute user');
+    return response;
+};
+
+export const unmuteUser = async (targetId: string) => {
+    const response = await fetch(`${API_BASE}/social/mute/${targetId}`, { method: 'DELETE' });
+    if (!response.ok) throw new Error('Failed to unmute user');
+    return response;
+};
+
+export const fetchMutedUsers = async (): Promise<RestrictedUser[]> => {
+    const response = await fetch(`${API_BASE}/social/muted`);
+    if (!response.ok) throw new Error('Failed to fetch muted users');
+    return response.json();
+};
+
+export const fetchNotifications = async (limit = 20, offset = 0, unreadOnly = false): Promise<NotificationsPage> => {
+    const response = await fetch(`${API_BASE}/notifications?limit=${limit}&offset=${offset}&unread_only=${unreadOnly}`);
+    if (!response.ok) throw new Error('Failed to fetch notifications');
+    return response.json();
+};
+
+// Marks everything read when no ids are given
+export const markNotificationsRead = async (ids?: string[]): Promise<{ unread_count: number }> => {
+    const response = await fetch(`${API_BASE}/notifications/read`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ ids }),
+    });
+    if (!response.ok) throw new Error('Failed to mark notifications read');
+    return response.json();
+};
+
+export const fetchGroups = async (): Promise<GroupSummary[]> => {
+    const response = await fetch(`${API_BASE}/groups`);
+    if (!response.ok) throw new Error('Failed to fetch groups');
+    return response.json();
+};
+
+export const createGroup = async (data: { name: string, description?: string }): Promise<GroupDetails> => {
+    const response = await fetch(`${API_BASE}/groups`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(data),
+    });
+    if (!response.ok) throw new Error('Failed to create group');
+    return response.json();
+};
+
+export const fetchGroup = async (groupId: string): Promise<GroupDetails> => {
+    const response = await fetch(`${API_BASE}/groups/${groupId}`);
+    if (!response.ok) throw new Error('Failed to fetch group');
+    return response.json();
+};
+
+export const inviteToGroup = async (groupId: string, userIds: string[], role?: GroupRole): Promise<GroupDetails> => {
+    const response = await fetch(`${API_BASE}/groups/${groupId}/invites`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ user_ids: userIds, role }),
+    });
+    if (!response.ok) throw new Error('Failed to invite to group');
+    return response.json();
+};
+
+export const joinGroup = async (groupId: string): Promise<GroupDetails> => {
+    const response = await fetch(`${API_BASE}/groups/${groupId}/join`, { method: 'POST' });
+    if (!response.ok) throw new Error('Failed to join group');
+    return response.json();
+};
+
+export const leaveGroup = async (groupId: string) => {
+    const response = await fetch(`${API_BASE}/groups/${groupId}/leave`, { method: 'POST' });
+    if (!response.ok) throw new Error('Failed to leave group');
+    return response;
+};
+
+export const updateGroupMemberRole = async (groupId: string, userId: string, role: GroupRole): Promise<GroupDetails> => {
+    const response = await fetch(`${API_BASE}/groups/${groupId}/members/${userId}`, {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ role }),
+    });
+    if (!response.ok) throw new Error('Failed to update role');
+    return response.json();
+};
+
+export const removeGroupMember = async (groupId: string, userId: string): Promise<GroupDetails> => {
+    const response = await fetch(`${API_BASE}/groups/${groupId}/members/${userId}`, { method: 'DELETE' });
+    if (!response.ok) throw new Error('Failed to remove member');
+    return response.json();
+};
+
+export const fetchGroupFeed = async (groupId: string, limit = 20, offset = 0): Promise<FeedPage> => {
+    const response = await fetch(`${API_BASE}/groups/${groupId}/feed?limit=${limit}&offset=${offset}`);
+    if (!response.ok) throw new Error('Failed to fetch group feed');
+    return response.json();
+};
+
+export const fetchCoaches = async (): Promise<CoachingLink[]> => {
+    const response = await fetch(`${API_BASE}/coaching/coaches`);
+    if (!response.ok) throw new Error('Failed to fetch coaches');
+    return response.json();
+};
+
+export const grantCoach = async (coachId: string, access: { can_view_workouts?: boolean, can_edit_templates?: boolean } = {}): Promise<CoachingLink[]> => {
+    const response = await fetch(`${API_BASE}/coaching/coaches/${coachId}`, {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(access),
+    });
+    if (!response.ok) throw new Error('Failed to add coach');
+    return response.json();
+};
+
+export const removeCoach = async (coachId: string) => {
+    const response = await fetch(`${API_BASE}/coaching/coaches/${coachId}`, { method: 'DELETE' });
+    if (!response.ok) throw new Error('Failed to remove coach');
+    return response;
+};
+
+export const fetchAthletes = async (): Promise<CoachingLink[]> => {
+    const response = await fetch(`${API_BASE}/coaching/athletes`);
+    if (!response.ok) throw new Error('Failed to fetch athletes');
+    return response.json();
+};
+
+export const removeAthlete = async (athleteId: string) => {
+    const response = await fetch(`${API_BASE}/coaching/athletes/${athleteId}`, { method: 'DELETE' });
+    if (!response.ok) throw new Error('Failed to remove athlete');
+    return response;
+};
+
+export const fetchAthleteWorkouts = async (athleteId: string, limit = 20, offset = 0): Promise<Workout[]> => {
+    const response = await fetch(`${API_BASE}/coaching/athletes/${athleteId}/workouts?limit=${limit}&offset=${offset}`);
+    if (!response.ok) throw new Error('Failed to fetch athlete workouts');
+    return response.json();
+};
+
+export const fetchAthleteWorkoutSets = async (athleteId: string, workoutId: string): Promise<Set[]> => {
+    const response = await fetch(`${API_BASE}/coaching/athletes/${athleteId}/workouts/${workoutId}/sets`);
+    if (!response.ok) throw new Error('Failed to fetch athlete sets');
+    return response.json();
+};
+
+export const assignTemplate = async (athleteId: string, templateId: string): Promise<WorkoutTemplate> => {
+    const response = await fetch(`${API_BASE}/coaching/athletes/${athleteId}/templates`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ template_id: templateId }),
+    });
+    if (!response.ok) throw new Error('Failed to assign template');
+    return response.json();
+};
+
+export const shareWorkout = async (workoutId: string): Promise<ShareLink> => {
+    const response = await fetch(`${API_BASE}/workouts/${workoutId}/share`, { method: 'POST' });
+    if (!response.ok) throw new Error('Failed to share workout');
+    return response.json();
+};
+
+export const fetchWorkoutShare = async (workoutId: string): Promise<ShareLink | null> => {
+    const response = await fetch(`${API_BASE}/workouts/${workoutId}/share`);
+    if (response.status === 404) return null;
+    if (!response.ok) throw new Error('Failed to fetch share link');
+    return response.json();
+};
+
+export const revokeWorkoutShare = async (workoutId: string) => {
+    const response = await fetch(`${API_BASE}/workouts/${workoutId}/share`, { method: 'DELETE' });
+    if (!response.ok) throw new Error('Failed to revoke share link');
+    return response;
+};
+
+export const fetchSharedWorkout = async (token: string): Promise<WorkoutCard> => {
+    const response = await fetch(`${API_BASE}/shared/${token}`);
+    if (!response.ok) throw new Error('Failed to fetch shared workout');
+    return response.json();
+};
+
+// Summary image to post on Instagram or Twitter
+export const fetchWorkoutCardImage = async (workoutId: string): Promise<Blob> => {
+    const response = await fetch(`${API_BASE}/workouts/${workoutId}/card.png`);
+    if (!response.ok) throw new Error('Failed to render workout card');
+    return response.blob();
+};
+
+// Without `b`, compares with the previous workout from the same template
+export const compareWorkouts = async (a: string, b?: string): Promise<WorkoutComparison> => {
+    const params = new URLSearchParams({ a });
+    if (b) params.set('b', b);
+    const response = await fetch(`${API_BASE}/workouts/compare?${params}`);
+    if (!response.ok) throw new Error('Failed to compare workouts');
+    return response.json();
+};
+
+// Live events: fetch a token, then open an EventSource on liveEventsUrl(token).
+// On a `resync` event refetch whatever is on screen; if the connection fails,
+// fetch a new token before reconnecting.
+export const fetchLiveToken = async (): Promise<LiveToken> => {
+    const response = await fetch(`${API_BASE}/events/token`, { method: 'POST' });
+    if (!response.ok) throw new Error('Failed to get live token');
+    return response.json();
+};
+
+export const liveEventsUrl = (token: string): string =>
+    `${API_BASE}/events?token=${encodeURIComponent(token)}`;
@@ -61,6 +61,7 @@ export interface WorkoutTemplate {
     name: string;
     description: string | null;
     created_at: string;
+    assigned_by: string | null; // The coach who assigned it
 }
 
 export interface TemplateExercise {
@@ -137,4 +138,280 @@ export interface UserBadge {
     workout_id?: string;
     badge_name: string;
     earned_at: string;
+    source: "rule" | "challenge";
+}
+
+export type BadgeTier = "bronze" | "silver" | "gold";
+
+export interface BadgeGroup {
+    name: string;
+    description?: string;
+    count: number;
+    first_earned_at?: string;
+    last_earned_at?: string;
+    tier?: BadgeTier;
+    next_tier?: {
+        tier: BadgeTier;
+        required: number;
+        remaining: number;
+        percent: number;
+    };
+}
+
+export interface WorkoutSummary {
+    id: string;
+    name?: string;
+    start_time?: string;
+    end_time?: string;
+    duration_minutes?: number;
+    total_volume_kg: number;
+    set_count: number;
+    exercise_count: number;
+    pr_count: number;
+    calories_burned?: number;
+}
+
+export interface FeedItem {
+    id: string;
+    user_id: string;
+    username: string;
+    kind: "manual" | "workout" | "pr";
+    content?: string;
+    media_url?: string;
+    workout_id?: string;
+    set_id?: string;
+    group_id?: string;
+    created_at: string;
+    kudos_count: number;
+    comment_count: number;
+    has_kudos: boolean;
+    workout?: WorkoutSummary;
+}
+
+export interface CommentNode {
+    id: string;
+    user_id: string;
+    username: string;
+    parent_id?: string;
+    body: string;
+    created_at: string;
+    edited_at?: string;
+    deleted: boolean;
+    replies: CommentNode[];
+}
+
+export interface FeedPage {
+    items: FeedItem[];
+    limit: number;
+    offset: number;
+    has_more: boolean;
+}
+
+export type Visibility = 'public' | 'followers' | 'only_me';
+
+export interface PrivacySettings {
+    activity: Visibility;
+    workout_history: Visibility;
+    lifts: Visibility;
+    body_weight: Visibility;
+    body_metrics: Visibility;
+    date_of_birth: Visibility;
+    nutrition: Visibility;
+}
+
+// A blocked or muted user
+export interface RestrictedUser {
+    id: string;
+    username: string;
+    since: string;
+}
+
+export type NotificationKind = 'follow' | 'follow_request' | 'follow_accepted' | 'badge' | 'personal_record' | 'kudos' | 'comment' | 'rank_beaten' | 'group_invite';
+
+export interface Notification {
+    id: string;
+    user_id: string;
+    kind: NotificationKind;
+    actor_id?: string;
+    actor_username?: string;
+    data: Record<string, unknown>; // Depends on the kind
+    read_at?: string;
+    created_at: string;
+}
+
+export interface NotificationsPage {
+    items: Notification[];
+    unread_count: number;
+    limit: number;
+    offset: number;
+    has_more: boolean;
+}
+
+export interface UserSearchResult {
+    id: string;
+    username: string;
+    bio?: string;
+    is_following: boolean;
+    follows_you: boolean;
+}
+
+export interface UserSearchPage {
+    users: UserSearchResult[];
+    limit: number;
+    offset: number;
+    has_more: boolean;
+}
+
+export interface UserSuggestion {
+    id: string;
+    username: string;
+    bio?: string;
+    mutual_follows: number;
+    shared_templates: number;
+}
+
+export type GroupRole = 'owner' | 'coach' | 'member';
+
+export interface Group {
+    id: string;
+    owner_id: string;
+    name: string;
+    description?: string;
+    created_at: string;
+}
+
+export interface GroupSummary extends Group {
+    my_role: GroupRole;
+    my_status: 'invited' | 'joined';
+    member_count: number;
+}
+
+export interface GroupMember {
+    user_id: string;
+    username: string;
+    role: GroupRole;
+    status: 'invited' | 'joined';
+    joined_at?: string;
+}
+
+export interface GroupDetails extends Group {
+    my_role: GroupRole;
+    my_status: 'invited' | 'joined';
+    members: GroupMember[];
+}
+
+export interface CoachingLink {
+    user_id: string; // The coach or the athlete, depending on the list
+    username: string;
+    can_view_workouts: boolean;
+    can_edit_templates: boolean;
+    since: string;
+}
+
+export interface ShareLink {
+    token: string;
+    url: string; // Public JSON
+    card_url: string; // Public HTML card
+    image_url: string; // Public PNG card
+    created_at: string;
+}
+
+export interface SharedTopSet {
+    weight: number;
+    reps: number;
+    pr_kind?: '1rm' | 'volume';
+}
+
+export interface SharedPr {
+    exercise_name: string;
+    weight: number;
+    reps: number;
+    kind: '1rm' | 'volume';
+}
+
+export interface SharedExercise {
+    exercise_id: string;
+    name: string;
+    set_count: number;
+    top_set?: SharedTopSet;
+    volume: number;
+}
+
+export interface WorkoutCard {
+    workout_id: string;
+    username: string;
+    instagram_handle?: string;
+    twitter_handle?: string;
+    name?: string;
+    start_time: string;
+    end_time: string;
+    duration_minutes: number;
+    unit: 'kg' | 'lb';
+    total_volume: number;
+    pr_count: number;
+    calories_burned?: number;
+    exercises: SharedExercise[];
+    prs: SharedPr[];
+    badges: string[];
+}
+
+export interface ComparedWorkout {
+    id: string;
+    user_id: string;
+    name?: string;
+    template_id?: string;
+    start_time: string;
+    end_time?: string;
+}
+
+export interface CompareSetLine {
+    weight: number;
+    reps: number;
+    rpe?: number;
+}
+
+export interface CompareExerciseStats {
+    sets: CompareSetLine[];
+    set_count: number;
+    total_reps: number;
+    best_set?: CompareSetLine; // Heaviest, then most reps
+    volume: number;
+    e1rm?: number;
+}
+
+// a minus b
+export interface CompareExerciseDelta {
+    set_count: number;
+    total_reps: number;
+    best_weight?: number;
+    best_reps?: number;
+    volume: number;
+    e1rm?: number;
+}
+
+export interface ExerciseComparison {
+    exercise_id: string;
+    name: string;
+    a?: CompareExerciseStats;
+    b?: CompareExerciseStats;
+    delta?: CompareExerciseDelta; // Only when both workouts have the exercise
+}
+
+export interface WorkoutComparison {
+    a: ComparedWorkout;
+    b: ComparedWorkout;
+    unit: 'kg' | 'lb';
+    exercises: ExerciseComparison[];
+    totals: {
+        volume_a: number;
+        volume_b: number;
+        volume: number;
+        set_count: number;
+        total_reps: number;
+    };
+}
+
+export interface LiveToken {
+    token: string;
+    expires_at: string; // Connect before then; fetch a new token to reconnect after
 }
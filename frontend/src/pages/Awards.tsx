@@ -1,8 +1,8 @@
 import { useQuery } from '@tanstack/react-query';
-import { fetchAllSets, fetchUserBadges } from '../api/client';
+import { fetchAllSets, fetchTrophies } from '../api/client';
+import type { BadgeTier } from '../api/types';
 import { Car, Truck, Trophy, Rocket, Component, Medal, Star, Award } from 'lucide-react';
 import { cn } from '../lib/utils';
-// UserBadge type used implicitly by fetchUserBadges return type
 
 // Hardcoded user ID for demo
 const DEMO_USER_ID = "763b9c95-4bae-4044-9d30-7ae513286b37";
@@ -23,11 +23,17 @@ const BADGE_META: Record<string, { description: string, icon: any, color: string
     "Volume Warrior": { description: "20+ sets completed", icon: Award, color: "text-purple-400" },
 };
 
+const TIER_STYLE: Record<BadgeTier, string> = {
+    bronze: "bg-amber-700",
+    silver: "bg-slate-400",
+    gold: "bg-yellow-500",
+};
+
 export function Awards() {
     const { data: sets } = useQuery({ queryKey: ['sets'], queryFn: fetchAllSets });
-    const { data: badges } = useQuery({
-        queryKey: ['badges', DEMO_USER_ID],
-        queryFn: () => fetchUserBadges(DEMO_USER_ID)
+    const { data: trophies } = useQuery({
+        queryKey: ['trophies', DEMO_USER_ID],
+        queryFn: () => fetchTrophies(DEMO_USER_ID)
     });
 
     if (!sets) return <div className="p-8">Loading awards...</div>;
@@ -36,11 +42,7 @@ export function Awards() {
     const nextMilestone = MILESTONES.find(m => m.volume > totalVolume) || MILESTONES[MILESTONES.length - 1];
     const progressToNext = nextMilestone ? (totalVolume / nextMilestone.volume) * 100 : 100;
 
-    // Group badges by name to show count
-    const badgeCounts = (badges || []).reduce((acc, badge) => {
-        acc[badge.badge_name] = (acc[badge.badge_name] || 0) + 1;
-        return acc;
-    }, {} as Record<string, number>);
+    const earned = (trophies || []).filter(t => t.count > 0);
 
     return (
         <div className="space-y-12 max-w-5xl mx-auto pb-20">
@@ -74,27 +76,37 @@ export function Awards() {
                     <h3 className="text-2xl font-bold">Trophy Case</h3>
                 </div>
 
-                {(!badges || badges.length === 0) ? (
+                {earned.length === 0 ? (
                     <div className="text-center p-12 border-2 border-dashed border-border rounded-2xl bg-muted/20">
                         <Trophy className="w-12 h-12 mx-auto text-muted-foreground/30 mb-3" />
                         <p className="text-muted-foreground">Complete workouts to earn badges!</p>
                     </div>
                 ) : (
                     <div className="grid grid-cols-2 sm:grid-cols-3 md:grid-cols-4 lg:grid-cols-5 gap-4">
-                        {Object.entries(badgeCounts).map(([name, count]) => {
-                            const meta = BADGE_META[name] || { description: "Awesome achievement", icon: Medal, color: "text-gray-400" };
+                        {earned.map((trophy) => {
+                            const meta = BADGE_META[trophy.name] || { description: trophy.description || "Awesome achievement", icon: Medal, color: "text-gray-400" };
                             const Icon = meta.icon;
 
                             return (
-                                <div key={name} className="bg-card border border-border p-4 rounded-xl flex flex-col items-center text-center hover:border-emerald-500/50 transition-colors shadow-sm group relative overflow-hidden">
+                                <div key={trophy.name} className="bg-card border border-border p-4 rounded-xl flex flex-col items-center text-center hover:border-emerald-500/50 transition-colors shadow-sm group relative overflow-hidden">
                                     <div className={cn("p-4 rounded-full bg-muted mb-3 group-hover:scale-110 transition-transform duration-300", meta.color.replace("text-", "bg-") + "/10")}>
                                         <Icon size={32} className={meta.color} />
                                     </div>
-                                    <div className="absolute top-2 right-2 bg-emerald-500 text-white text-[10px] font-bold px-2 py-0.5 rounded-full">
-                                        x{count}
+                                    <div className={cn("absolute top-2 right-2 text-white text-[10px] font-bold px-2 py-0.5 rounded-full", trophy.tier ? TIER_STYLE[trophy.tier] : "bg-emerald-500")}>
+                                        x{trophy.count}
                                     </div>
-                                    <h4 className="font-bold text-sm mb-1">{name}</h4>
+                                    <h4 className="font-bold text-sm mb-1">{trophy.name}</h4>
                                     <p className="text-[10px] text-muted-foreground leading-tight">{meta.description}</p>
+                                    {trophy.next_tier ? (
+                                        <div className="w-full mt-3 space-y-1">
+                                            <div className="w-full h-1.5 bg-muted rounded-full overflow-hidden">
+                                                <div className={cn("h-full", TIER_STYLE[trophy.next_tier.tier])} style={{ width: `${trophy.next_tier.percent}%` }} />
+                                            </div>
+                                            <p className="text-[10px] text-muted-foreground capitalize">{trophy.next_tier.remaining} more for {trophy.next_tier.tier}</p>
+                                        </div>
+                                    ) : (
+                                        <p className="mt-3 text-[10px] font-extrabold uppercase tracking-wider text-yellow-500">Gold</p>
+                                    )}
                                 </div>
                             );
                         })}
@@ -4,11 +4,17 @@ import { cn } from '../lib/utils';
 import { useState } from 'react';
 
 interface LeaderboardEntry {
+    id: string;
     username: string;
-    total_volume_kg: number;
+    value: number;
     rank: number;
-    period?: string;
-    muscle_group?: string;
+}
+
+interface LeaderboardResponse {
+    total: number;
+    entries: LeaderboardEntry[];
+    me?: LeaderboardEntry;
+    snapshot_at?: string | null;
 }
 
 const PERIODS = [
@@ -28,7 +34,7 @@ const MUSCLE_GROUPS = [
     { value: 'core', label: 'Core' },
 ];
 
-async function fetchLeaderboard(period: string, muscleGroup: string): Promise<LeaderboardEntry[]> {
+async function fetchLeaderboard(period: string, muscleGroup: string): Promise<LeaderboardResponse> {
     const params = new URLSearchParams();
     if (period) params.set('period', period);
     if (muscleGroup) params.set('muscle_group', muscleGroup);
@@ -42,10 +48,11 @@ export function Leaderboard() {
     const [period, setPeriod] = useState('all');
     const [muscleGroup, setMuscleGroup] = useState('');
 
-    const { data: leaderboard, isLoading } = useQuery({
+    const { data, isLoading } = useQuery({
         queryKey: ['leaderboard', period, muscleGroup],
         queryFn: () => fetchLeaderboard(period, muscleGroup)
     });
+    const leaderboard = data?.entries;
 
     return (
         <div className="space-y-8 max-w-4xl mx-auto">
@@ -121,7 +128,7 @@ export function Leaderboard() {
                                 </tr>
                             ) : (
                                 leaderboard?.map((entry) => (
-                                    <tr key={entry.username} className="group hover:bg-muted/50 transition-colors">
+                                    <tr key={entry.id} className="group hover:bg-muted/50 transition-colors">
                                         <td className="px-6 py-4 font-mono font-bold text-lg">
                                             <RankBadge rank={entry.rank} />
                                         </td>
@@ -136,7 +143,7 @@ export function Leaderboard() {
                                             </div>
                                         </td>
                                         <td className="px-6 py-4 text-right font-mono text-emerald-400 font-bold">
-                                            {entry.total_volume_kg?.toLocaleString() || 0}
+                                            {entry.value.toLocaleString()}
                                         </td>
                                     </tr>
                                 ))
@@ -146,9 +153,18 @@ export function Leaderboard() {
                 </div>
             )}
 
+            {data?.me && (
+                <p className="text-center text-sm font-medium">
+                    Your rank: <span className="font-bold">#{data.me.rank}</span> of {data.total}
+                </p>
+            )}
+
             <div className="text-center text-sm text-muted-foreground flex items-center justify-center gap-2">
                 <TrendingUp className="w-4 h-4" />
-                <span>Rankings update in real-time based on logged sets.</span>
+                <span>
+                    Rankings update as sets are logged
+                    {data?.snapshot_at && ` · as of ${new Date(data.snapshot_at).toLocaleTimeString()}`}
+                </span>
             </div>
         </div>
     );
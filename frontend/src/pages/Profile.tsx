@@ -10,14 +10,15 @@ import { UserSearch } from '../components/UserSearch';
 // Hardcoded user ID for demo
 const USER_ID = "763b9c95-4bae-4044-9d30-7ae513286b37";
 
+// Activity fields are null when the owner keeps them private
 interface UserProfile {
     username: string;
-    total_workouts: number;
-    total_volume_kg: number;
+    total_workouts: number | null;
+    total_volume_kg: number | null;
     join_date: string;
-    activity_log: { date: string, volume_kg: number }[];
-    current_streak: number;
-    max_streak: number;
+    activity_log: { date: string, volume_kg: number }[] | null;
+    current_streak: number | null;
+    max_streak: number | null;
 }
 
 async function fetchProfile(userId: string): Promise<UserProfile> {
@@ -366,7 +367,7 @@ export function Profile() {
                             <StatCard
                                 icon={<Trophy className="w-5 h-5 text-yellow-400" />}
                                 label="Workouts"
-                                value={profile?.total_workouts.toString() || "0"}
+                                value={profile?.total_workouts?.toString() || "0"}
                                 subtext="View Awards"
                             />
                         </Link>
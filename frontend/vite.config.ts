@@ -9,6 +9,8 @@ export default defineConfig({
       '/api': {
         target: 'http://127.0.0.1:3000',
         changeOrigin: true,
+        // Dev-only stand-in for auth: act as the seeded demo user (see backend/src/auth.rs)
+        headers: { 'X-User-Id': '763b9c95-4bae-4044-9d30-7ae513286b37' },
       }
     }
   }